@@ -0,0 +1,64 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use rustyknife::behaviour::Intl;
+use rustyknife::rfc5321::{mail_command, mailbox, mailbox_ref, rcpt_command, validate_address};
+
+const ADDRESSES: &[(&str, &str)] = &[
+    ("atom", "bob@example.org"),
+    ("dot_atom", "bob.smith.jr@mail.example.org"),
+    ("quoted", "\"bob \\\"the builder\\\" smith\"@example.org"),
+    ("ipv4", "bob@[192.0.2.1]"),
+    ("ipv6", "bob@[IPv6:2001:db8:85a3::8a2e:370:7334]"),
+    ("utf8", "josé.garcía@correo.ejemplo.es"),
+    ("long_domain", "bob@a.very.long.sub.domain.name.with.many.labels.belonging.to.example.org"),
+];
+
+const MAIL_COMMANDS: &[(&str, &str)] = &[
+    ("null", "MAIL FROM:<>\r\n"),
+    ("plain", "MAIL FROM:<bob@example.org>\r\n"),
+    ("params", "MAIL FROM:<bob@example.org> BODY=8BITMIME SIZE=123456 SMTPUTF8 RET=HDRS ENVID=QQ314159\r\n"),
+    ("source_route", "MAIL FROM:<@a.example,@b.example:bob@example.org>\r\n"),
+];
+
+const RCPT_COMMANDS: &[(&str, &str)] = &[
+    ("postmaster", "RCPT TO:<postmaster>\r\n"),
+    ("plain", "RCPT TO:<bob@example.org>\r\n"),
+    ("params", "RCPT TO:<bob@example.org> NOTIFY=SUCCESS,FAILURE ORCPT=rfc822;bob+2Bsmith@example.org\r\n"),
+    ("quoted", "RCPT TO:<\"bob smith\"@[IPv6:::1]>\r\n"),
+];
+
+fn bench_addresses(c: &mut Criterion) {
+    let mut group = c.benchmark_group("address");
+
+    for (name, input) in ADDRESSES {
+        group.bench_with_input(BenchmarkId::new("validate_address", name), input, |b, i| {
+            b.iter(|| validate_address::<Intl>(black_box(i.as_bytes())))
+        });
+        group.bench_with_input(BenchmarkId::new("mailbox", name), input, |b, i| {
+            b.iter(|| mailbox::<Intl>(black_box(i.as_bytes())))
+        });
+        group.bench_with_input(BenchmarkId::new("mailbox_ref", name), input, |b, i| {
+            b.iter(|| mailbox_ref::<Intl>(black_box(i.as_bytes())))
+        });
+    }
+    group.finish();
+}
+
+fn bench_commands(c: &mut Criterion) {
+    let mut group = c.benchmark_group("command");
+
+    for (name, input) in MAIL_COMMANDS {
+        group.bench_with_input(BenchmarkId::new("mail_command", name), input, |b, i| {
+            b.iter(|| mail_command::<Intl>(black_box(i.as_bytes())))
+        });
+    }
+    for (name, input) in RCPT_COMMANDS {
+        group.bench_with_input(BenchmarkId::new("rcpt_command", name), input, |b, i| {
+            b.iter(|| rcpt_command::<Intl>(black_box(i.as_bytes())))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_addresses, bench_commands);
+criterion_main!(benches);
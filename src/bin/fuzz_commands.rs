@@ -0,0 +1,16 @@
+#[macro_use]
+extern crate afl;
+
+use rustyknife::behaviour::{Intl, Legacy};
+use rustyknife::rfc5321::{mail_command, rcpt_command, validate_address};
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        let _ = mail_command::<Intl>(data);
+        let _ = mail_command::<Legacy>(data);
+        let _ = rcpt_command::<Intl>(data);
+        let _ = rcpt_command::<Legacy>(data);
+        let _ = validate_address::<Intl>(data);
+        let _ = validate_address::<Legacy>(data);
+    });
+}
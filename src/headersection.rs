@@ -6,8 +6,8 @@
 //!
 //! [RFC 5322]: https://tools.ietf.org/html/rfc5322
 
-use std::borrow::Cow;
-use std::str;
+use alloc::borrow::Cow;
+use core::str;
 
 use nom::branch::alt;
 use nom::bytes::streaming::{tag, take_while1, take_until};
@@ -15,6 +15,7 @@ use nom::combinator::{opt, map, map_opt, recognize};
 use nom::multi::{many0, many1};
 use nom::sequence::{pair, terminated, separated_pair};
 
+use crate::prelude::*;
 use crate::util::*;
 
 fn fws(input: &[u8]) -> NomResult<Cow<str>> {
@@ -1,3 +1,4 @@
+#![cfg_attr(not(any(feature="std", test)), no_std)]
 #![cfg_attr(feature="nightly", feature(external_doc))]
 #![cfg_attr(feature="nightly", doc(include = "../README.md"))]
 
@@ -7,6 +8,7 @@
 
 #[macro_use]
 pub extern crate nom;
+extern crate alloc;
 
 /// Types used for varying parser behaviour.
 pub mod behaviour {
@@ -20,16 +22,30 @@ pub mod behaviour {
     pub struct Intl;
 }
 
+// Items of the standard prelude that are provided by alloc, for
+// builds without std.
+mod prelude {
+    pub(crate) use alloc::string::{String, ToString};
+    pub(crate) use alloc::vec::Vec;
+    pub(crate) use alloc::{format, vec};
+}
+
 #[macro_use]
 mod util;
 mod rfc5234;
 pub mod rfc2047;
 pub mod rfc2231;
+#[cfg(feature = "std")]
+pub mod mime;
 pub mod rfc5321;
 pub mod rfc5322;
 pub mod rfc3461;
+pub mod rfc3463;
+pub mod rfc8601;
 pub mod types;
 pub mod headersection;
+pub mod proxy_protocol;
+pub mod xclient;
 pub mod xforward;
 
 #[cfg(feature = "python")]
@@ -38,4 +54,4 @@ mod pymod;
 #[cfg(test)]
 mod tests;
 
-pub use util::NomResult;
+pub use util::{NomError, NomResult};
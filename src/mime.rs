@@ -0,0 +1,100 @@
+//! [MIME] header values
+//!
+//! Entry points for callers that want a typed result instead of the
+//! nom interface of [`rfc2231`](crate::rfc2231).
+//!
+//! [MIME]: https://tools.ietf.org/html/rfc2045
+
+use std::collections::HashMap;
+use core::fmt::{self, Display};
+
+use crate::rfc2231::{_content_type_raw, _duplicate_param, decode_parameter_list};
+use crate::prelude::*;
+use crate::util::*;
+
+/// Value from a MIME `"Content-Type"` header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContentType {
+    /// Top-level media type in lowercase such as `"text"`.
+    pub media_type: String,
+    /// Subtype in lowercase such as `"plain"`.
+    pub subtype: String,
+    /// Decoded parameters keyed by their lowercase name.
+    pub params: HashMap<String, String>,
+}
+
+impl ContentType {
+    /// Look up a parameter by its case-insensitive name.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(&name.to_lowercase()).map(String::as_str)
+    }
+}
+
+impl Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.media_type, self.subtype)
+    }
+}
+
+/// Error returned by [`content_type`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MimeError {
+    /// The value is invalid starting at the given byte offset.
+    Syntax(usize),
+    /// A parameter was given more than once. Holds its lowercase name.
+    ///
+    /// This includes a parameter both split in RFC 2231 sections and
+    /// given as a whole.
+    DuplicateParameter(String),
+}
+
+impl Display for MimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MimeError::Syntax(offset) => write!(f, "invalid syntax at offset {}", offset),
+            MimeError::DuplicateParameter(name) => write!(f, "duplicate parameter {}", name),
+        }
+    }
+}
+
+impl std::error::Error for MimeError {}
+
+/// Parse the value of a `"Content-Type"` header.
+///
+/// The whole input must be consumed, up to an optional CRLF. Quoted
+/// values and RFC 2231 continuations are decoded. Unlike
+/// [`rfc2231::content_type`](crate::rfc2231::content_type), a
+/// parameter given more than once is an error.
+/// # Examples
+/// ```
+/// use rustyknife::mime::{content_type, MimeError};
+///
+/// let ct = content_type(b"Text/Plain; charset=\"utf-8\";\r\n title*0=\"a \"; title*1=b\r\n").unwrap();
+///
+/// assert_eq!(ct.media_type, "text");
+/// assert_eq!(ct.subtype, "plain");
+/// assert_eq!(ct.param("Charset"), Some("utf-8"));
+/// assert_eq!(ct.param("title"), Some("a b"));
+/// assert_eq!(ct.to_string(), "text/plain");
+/// assert_eq!(content_type(b"text/plain; a=1; A=2"), Err(MimeError::DuplicateParameter("a".into())));
+/// assert_eq!(content_type(b"text"), Err(MimeError::Syntax(4)));
+/// ```
+pub fn content_type(input: &[u8]) -> Result<ContentType, MimeError> {
+    let (_, (mt, params)) = exact!(input, _content_type_raw).map_err(|e| match e {
+        nom::Err::Error(e) | nom::Err::Failure(e) => MimeError::Syntax(e.offset(input)),
+        nom::Err::Incomplete(_) => MimeError::Syntax(input.len()),
+    })?;
+
+    if let Some(name) = _duplicate_param(&params) {
+        return Err(MimeError::DuplicateParameter(name));
+    }
+
+    let mt = ascii_to_string(mt).to_lowercase();
+    let (media_type, subtype) = mt.split_once('/').ok_or(MimeError::Syntax(0))?;
+
+    Ok(ContentType {
+        media_type: media_type.into(),
+        subtype: subtype.into(),
+        params: decode_parameter_list(params).into_iter().collect(),
+    })
+}
@@ -0,0 +1,145 @@
+//! HAProxy [PROXY protocol] header parser
+//!
+//! Only the human readable version 1 of the header is supported.
+//!
+//! [PROXY protocol]: https://www.haproxy.org/download/2.0/doc/proxy-protocol.txt
+
+use core::fmt::{self, Display};
+#[cfg(feature = "std")]
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+#[cfg(not(feature = "std"))]
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use core::str::{self, FromStr};
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_until, take_while1, take_while_m_n};
+use nom::character::{is_digit, is_hex_digit};
+use nom::combinator::{map, map_res, verify};
+use nom::multi::many_m_n;
+use nom::sequence::{pair, preceded, terminated, tuple};
+
+use crate::rfc5234::crlf;
+use crate::rfc5321::_ip_int;
+use crate::util::*;
+
+/// Maximum length of a version 1 header including the CRLF.
+pub const MAX_V1_LENGTH: usize = 107;
+
+/// Connection information carried by the PROXY header.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProxyInfo {
+    /// Proxied TCP connection over IPv4 or IPv6.
+    Tcp {
+        /// Address and port of the client.
+        source: SocketAddr,
+        /// Address and port the client connected to.
+        destination: SocketAddr,
+    },
+    /// The proxy did not provide the connection information.
+    ///
+    /// The connection should be treated as coming from the proxy
+    /// itself.
+    Unknown,
+}
+
+/// Error returned by [`parse_proxy_v1`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProxyError {
+    /// The header is not terminated by a CRLF yet.
+    ///
+    /// More input should be read unless [`MAX_V1_LENGTH`] was reached.
+    Incomplete,
+    /// The header is longer than [`MAX_V1_LENGTH`].
+    TooLong,
+    /// The header is malformed.
+    Invalid,
+}
+
+impl Display for ProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProxyError::Incomplete => write!(f, "incomplete PROXY header"),
+            ProxyError::TooLong => write!(f, "PROXY header exceeds {} octets", MAX_V1_LENGTH),
+            ProxyError::Invalid => write!(f, "invalid PROXY header"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ProxyError {}
+
+fn ipv4(input: &[u8]) -> NomResult<IpAddr> {
+    map(pair(_ip_int, many_m_n(3, 3, preceded(tag("."), _ip_int))),
+        |(a, b)| Ipv4Addr::new(a, b[0], b[1], b[2]).into())(input)
+}
+
+fn ipv6(input: &[u8]) -> NomResult<IpAddr> {
+    map_res(map_res(take_while1(|c| is_hex_digit(c) || c == b':' || c == b'.'), str::from_utf8),
+            |addr| Ipv6Addr::from_str(addr).map(IpAddr::from))(input)
+}
+
+// Leading zeros are not allowed.
+fn port(input: &[u8]) -> NomResult<u16> {
+    map_res(map_res(verify(take_while_m_n(1, 5, is_digit), |p: &[u8]| p.len() == 1 || p[0] != b'0'), str::from_utf8),
+            u16::from_str)(input)
+}
+
+fn addresses<'a, F>(proto: &'static str, addr: F) -> impl FnMut(&'a [u8]) -> NomResult<'a, ProxyInfo>
+    where F: Fn(&'a [u8]) -> NomResult<'a, IpAddr> + Copy
+{
+    map(preceded(tag(proto),
+                 tuple((preceded(tag(" "), addr), preceded(tag(" "), addr),
+                        preceded(tag(" "), port), preceded(tag(" "), port)))),
+        |(src, dst, sport, dport)| ProxyInfo::Tcp {
+            source: SocketAddr::new(src, sport),
+            destination: SocketAddr::new(dst, dport),
+        })
+}
+
+// Anything following UNKNOWN must be ignored.
+fn unknown(input: &[u8]) -> NomResult<ProxyInfo> {
+    map(preceded(tag("UNKNOWN"), take_until("\r\n")), |_| ProxyInfo::Unknown)(input)
+}
+
+fn header(input: &[u8]) -> NomResult<ProxyInfo> {
+    terminated(preceded(tag("PROXY "),
+                        alt((addresses("TCP4", ipv4),
+                             addresses("TCP6", ipv6),
+                             unknown))),
+               crlf)(input)
+}
+
+/// Parse a PROXY protocol version 1 header.
+///
+/// `input` must contain the whole header line including the CRLF and
+/// nothing else. [`ProxyError::Incomplete`] is returned if the CRLF
+/// has not been received yet.
+/// # Examples
+/// ```
+/// use std::net::SocketAddr;
+/// use rustyknife::proxy_protocol::{parse_proxy_v1, ProxyInfo};
+///
+/// let info = parse_proxy_v1(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 25\r\n").unwrap();
+///
+/// assert_eq!(info, ProxyInfo::Tcp {
+///     source: "192.0.2.1:56324".parse::<SocketAddr>().unwrap(),
+///     destination: "198.51.100.1:25".parse::<SocketAddr>().unwrap(),
+/// });
+/// ```
+pub fn parse_proxy_v1(input: &[u8]) -> Result<ProxyInfo, ProxyError> {
+    match input.windows(2).position(|w| w == b"\r\n") {
+        Some(end) if end + 2 > MAX_V1_LENGTH => return Err(ProxyError::TooLong),
+        Some(_) => (),
+        None if input.len() >= MAX_V1_LENGTH => return Err(ProxyError::TooLong),
+        None => return if b"PROXY ".starts_with(&input[..input.len().min(6)]) {
+            Err(ProxyError::Incomplete)
+        } else {
+            Err(ProxyError::Invalid)
+        },
+    }
+
+    match header(input) {
+        Ok((b"", info)) => Ok(info),
+        _ => Err(ProxyError::Invalid),
+    }
+}
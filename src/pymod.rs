@@ -5,7 +5,7 @@ use crate::behaviour::{Legacy, Intl};
 use crate::rfc2231::{content_type, content_disposition, content_transfer_encoding};
 use crate::rfc3461::{orcpt_address, dsn_mail_params, DSNMailParams, DSNRet};
 use crate::rfc5321::{Param as ESMTPParam, mail_command, rcpt_command, validate_address, ForwardPath, ReversePath};
-use crate::rfc5322::{Address, Mailbox, Group, from, sender, reply_to, unstructured};
+use crate::rfc5322::{Address, Mailbox, Group, from, sender, reply_to, to, cc, unstructured};
 use crate::headersection::{header_section};
 use crate::xforward::{Param as XFORWARDParam, xforward_params};
 use crate::util::NomResult;
@@ -132,6 +132,18 @@ fn rustyknife(_py: Python, m: &PyModule) -> PyResult<()> {
         convert_result(reply_to::<Intl>(input.as_bytes()), true)
     }
 
+    /// to(input)
+    #[pyfn(m, "to")]
+    fn py_to(input: &PyBytes) -> PyResult<Vec<Address>> {
+        convert_result(to::<Intl>(input.as_bytes()), true)
+    }
+
+    /// cc(input)
+    #[pyfn(m, "cc")]
+    fn py_cc(input: &PyBytes) -> PyResult<Vec<Address>> {
+        convert_result(cc::<Intl>(input.as_bytes()), true)
+    }
+
     /// header_section(input) -> ([headers...], end of headers position)
     ///
     /// :param input: Input string.
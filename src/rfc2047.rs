@@ -3,11 +3,7 @@
 //! [Header extensions for non-ASCII text]: https://tools.ietf.org/html/rfc2047
 
 
-use std::borrow::Cow;
-
-use encoding::DecoderTrap;
-use encoding::all::ASCII;
-use encoding::label::encoding_from_whatwg_label;
+use alloc::borrow::Cow;
 
 use nom::branch::alt;
 use nom::bytes::complete::{tag, take_while1};
@@ -15,6 +11,7 @@ use nom::combinator::{map, opt};
 use nom::multi::many0;
 use nom::sequence::{delimited, preceded, terminated, tuple};
 
+use crate::prelude::*;
 use crate::util::*;
 use crate::rfc3461::hexpair;
 
@@ -62,18 +59,45 @@ fn _encoded_word(input: &[u8]) -> NomResult<(Cow<str>, Vec<u8>)> {
 
 fn decode_charset((charset, bytes): (Cow<str>, Vec<u8>)) -> String
 {
-    encoding_from_whatwg_label(&charset).unwrap_or(ASCII).decode(&bytes, DecoderTrap::Replace).unwrap()
+    charset_decode(charset_from_label(&charset).unwrap_or(ASCII_CHARSET), &bytes)
 }
 
 /// Decode an encoded word.
 ///
+/// Charsets other than ASCII and UTF-8 are only known with the `std`
+/// feature. Text in an unknown charset is decoded as ASCII.
 /// # Examples
 /// ```
 /// use rustyknife::rfc2047::encoded_word;
 ///
+/// # #[cfg(feature = "std")] {
 /// let (_, decoded) = encoded_word(b"=?x-sjis?B?lEWWQI7Kg4GM9ZTygs6CtSiPzik=?=").unwrap();
 /// assert_eq!(decoded, "忍法写メ光飛ばし(笑)");
+/// # }
 /// ```
 pub fn encoded_word(input: &[u8]) -> NomResult<String> {
     map(_encoded_word, decode_charset)(input)
 }
+
+/// Decode all the encoded words in a string of text.
+///
+/// Adjacent encoded words separated only by whitespace are
+/// concatenated. Text that is not an encoded word is kept as is. This
+/// is the same decoding that [`crate::rfc5322::unstructured`] applies
+/// to header values.
+/// # Examples
+/// ```
+/// use rustyknife::rfc2047::decode_encoded_words;
+///
+/// assert_eq!(decode_encoded_words("=?UTF-8?Q?M=C3=BCller?="), "Müller");
+/// assert_eq!(decode_encoded_words("=?ISO-8859-1?Q?Hans?=  =?UTF-8?B?IE3DvGxsZXI=?= <hm>"), "Hans Müller <hm>");
+/// ```
+pub fn decode_encoded_words(input: &str) -> String {
+    match crate::rfc5322::unstructured::<crate::behaviour::Intl>(input.as_bytes()) {
+        Ok((rem, mut out)) => {
+            out.push_str(&String::from_utf8_lossy(rem));
+            out
+        }
+        Err(_) => input.into(),
+    }
+}
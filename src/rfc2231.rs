@@ -6,15 +6,10 @@
 //! [RFC 2045]: https://tools.ietf.org/html/rfc2045
 
 
-use std::borrow::Cow;
-use std::fmt::{self, Display};
-use std::str;
-use std::collections::HashMap;
-
-use encoding::label::encoding_from_whatwg_label;
-use encoding::types::EncodingRef;
-use encoding::DecoderTrap;
-use encoding::all::ASCII;
+use alloc::borrow::Cow;
+use core::fmt::{self, Display};
+use core::str;
+use alloc::collections::{BTreeMap, BTreeSet};
 
 use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case, take_while1, take_while_m_n};
@@ -23,13 +18,14 @@ use nom::combinator::{map, opt, recognize, verify};
 use nom::multi::many0;
 use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 
+use crate::prelude::*;
 use crate::util::*;
 use crate::rfc3461::hexpair;
 use crate::rfc5234::crlf;
 use crate::rfc5322::{ofws, quoted_string};
 
 #[derive(Debug)]
-struct Parameter<'a> {
+pub(crate) struct Parameter<'a> {
     name: Name<'a>,
     value: Value<'a>,
 }
@@ -67,13 +63,13 @@ fn regular_parameter(input: &[u8]) -> NomResult<Parameter> {
 
 fn regular_parameter_name(input: &[u8]) -> NomResult<Name> {
     map(pair(attribute, opt(section)),
-        |(name, section)| Name{name: std::str::from_utf8(name).unwrap(), section}
+        |(name, section)| Name{name: core::str::from_utf8(name).unwrap(), section}
     )(input)
 }
 
-fn token(input: &[u8]) -> NomResult<&str> {
+pub(crate) fn token(input: &[u8]) -> NomResult<&str> {
     map(take_while1(|c| (33..=126).contains(&c) && !b"()<>@,;:\\\"/[]?=".contains(&c)),
-        |t| std::str::from_utf8(t).unwrap())(input)
+        |t| core::str::from_utf8(t).unwrap())(input)
 }
 
 fn is_attribute_char(c: u8) -> bool {
@@ -160,13 +156,13 @@ enum Segment<'a> {
     Decoded(Cow<'a, str>),
 }
 
-fn decode_segments(mut input: Vec<(u32, Segment)>, encoding: EncodingRef) -> String {
+fn decode_segments(mut input: Vec<(u32, Segment)>, charset: Charset) -> String {
     input.sort_by(|a, b| a.0.cmp(&b.0));
     let mut out = String::new();
     let mut encoded = Vec::new();
 
     let decode = |bytes: &mut Vec<_>, out: &mut String| {
-        out.push_str(&encoding.decode(&bytes, DecoderTrap::Replace).unwrap());
+        out.push_str(&charset_decode(charset, bytes));
         bytes.clear();
     };
 
@@ -182,11 +178,11 @@ fn decode_segments(mut input: Vec<(u32, Segment)>, encoding: EncodingRef) -> Str
     out
 }
 
-fn decode_parameter_list(input: Vec<Parameter>) -> Vec<(String, String)> {
-    let mut simple = HashMap::<String, String>::new();
-    let mut simple_encoded = HashMap::<String, String>::new();
-    let mut composite = HashMap::<String, Vec<(u32, Segment)>>::new();
-    let mut composite_encoding = HashMap::new();
+pub(crate) fn decode_parameter_list(input: Vec<Parameter>) -> Vec<(String, String)> {
+    let mut simple = BTreeMap::<String, String>::new();
+    let mut simple_encoded = BTreeMap::<String, String>::new();
+    let mut composite = BTreeMap::<String, Vec<(u32, Segment)>>::new();
+    let mut composite_encoding = BTreeMap::new();
 
     for Parameter{name, value} in input {
         let name_norm = name.name.to_lowercase();
@@ -196,11 +192,11 @@ fn decode_parameter_list(input: Vec<Parameter>) -> Vec<(String, String)> {
                 match value {
                     Value::Regular(v) => { simple.insert(name_norm, v.into()); },
                     Value::Extended(ExtendedValue::Initial{value, encoding: encoding_name, ..}) => {
-                        let codec = match encoding_name {
-                            Some(encoding_name) => encoding_from_whatwg_label(&ascii_to_string(encoding_name)).unwrap_or(ASCII),
-                            None => ASCII,
+                        let charset = match encoding_name {
+                            Some(encoding_name) => charset_from_label(&ascii_to_string(encoding_name)).unwrap_or(ASCII_CHARSET),
+                            None => ASCII_CHARSET,
                         };
-                        simple_encoded.insert(name_norm, codec.decode(&value, DecoderTrap::Replace).unwrap());
+                        simple_encoded.insert(name_norm, charset_decode(charset, &value));
                     }
                     Value::Extended(ExtendedValue::Other(..)) => unreachable!(),
                 }
@@ -212,8 +208,8 @@ fn decode_parameter_list(input: Vec<Parameter>) -> Vec<(String, String)> {
                     Value::Regular(v) => ent.push((section, Segment::Decoded(v))),
                     Value::Extended(ExtendedValue::Initial{value, encoding: encoding_name, ..}) => {
                         if let Some(encoding_name) = encoding_name {
-                            if let Some(codec) = encoding_from_whatwg_label(&ascii_to_string(encoding_name)) {
-                                composite_encoding.insert(name_norm, codec);
+                            if let Some(charset) = charset_from_label(&ascii_to_string(encoding_name)) {
+                                composite_encoding.insert(name_norm, charset);
                             }
                         }
                         ent.push((section, Segment::Encoded(value.to_vec())))
@@ -226,8 +222,8 @@ fn decode_parameter_list(input: Vec<Parameter>) -> Vec<(String, String)> {
 
     let mut composite_out = Vec::new();
     for (name, segments) in composite {
-        let codec = composite_encoding.get(&name).cloned().unwrap_or(ASCII);
-        composite_out.push((name, decode_segments(segments, codec)));
+        let charset = composite_encoding.get(&name).cloned().unwrap_or(ASCII_CHARSET);
+        composite_out.push((name, decode_segments(segments, charset)));
     }
 
     for (name, value) in simple_encoded.into_iter().chain(composite_out.into_iter()) {
@@ -241,9 +237,31 @@ fn decode_parameter_list(input: Vec<Parameter>) -> Vec<(String, String)> {
 ///
 /// Returns a tuple of the MIME type and parameters.
 pub fn content_type(input: &[u8]) -> NomResult<(String, Vec<(String, String)>)> {
-    map(pair(delimited(ofws, _mime_type, ofws),
-             _parameter_list),
-        |(mt, p)| (ascii_to_string(mt).to_lowercase(), decode_parameter_list(p)))(input)
+    map(_content_type_raw, |(mt, p)| (ascii_to_string(mt).to_lowercase(), decode_parameter_list(p)))(input)
+}
+
+pub(crate) fn _content_type_raw(input: &[u8]) -> NomResult<(&[u8], Vec<Parameter>)> {
+    pair(delimited(ofws, _mime_type, ofws), _parameter_list)(input)
+}
+
+// Name of the first parameter given more than once. A parameter may
+// be split in sections or not, but not both.
+pub(crate) fn _duplicate_param(params: &[Parameter]) -> Option<String> {
+    let mut whole = BTreeSet::new();
+    let mut sections = BTreeSet::new();
+
+    for Parameter{name, ..} in params {
+        let norm = name.name.to_lowercase();
+        let unique = match name.section {
+            None => !sections.iter().any(|(n, _)| *n == norm) && whole.insert(norm.clone()),
+            Some(section) => !whole.contains(&norm) && sections.insert((norm.clone(), section)),
+        };
+
+        if !unique {
+            return Some(norm);
+        }
+    }
+    None
 }
 
 fn _x_token(input: &[u8]) -> NomResult<&str> {
@@ -2,13 +2,15 @@
 //!
 //! [SMTP DSN]: https://tools.ietf.org/html/rfc3461
 
-use std::borrow::Cow;
-use std::str;
+use alloc::borrow::Cow;
+use core::fmt::{self, Display};
+use core::str;
 
+use crate::prelude::*;
 use crate::util::*;
 
 use nom::branch::alt;
-use nom::bytes::complete::{take, tag, tag_no_case};
+use nom::bytes::complete::{take, take_while_m_n, tag, tag_no_case};
 use nom::character::is_hex_digit;
 use nom::combinator::{map, map_res, verify};
 use nom::multi::many0;
@@ -17,14 +19,24 @@ use nom::sequence::{preceded, separated_pair};
 use crate::rfc5322::atom;
 
 pub(crate) fn hexpair(input: &[u8]) -> NomResult<u8> {
-    map_res(verify(take(2usize), |c: &[u8]| c.iter().cloned().all(is_hex_digit)),
-            |x| u8::from_str_radix(str::from_utf8(x).unwrap(), 16))(input)
+    map_res(map_res(verify(take(2usize), |c: &[u8]| c.iter().cloned().all(is_hex_digit)), str::from_utf8),
+            |x| u8::from_str_radix(x, 16))(input)
 }
 
+fn upper_hexpair(input: &[u8]) -> NomResult<u8> {
+    map_res(map_res(take_while_m_n(2, 2, |c: u8| c.is_ascii_digit() || (b'A'..=b'F').contains(&c)), str::from_utf8),
+            |x| u8::from_str_radix(x, 16))(input)
+}
+
+// Lower case hex digits are tolerated by the parameter parsers.
 fn hexchar(input: &[u8]) -> NomResult<u8> {
     preceded(tag("+"), hexpair)(input)
 }
 
+fn upper_hexchar(input: &[u8]) -> NomResult<u8> {
+    preceded(tag("+"), upper_hexpair)(input)
+}
+
 fn xchar(input: &[u8]) -> NomResult<u8> {
     take1_filter(|c| match c { 33..=42 | 44..=60 | 62..=126 => true, _ => false})(input)
 }
@@ -33,6 +45,90 @@ pub(crate) fn xtext(input: &[u8]) -> NomResult<Vec<u8>> {
     many0(alt((xchar, hexchar)))(input)
 }
 
+fn strict_xtext(input: &[u8]) -> NomResult<Vec<u8>> {
+    many0(alt((xchar, upper_hexchar)))(input)
+}
+
+/// Error returned by [`xtext_decode`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct XtextError {
+    /// Byte offset of the first character that is neither a valid
+    /// xchar nor a `+HH` escape, or of the escape starting an invalid
+    /// UTF-8 sequence.
+    pub offset: usize,
+}
+
+impl Display for XtextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid xtext at offset {}", self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for XtextError {}
+
+/// Decode an xtext encoded string.
+///
+/// Each `+` must be followed by two upper case hexadecimal digits.
+/// The decoded bytes must be valid UTF-8, as produced by
+/// [`xtext_encode`].
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::{xtext_decode, XtextError};
+///
+/// assert_eq!(xtext_decode("bob+2Bsmith+3D"), Ok("bob+smith=".into()));
+/// assert_eq!(xtext_decode("jos+C3+A9"), Ok("josé".into()));
+/// assert_eq!(xtext_decode("bob+2b"), Err(XtextError{ offset: 3 }));
+/// assert_eq!(xtext_decode("bob+FF"), Err(XtextError{ offset: 3 }));
+/// ```
+pub fn xtext_decode(input: &str) -> Result<String, XtextError> {
+    match strict_xtext(input.as_bytes()) {
+        Ok((b"", out)) => String::from_utf8(out).map_err(|e| XtextError{ offset: _xtext_offset(input, e.utf8_error().valid_up_to()) }),
+        Ok((rem, _)) => Err(XtextError{ offset: input.len() - rem.len() }),
+        Err(_) => Err(XtextError{ offset: 0 }),
+    }
+}
+
+// Offset in valid xtext of the escape or xchar decoding to the byte
+// at `decoded`.
+fn _xtext_offset(input: &str, decoded: usize) -> usize {
+    let mut offset = 0;
+
+    for _ in 0..decoded {
+        offset += if input.as_bytes()[offset] == b'+' { 3 } else { 1 };
+    }
+    offset
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Encode a string as xtext.
+///
+/// Bytes outside of `33..=126` as well as `+` and `=` are escaped as
+/// `+HH`.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::xtext_encode;
+///
+/// assert_eq!(xtext_encode("bob+smith= é"), "bob+2Bsmith+3D+20+C3+A9");
+/// ```
+pub fn xtext_encode(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+
+    for c in input.bytes() {
+        match c {
+            b'+' | b'=' | 0..=32 | 127..=255 => {
+                out.push('+');
+                out.push(HEX_DIGITS[(c >> 4) as usize] as char);
+                out.push(HEX_DIGITS[(c & 0xf) as usize] as char);
+            }
+            _ => out.push(c as char),
+        }
+    }
+
+    out
+}
+
 fn _printable_xtext(input: &[u8]) -> NomResult<Vec<u8>> {
     verify(xtext, |xtext: &[u8]| {
         xtext.iter().all(|c| match c { 9..=13 | 32..=126 => true, _ => false})
@@ -56,7 +152,7 @@ pub fn orcpt_address(input: &[u8]) -> NomResult<(Cow<str>, Cow<str>)> {
 }
 
 /// The DSN return type desired by the sender.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DSNRet {
     /// Return full the full message content.
     Full,
@@ -136,12 +232,193 @@ pub fn dsn_mail_params<'a>(input: &[Param<'a>]) -> Result<(DSNMailParams, Vec<Pa
     Ok((DSNMailParams{envid: envid_val, ret: ret_val}, out))
 }
 
+/// DSN parameters for the RCPT command.
+#[derive(Debug, PartialEq)]
+pub struct DSNRcptParams {
+    /// The conditions under which a DSN should be generated.
+    ///
+    /// `None` if not specified.
+    pub notify: Option<Notify>,
+    /// The address type and the decoded original recipient address.
+    ///
+    /// `None` if not specified.
+    pub orcpt: Option<(String, String)>,
+}
+
+/// Parse a list of ESMTP parameters on a RCPT TO command into a
+/// [`DSNRcptParams`] option block.
+///
+/// Returns the option block and a vector of parameters that were not
+/// consumed.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::{dsn_rcpt_params, DSNRcptParams, Notify};
+/// let input = &[("NOTIFY", Some("SUCCESS,DELAY")),
+///               ("OTHER", None)];
+///
+/// let (params, other) = dsn_rcpt_params(input).unwrap();
+///
+/// assert_eq!(params, DSNRcptParams{ notify: Some(Notify{ on_success: true, on_failure: false, delay: true }),
+///                                   orcpt: None });
+/// assert_eq!(other, [("OTHER", None)]);
+/// ```
+pub fn dsn_rcpt_params<'a>(input: &[Param<'a>]) -> Result<(DSNRcptParams, Vec<Param<'a>>), &'static str>
+{
+    let mut out = Vec::new();
+    let mut notify_val : Option<Notify> = None;
+    let mut orcpt_val : Option<(String, String)> = None;
+
+    for (name, value) in input {
+        match (name.to_lowercase().as_str(), value) {
+            ("notify", Some(value)) => {
+                if notify_val.is_some() { return Err("Duplicate NOTIFY"); }
+
+                notify_val = Some(exact!(*value, dsn_notify).map_err(|_| "Invalid NOTIFY")?.1);
+            },
+            ("orcpt", Some(value)) => {
+                if orcpt_val.is_some() { return Err("Duplicate ORCPT"); }
+
+                orcpt_val = Some(_orcpt_value(value).ok_or("Invalid ORCPT")?);
+            },
+            ("notify", None) => { return Err("NOTIFY without value") },
+            ("orcpt", None) => { return Err("ORCPT without value") },
+            _ => {
+                out.push((*name, *value))
+            }
+        }
+    }
+
+    Ok((DSNRcptParams{notify: notify_val, orcpt: orcpt_val}, out))
+}
+
+/// Error returned by [`orcpt_param`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrcptError {
+    /// The ORCPT parameter was specified more than once.
+    Duplicate,
+    /// The ORCPT parameter has no value.
+    MissingValue,
+    /// The value is not a valid `addr-type ";" xtext` pair.
+    ///
+    /// This includes malformed `+HH` escapes.
+    Invalid,
+}
+
+impl Display for OrcptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            OrcptError::Duplicate => "Duplicate ORCPT",
+            OrcptError::MissingValue => "ORCPT without value",
+            OrcptError::Invalid => "Invalid ORCPT",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OrcptError {}
+
+fn _orcpt_value(value: &str) -> Option<(String, String)> {
+    exact!(value.as_bytes(), orcpt_address).ok()
+        .map(|(_, (addr_type, addr))| (addr_type.into(), addr.into()))
+}
+
+/// Find and decode the ORCPT parameter in a list of ESMTP parameters.
+///
+/// Returns the address type and the xtext decoded original recipient
+/// address or `None` if the parameter is absent.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::{orcpt_param, OrcptError};
+/// let input = &[("NOTIFY", Some("NEVER")),
+///               ("ORCPT", Some("rfc822;bob+2Bsmith@example.org"))];
+///
+/// assert_eq!(orcpt_param(input), Ok(Some(("rfc822".into(), "bob+smith@example.org".into()))));
+/// assert_eq!(orcpt_param(&[("ORCPT", Some("rfc822;bob+Z@example.org"))]), Err(OrcptError::Invalid));
+/// ```
+pub fn orcpt_param(input: &[Param]) -> Result<Option<(String, String)>, OrcptError>
+{
+    let mut orcpt_val = None;
+
+    for (name, value) in input {
+        if !name.eq_ignore_ascii_case("orcpt") {
+            continue;
+        }
+        if orcpt_val.is_some() { return Err(OrcptError::Duplicate); }
+
+        let value = value.ok_or(OrcptError::MissingValue)?;
+        orcpt_val = Some(_orcpt_value(value).ok_or(OrcptError::Invalid)?);
+    }
+
+    Ok(orcpt_val)
+}
+
+/// The conditions under which a DSN should be generated.
+///
+/// All the flags are false for `"NEVER"`.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Notify {
+    /// Notify on successful delivery.
     pub on_success: bool,
+    /// Notify on delivery failure.
     pub on_failure: bool,
+    /// Notify on delayed delivery.
     pub delay: bool,
 }
 
+/// Error returned by [`notify_param`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NotifyError {
+    /// The NOTIFY parameter was specified more than once.
+    Duplicate,
+    /// The NOTIFY parameter has no value.
+    MissingValue,
+    /// The value is not `"NEVER"` or a list of `"SUCCESS"`,
+    /// `"FAILURE"` and `"DELAY"`.
+    Invalid,
+}
+
+impl Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            NotifyError::Duplicate => "Duplicate NOTIFY",
+            NotifyError::MissingValue => "NOTIFY without value",
+            NotifyError::Invalid => "Invalid NOTIFY",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NotifyError {}
+
+/// Find and parse the NOTIFY parameter in a list of ESMTP parameters.
+///
+/// Returns `None` if the parameter is absent.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3461::{notify_param, Notify, NotifyError};
+///
+/// assert_eq!(notify_param(&[("NOTIFY", Some("failure,delay"))]),
+///            Ok(Some(Notify{on_success: false, on_failure: true, delay: true})));
+/// assert_eq!(notify_param(&[("NOTIFY", Some("NEVER,DELAY"))]), Err(NotifyError::Invalid));
+/// assert_eq!(notify_param(&[]), Ok(None));
+/// ```
+pub fn notify_param(input: &[Param]) -> Result<Option<Notify>, NotifyError>
+{
+    let mut notify_val = None;
+
+    for (name, value) in input {
+        if !name.eq_ignore_ascii_case("notify") {
+            continue;
+        }
+        if notify_val.is_some() { return Err(NotifyError::Duplicate); }
+
+        let value = value.ok_or(NotifyError::MissingValue)?;
+        notify_val = Some(exact!(value, dsn_notify).map_err(|_| NotifyError::Invalid)?.1);
+    }
+
+    Ok(notify_val)
+}
+
 fn convert_notify_list(input: Vec<&str>) -> Notify {
     let mut on_success = false;
     let mut on_failure = false;
@@ -172,6 +449,10 @@ fn notify_item(input: &str) -> Result<(&str, &str), nom::Err<()>> {
     ))(input)
 }
 
+/// Parse the value of the ESMTP NOTIFY parameter.
+///
+/// `"NEVER"` may not be combined with other keywords. Keywords are
+/// case-insensitive.
 pub fn dsn_notify(input: &str) -> Result<(&str, Notify), nom::Err<()>> {
     alt((
         map(tag_no_case("never"), |_| Notify {
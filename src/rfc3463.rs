@@ -0,0 +1,171 @@
+//! [Enhanced mail system status codes]
+//!
+//! [Enhanced mail system status codes]: https://tools.ietf.org/html/rfc3463
+
+use core::convert::TryFrom;
+use core::fmt::{self, Display};
+use core::str::{self, FromStr};
+
+use nom::bytes::complete::tag;
+use nom::character::complete::digit1;
+use nom::combinator::map_res;
+use nom::sequence::{preceded, tuple};
+
+use crate::util::*;
+
+/// An enhanced status code such as `"5.1.1"`.
+///
+/// # Examples
+/// ```
+/// use std::convert::TryFrom;
+/// use std::str::FromStr;
+/// use rustyknife::rfc3463::{EnhancedStatus, StatusError};
+///
+/// let status = EnhancedStatus::from_str("5.1.1").unwrap();
+///
+/// assert_eq!((status.class(), status.subject(), status.detail()), (5, 1, 1));
+/// assert_eq!(EnhancedStatus::try_from((4, 7, 0)).unwrap().to_string(), "4.7.0");
+/// assert_eq!(EnhancedStatus::from_str("3.1.1"), Err(StatusError::InvalidClass));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EnhancedStatus {
+    class: u8,
+    subject: u16,
+    detail: u16,
+}
+
+impl EnhancedStatus {
+    /// Class of the status: 2 for success, 4 for a persistent
+    /// transient failure and 5 for a permanent failure.
+    pub fn class(&self) -> u8 {
+        self.class
+    }
+
+    /// Subject of the status such as 1 for addressing or 7 for
+    /// security.
+    pub fn subject(&self) -> u16 {
+        self.subject
+    }
+
+    /// Detail within the subject.
+    pub fn detail(&self) -> u16 {
+        self.detail
+    }
+
+    /// Returns true if the class is 2.
+    pub fn is_success(&self) -> bool {
+        self.class == 2
+    }
+
+    /// Returns true if the class is 4.
+    pub fn is_transient(&self) -> bool {
+        self.class == 4
+    }
+
+    /// Returns true if the class is 5.
+    pub fn is_permanent(&self) -> bool {
+        self.class == 5
+    }
+}
+
+impl Display for EnhancedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.class, self.subject, self.detail)
+    }
+}
+
+/// Error returned when building or parsing an [`EnhancedStatus`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatusError {
+    /// The input is not of the form `class.subject.detail`.
+    Syntax,
+    /// The class is not 2, 4 or 5.
+    InvalidClass,
+    /// The subject or the detail is above 999.
+    OutOfRange,
+}
+
+impl Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            StatusError::Syntax => "Invalid enhanced status code",
+            StatusError::InvalidClass => "Invalid class",
+            StatusError::OutOfRange => "Sub-code out of range",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StatusError {}
+
+impl TryFrom<(u8, u16, u16)> for EnhancedStatus {
+    type Error = StatusError;
+
+    fn try_from((class, subject, detail): (u8, u16, u16)) -> Result<Self, Self::Error> {
+        if ![2, 4, 5].contains(&class) {
+            Err(StatusError::InvalidClass)
+        } else if subject > 999 || detail > 999 {
+            Err(StatusError::OutOfRange)
+        } else {
+            Ok(EnhancedStatus{class, subject, detail})
+        }
+    }
+}
+
+impl FromStr for EnhancedStatus {
+    type Err = StatusError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_enhanced_status(s)
+    }
+}
+
+fn _status_parts(input: &[u8]) -> NomResult<(&[u8], &[u8], &[u8])> {
+    tuple((digit1, preceded(tag("."), digit1), preceded(tag("."), digit1)))(input)
+}
+
+fn _to_status((class, subject, detail): (&[u8], &[u8], &[u8])) -> Result<EnhancedStatus, StatusError> {
+    let sub_code = |digits: &[u8]| match digits.len() {
+        1..=3 => Ok(digits.iter().fold(0, |code, c| code * 10 + u16::from(c - b'0'))),
+        _ => Err(StatusError::OutOfRange),
+    };
+    let class = match class {
+        [c] => c - b'0',
+        _ => return Err(StatusError::InvalidClass),
+    };
+
+    EnhancedStatus::try_from((class, sub_code(subject)?, sub_code(detail)?))
+}
+
+/// Parse an enhanced status code.
+///
+/// Anything following the code, such as the rest of a reply line, is
+/// returned as the remaining input.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3463::enhanced_status_code;
+///
+/// let (rem, status) = enhanced_status_code(b"5.1.1 User unknown").unwrap();
+///
+/// assert_eq!(status.to_string(), "5.1.1");
+/// assert_eq!(rem, b" User unknown");
+/// ```
+pub fn enhanced_status_code(input: &[u8]) -> NomResult<EnhancedStatus> {
+    map_res(_status_parts, _to_status)(input)
+}
+
+/// Parse a complete enhanced status code such as `"5.1.1"`.
+/// # Examples
+/// ```
+/// use rustyknife::rfc3463::{parse_enhanced_status, StatusError};
+///
+/// assert!(parse_enhanced_status("5.1.1").unwrap().is_permanent());
+/// assert_eq!(parse_enhanced_status("3.1.1"), Err(StatusError::InvalidClass));
+/// assert_eq!(parse_enhanced_status("5.1000.1"), Err(StatusError::OutOfRange));
+/// assert_eq!(parse_enhanced_status("5.1"), Err(StatusError::Syntax));
+/// ```
+pub fn parse_enhanced_status(s: &str) -> Result<EnhancedStatus, StatusError> {
+    let (_, parts) = exact!(s.as_bytes(), _status_parts).map_err(|_| StatusError::Syntax)?;
+
+    _to_status(parts)
+}
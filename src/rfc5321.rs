@@ -1,27 +1,39 @@
 //! Parsers for [SMTP] command syntax
 //!
+//! The command parsers expect a whole command line including the
+//! terminating CRLF, as read from the connection. Use [`strip_crlf`]
+//! to go the other way, or set [`ParseOptions::require_crlf`] to
+//! false to accept lines that were already trimmed.
+//!
 //! [SMTP]: https://tools.ietf.org/html/rfc5321
 
-use std::convert::TryFrom;
-use std::fmt::{self, Display};
+use core::convert::TryFrom;
+use core::fmt::{self, Display};
+#[cfg(feature = "std")]
 use std::net::{Ipv4Addr, Ipv6Addr};
-use std::str::{self, FromStr};
+#[cfg(not(feature = "std"))]
+use core::net::{Ipv4Addr, Ipv6Addr};
+use core::str::{self, FromStr};
 
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
 use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case, take_while1, take_while_m_n};
+use nom::bytes::streaming;
 use nom::character::{is_alphanumeric, is_digit, is_hex_digit};
-use nom::combinator::{map, map_res, opt, recognize, verify};
-use nom::error::ParseError;
+use nom::combinator::{consumed, eof, map, map_res, opt, peek, recognize, verify};
+use nom::error::{context, ErrorKind, ParseError};
+use nom::Needed;
 use nom::multi::{many0, many1, many_m_n};
-use nom::sequence::{delimited, pair, preceded, separated_pair, terminated};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 
 use crate::behaviour::{Legacy, Intl};
 use crate::rfc5322::utf8_non_ascii;
+use crate::rfc3461::{dsn_mail_params, notify_param, orcpt_param, DSNRet, Notify, NotifyError, OrcptError};
 use crate::rfc5234::{crlf, wsp};
 use crate::types::*;
+use crate::prelude::*;
 use crate::util::*;
 
 #[allow(missing_docs)] // Mostly internal
@@ -45,6 +57,7 @@ impl UTF8Policy for Legacy {
         map(take1_filter(|c| match c {33..=60 | 62..=126 => true, _ => false}), char::from)(input)
     }
 
+    // Label lengths are checked by the domain parser.
     fn sub_domain(input: &[u8]) -> NomResult<&[u8]> {
         recognize(pair(let_dig, opt(ldh_str)))(input)
     }
@@ -63,18 +76,33 @@ impl UTF8Policy for Intl {
         alt((Legacy::esmtp_value_char, utf8_non_ascii))(input)
     }
 
+    #[cfg(feature = "idna")]
     fn sub_domain(input: &[u8]) -> NomResult<&[u8]> {
-        verify(recognize_many1(alt((map(take1_filter(_is_ldh), char::from), utf8_non_ascii))), |label| {
-            idna::Config::default()
-                .use_std3_ascii_rules(true)
-                .verify_dns_length(true)
-                .check_hyphens(true)
-                .to_ascii(str::from_utf8(label).unwrap())
-                .is_ok()
-        })(input)
+        recognize(verify(map_res(_utf8_label, str::from_utf8),
+                         |label: &str| _idna_config().verify_dns_length(false).to_ascii(label).is_ok()))(input)
+    }
+
+    // Without IDNA, U-labels are only checked for their syntax.
+    #[cfg(not(feature = "idna"))]
+    fn sub_domain(input: &[u8]) -> NomResult<&[u8]> {
+        recognize(verify(_utf8_label, |label: &[u8]| {
+            label.first() != Some(&b'-') && label.last() != Some(&b'-')
+        }))(input)
     }
 }
 
+fn _utf8_label(input: &[u8]) -> NomResult<&[u8]> {
+    recognize_many1(alt((map(take1_filter(_is_ldh), char::from), utf8_non_ascii)))(input)
+}
+
+#[cfg(feature = "idna")]
+pub(crate) fn _idna_config() -> idna::Config {
+    idna::Config::default()
+        .use_std3_ascii_rules(true)
+        .verify_dns_length(true)
+        .check_hyphens(true)
+}
+
 /// ESMTP parameter.
 ///
 /// Represents an ESMTP parameter.
@@ -152,27 +180,55 @@ impl<'a> Display for Params<'a> {
 /// Used as the left side in an ESMTP parameter.  For example, it
 /// represents the "BODY" string in a parameter "BODY=8BIT".
 #[derive(Clone, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Keyword(pub(crate) String);
 string_newtype!(Keyword);
 nom_fromstr!(Keyword, esmtp_keyword);
+serde_string!(Keyword);
 
 /// ESMTP parameter value.
 ///
 /// Used as the right side in an ESMTP parameter.  For example, it
 /// represents the "8BIT" string in a parameter "BODY=8BIT".
 #[derive(Clone, PartialEq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Value(pub(crate) String);
 string_newtype!(Value);
 nom_fromstr!(Value, esmtp_value::<Intl>);
+serde_string!(Value);
 
 /// Path with source route.
 ///
 /// The source route is absent when `self.1.is_empty()`.
+/// # Examples
+/// ```
+/// use std::str::FromStr;
+/// use rustyknife::rfc5321::Path;
+///
+/// let path = Path::from_str("<@a.example,@b.example:bob@example.org>").unwrap();
+///
+/// assert_eq!(path.0.to_string(), "bob@example.org");
+/// assert_eq!(path.1.iter().map(|d| d.to_string()).collect::<Vec<_>>(), ["a.example", "b.example"]);
+/// ```
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Path(pub Mailbox, pub Vec<Domain>);
 nom_fromstr!(Path, path::<Intl>);
+serde_string!(Path);
+
+impl Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<")?;
+        for (i, domain) in self.1.iter().enumerate() {
+            let sep = if i < self.1.len() - 1 { "," } else { ":" };
+            write!(f, "@{}{}", domain, sep)?;
+        }
+        write!(f, "{}>", self.0)
+    }
+}
+
+impl From<Path> for String {
+    fn from(path: Path) -> String {
+        path.to_string()
+    }
+}
 
 /// A generic SMTP string built from an atom or a quoted string
 #[derive(Clone, PartialEq)]
@@ -180,6 +236,19 @@ pub struct SMTPString(pub(crate) String);
 string_newtype!(SMTPString);
 
 /// Represents a forward path from the `"RCPT TO"` command.
+///
+/// Both forms of the postmaster address from [RFC 5321] are
+/// recognized, case-insensitively.
+/// # Examples
+/// ```
+/// use std::str::FromStr;
+/// use rustyknife::rfc5321::ForwardPath;
+///
+/// assert!(matches!(ForwardPath::from_str("<Postmaster>").unwrap(), ForwardPath::PostMaster(None)));
+/// assert!(matches!(ForwardPath::from_str("<postmaster@example.org>").unwrap(), ForwardPath::PostMaster(Some(_))));
+/// ```
+///
+/// [RFC 5321]: https://tools.ietf.org/html/rfc5321#section-4.1.1.3
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum ForwardPath {
     /// `"<person@example.org>"`
@@ -189,6 +258,7 @@ pub enum ForwardPath {
     PostMaster(Option<Domain>),
 }
 nom_fromstr!(ForwardPath, _forward_path::<Intl>);
+serde_string!(ForwardPath);
 
 impl ForwardPath {
     /// Convert this path into a mailbox.
@@ -212,15 +282,21 @@ impl ForwardPath {
 impl Display for ForwardPath {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ForwardPath::Path(p) => write!(f, "<{}>", p.0),
+            ForwardPath::Path(p) => write!(f, "{}", p),
             ForwardPath::PostMaster(None) => write!(f, "<postmaster>"),
             ForwardPath::PostMaster(Some(d)) => write!(f, "<postmaster@{}>", d),
         }
     }
 }
 
+impl From<ForwardPath> for String {
+    fn from(path: ForwardPath) -> String {
+        path.to_string()
+    }
+}
+
 /// Represents a reverse path from the `"MAIL FROM"` command.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum ReversePath {
     /// MAIL FROM: \<person@example.org\>
     Path(Path),
@@ -228,16 +304,49 @@ pub enum ReversePath {
     Null,
 }
 nom_fromstr!(ReversePath, reverse_path::<Intl>);
+serde_string!(ReversePath);
+
+impl ReversePath {
+    /// Check whether this is the null reverse path `"<>"`.
+    ///
+    /// The null reverse path is used for bounces and other
+    /// notifications that must not be answered.
+    pub fn is_null_sender(&self) -> bool {
+        matches!(self, ReversePath::Null)
+    }
+
+    /// Render this path in angle brackets as sent in a MAIL command.
+    ///
+    /// Same as the [`Display`] output, the null reverse path is
+    /// rendered as `"<>"`.
+    /// # Examples
+    /// ```
+    /// use std::str::FromStr;
+    /// use rustyknife::rfc5321::ReversePath;
+    ///
+    /// assert_eq!(ReversePath::Null.to_smtp_path(), "<>");
+    /// assert_eq!(ReversePath::from_str("<bob@example.org>").unwrap().to_smtp_path(), "<bob@example.org>");
+    /// ```
+    pub fn to_smtp_path(&self) -> String {
+        self.to_string()
+    }
+}
 
 impl Display for ReversePath {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ReversePath::Path(p) => write!(f, "<{}>", p.0),
+            ReversePath::Path(p) => write!(f, "{}", p),
             ReversePath::Null => write!(f, "<>"),
         }
     }
 }
 
+impl From<ReversePath> for String {
+    fn from(path: ReversePath) -> String {
+        path.to_string()
+    }
+}
+
 impl From<ReversePath> for Option<Mailbox> {
     fn from(path: ReversePath) -> Option<Mailbox> {
         match path {
@@ -252,25 +361,40 @@ fn _is_ldh(c: u8) -> bool {
 }
 
 fn esmtp_keyword(input: &[u8]) -> NomResult<Keyword> {
-    map(recognize(pair(take1_filter(is_alphanumeric), recognize_many0(take1_filter(_is_ldh)))),
-        |x| Keyword(std::str::from_utf8(x).unwrap().into()))(input)
+    map(map_res(recognize(pair(take1_filter(is_alphanumeric), recognize_many0(take1_filter(_is_ldh)))), str::from_utf8),
+        |x| Keyword(x.into()))(input)
+}
+
+fn _esmtp_value_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, Value> {
+    map(verify(map_res(recognize_many1(P::esmtp_value_char), str::from_utf8),
+               |v: &str| v.len() <= options.max_param_value_length),
+        |x| Value(x.into()))(input)
 }
 
 fn esmtp_value<P: UTF8Policy>(input: &[u8]) -> NomResult<Value> {
-    map(recognize_many1(P::esmtp_value_char),
-        |x| Value(std::str::from_utf8(x).unwrap().into()))(input)
+    _esmtp_value_with::<P>(input, &BASE_OPTIONS)
 }
 
-fn esmtp_param<P: UTF8Policy>(input: &[u8]) -> NomResult<Param> {
-    map(pair(esmtp_keyword, opt(preceded(tag("="), esmtp_value::<P>))),
+fn _esmtp_param_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, Param> {
+    map(pair(esmtp_keyword, opt(preceded(tag("="), |i| _esmtp_value_with::<P>(i, options)))),
         |(n, v)| Param(n, v))(input)
 }
 
+pub(crate) fn esmtp_param<P: UTF8Policy>(input: &[u8]) -> NomResult<Param> {
+    _esmtp_param_with::<P>(input, &BASE_OPTIONS)
+}
+
+fn _esmtp_params_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, Vec<Param>> {
+    let param = |i| _esmtp_param_with::<P>(i, options);
+
+    fold_prefix0(param, preceded(many1(wsp), param))(input)
+}
+
 fn _esmtp_params<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<Param>> {
-    fold_prefix0(esmtp_param::<P>, preceded(many1(wsp), esmtp_param::<P>))(input)
+    _esmtp_params_with::<P>(input, &BASE_OPTIONS)
 }
 
-fn ldh_str(input: &[u8]) -> NomResult<&[u8]> {
+pub(crate) fn ldh_str(input: &[u8]) -> NomResult<&[u8]> {
     let (_, mut out) = take_while1(_is_ldh)(input)?;
 
     while out.last() == Some(&b'-') {
@@ -289,26 +413,76 @@ fn let_dig(input: &[u8]) -> NomResult<u8> {
     take1_filter(is_alphanumeric)(input)
 }
 
-pub(crate) fn domain<P: UTF8Policy>(input: &[u8]) -> NomResult<Domain> {
-    map(recognize(pair(P::sub_domain, many0(pair(tag("."), P::sub_domain)))),
-        |domain| Domain(str::from_utf8(domain).unwrap().into()))(input)
+/// Maximum length of a domain label in octets.
+pub const MAX_LABEL_LENGTH: usize = 63;
+
+/// Maximum length of a domain in octets as per [RFC 5321].
+///
+/// [RFC 5321]: https://tools.ietf.org/html/rfc5321#section-4.5.3.1.2
+pub const MAX_DOMAIN_LENGTH: usize = 255;
+
+// Length of a domain or label in its ASCII form.
+#[cfg(feature = "idna")]
+fn _ascii_len(domain: &str) -> usize {
+    if domain.is_ascii() {
+        domain.len()
+    } else {
+        _idna_config().verify_dns_length(false).to_ascii(domain).map_or(usize::MAX, |d| d.len())
+    }
+}
+
+// Without IDNA, U-labels are measured in octets.
+#[cfg(not(feature = "idna"))]
+fn _ascii_len(domain: &str) -> usize {
+    domain.len()
+}
+
+// Whether the last label of a domain is all digits.
+fn _is_numeric_tld(domain: &str) -> bool {
+    domain.rsplit('.').next().map_or(false, |tld| tld.bytes().all(|c| c.is_ascii_digit()))
+}
+
+fn _domain_ref_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, &'a str> {
+    let (rem, domain) = verify(map_res(recognize(pair(P::sub_domain, recognize_many0(pair(tag("."), P::sub_domain)))), str::from_utf8),
+                               |d: &str| {
+                                   _ascii_len(d) <= options.max_domain_length &&
+                                       d.split('.').all(|label| _ascii_len(label) <= options.max_label_length)
+                               })(input)?;
+
+    if !options.allow_numeric_tld && _is_numeric_tld(domain) {
+        return Err(nom::Err::Error(NomError{input, kind: ErrorKind::Verify, context: Some("numeric top-level domain")}));
+    }
+    Ok((rem, domain))
+}
+
+fn _domain_ref<P: UTF8Policy>(input: &[u8]) -> NomResult<&str> {
+    _domain_ref_with::<P>(input, &BASE_OPTIONS)
 }
 
-fn at_domain<P: UTF8Policy>(input: &[u8]) -> NomResult<Domain> {
-    preceded(tag("@"), domain::<P>)(input)
+fn _domain_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, Domain> {
+    map(|i| _domain_ref_with::<P>(i, options), |domain| Domain(domain.into()))(input)
 }
 
-fn a_d_l<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<Domain>> {
-    fold_prefix0(at_domain::<P>, preceded(tag(","), at_domain::<P>))(input)
+pub(crate) fn domain<P: UTF8Policy>(input: &[u8]) -> NomResult<Domain> {
+    _domain_with::<P>(input, &BASE_OPTIONS)
+}
+
+// Check the syntax of a domain, ignoring length limits.
+pub(crate) fn _is_domain_syntax(input: &[u8]) -> bool {
+    let options = ParseOptions{max_label_length: usize::MAX, max_domain_length: usize::MAX, ..BASE_OPTIONS};
+    exact!(input, |i| _domain_ref_with::<Intl>(i, &options)).is_ok()
 }
 
 fn atom<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
     recognize_many1(P::atext)(input)
 }
 
+fn _dot_string_ref<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
+    recognize(pair(atom::<P>, recognize_many0(pair(tag("."), atom::<P>))))(input)
+}
+
 pub(crate) fn dot_string<P: UTF8Policy>(input: &[u8]) -> NomResult<DotAtom> {
-    map(recognize(pair(atom::<P>, many0(pair(tag("."), atom::<P>)))),
-        |a| DotAtom(str::from_utf8(a).unwrap().into()))(input)
+    map(map_res(_dot_string_ref::<P>, str::from_utf8), |a| DotAtom(a.into()))(input)
 }
 
 fn quoted_pair_smtp(input: &[u8]) -> NomResult<char> {
@@ -327,66 +501,259 @@ pub(crate) fn quoted_string<P: UTF8Policy>(input: &[u8]) -> NomResult<QuotedStri
         |qs| QuotedString(qs.into_iter().collect()))(input)
 }
 
-pub(crate) fn local_part<P: UTF8Policy>(input: &[u8]) -> NomResult<LocalPart> {
+// The empty quoted string is rejected unless allowed since it can
+// not designate a mailbox. Other failures of the parser keep their
+// own error.
+fn _non_empty_quoted<'a, O, F>(input: &'a [u8], allow_empty: bool, mut parser: F) -> NomResult<'a, O>
+    where F: FnMut(&'a [u8]) -> NomResult<'a, O>,
+{
+    let (rem, out) = parser(input)?;
+
+    if !allow_empty && input.len() - rem.len() == 2 {
+        Err(nom::Err::Error(NomError{input, kind: ErrorKind::Verify, context: Some("empty quoted local part")}))
+    } else {
+        Ok((rem, out))
+    }
+}
+
+fn _quoted_string_ref<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
+    _non_empty_quoted(input, false, recognize(delimited(tag("\""), recognize_many0(qcontent_smtp::<P>), tag("\""))))
+}
+
+fn _local_part_with<P: UTF8Policy>(input: &[u8], allow_empty: bool) -> NomResult<LocalPart> {
     alt((map(dot_string::<P>, |s| s.into()),
-         map(quoted_string::<P>, LocalPart::Quoted)))(input)
+         map(|i| _non_empty_quoted(i, allow_empty, quoted_string::<P>), LocalPart::Quoted)))(input)
+}
+
+pub(crate) fn local_part<P: UTF8Policy>(input: &[u8]) -> NomResult<LocalPart> {
+    _local_part_with::<P>(input, false)
+}
+
+// Leading zeros are rejected since they are interpreted as octal by
+// some implementations.
+pub(crate) fn _ip_int(input: &[u8]) -> NomResult<u8> {
+    map_res(map_res(verify(take_while_m_n(1, 3, is_digit), |ip: &[u8]| ip.len() == 1 || ip[0] != b'0'), str::from_utf8),
+            u8::from_str)(input)
 }
 
-fn _ip_int(input: &[u8]) -> NomResult<u8> {
-    map_res(take_while_m_n(1, 3, is_digit),
-            |ip| str::from_utf8(ip).unwrap().parse())(input)
+fn _ip_int_with<'a>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, u8> {
+    if options.allow_leading_zeros {
+        map_res(map_res(take_while_m_n(1, 3, is_digit), str::from_utf8), u8::from_str)(input)
+    } else {
+        _ip_int(input)
+    }
 }
 
-fn _ipv4_literal(input: &[u8]) -> NomResult<AddressLiteral> {
-    map(pair(_ip_int, many_m_n(3, 3, preceded(tag("."), _ip_int))),
+fn _ipv4_literal_with<'a>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, AddressLiteral> {
+    let ip_int = |i| _ip_int_with(i, options);
+
+    map(pair(ip_int, many_m_n(3, 3, preceded(tag("."), ip_int))),
         |(a, b)| (AddressLiteral::IP(Ipv4Addr::new(a, b[0], b[1], b[2]).into())))(input)
 }
 
+// Zone identifier as in RFC 6874 without percent encoding.
+fn _ipv6_zone(input: &[u8]) -> NomResult<&str> {
+    map_res(preceded(tag("%"), take_while1(|c| is_alphanumeric(c) || b"-._~".contains(&c))),
+            str::from_utf8)(input)
+}
+
 fn _ipv6_literal(input: &[u8]) -> NomResult<AddressLiteral> {
-    map_res(preceded(tag_no_case("IPv6:"), take_while1(|c| is_hex_digit(c) || c == b':' || c == b'.')),
-            |addr| Ipv6Addr::from_str(str::from_utf8(addr).unwrap()).map(|ip| AddressLiteral::IP(ip.into())))(input)
+    let addr = map_res(take_while1(|c| is_hex_digit(c) || c == b':' || c == b'.'), str::from_utf8);
+
+    preceded(tag_no_case("IPv6:"),
+             context("invalid IPv6 literal", map_res(pair(addr, opt(_ipv6_zone)),
+                 |(addr, zone)| Ipv6Addr::from_str(addr).map(|ip| match zone {
+                     Some(zone) => AddressLiteral::ZonedIPv6(ip, zone.into()),
+                     None => AddressLiteral::IP(ip.into()),
+                 }))))(input)
 }
 
 fn dcontent(input: &[u8]) -> NomResult<u8> {
     take1_filter(|c| match c { 33..=90 | 94..=126 => true, _ => false})(input)
 }
 
+/// Address literal tags registered with [IANA].
+///
+/// Tags are compared case-insensitively.
+///
+/// [IANA]: https://www.iana.org/assignments/address-literal-tags
+pub const ADDRESS_LITERAL_TAGS: &[&str] = &["IPv6"];
+
 fn general_address_literal(input: &[u8]) -> NomResult<AddressLiteral> {
-    map(separated_pair(ldh_str, tag(":"), map(recognize_many1(dcontent), |d| str::from_utf8(d).unwrap())),
-        |(tag, value)| AddressLiteral::Tagged(str::from_utf8(tag).unwrap().into(), value.into())
+    // Registered tags have a dedicated syntax.
+    let standardized_tag = map_res(verify(ldh_str, |t: &[u8]| !ADDRESS_LITERAL_TAGS.iter().any(|r| t.eq_ignore_ascii_case(r.as_bytes()))),
+                                   str::from_utf8);
+
+    map(separated_pair(standardized_tag, tag(":"), map_res(recognize_many1(dcontent), str::from_utf8)),
+        |(tag, value)| AddressLiteral::Tagged(tag.into(), value.into())
     )(input)
 }
 
+fn _inner_address_literal_with<'a>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, AddressLiteral> {
+    alt((|i| _ipv4_literal_with(i, options), _ipv6_literal, general_address_literal))(input)
+}
+
 pub(crate) fn _inner_address_literal(input: &[u8]) -> NomResult<AddressLiteral> {
-    alt((_ipv4_literal, _ipv6_literal, general_address_literal))(input)
+    _inner_address_literal_with(input, &BASE_OPTIONS)
+}
+
+// Parse the content of an address literal, explaining why and where
+// it failed.
+pub(crate) fn _checked_inner_address_literal(input: &[u8]) -> Result<AddressLiteral, AddressLiteralError> {
+    use AddressLiteralErrorKind::*;
+    let error = |kind, rem: &[u8]| AddressLiteralError{kind, offset: input.len() - rem.len()};
+
+    match _inner_address_literal(input) {
+        Ok((b"", literal)) => return Ok(literal),
+        Ok((rem, _)) => return Err(error(TrailingData, rem)),
+        Err(_) => (),
+    }
+
+    if let Ok((rem, _)) = tag_no_case::<_, _, NomError>("IPv6:")(input) {
+        return Err(error(BadIpv6, rem));
+    }
+
+    if matches!(input.first(), Some(c) if c.is_ascii_digit()) && input.iter().all(|c| c.is_ascii_digit() || *c == b'.') {
+        let mut offset = 0;
+        for octet in input.split(|c| *c == b'.').take(4) {
+            if exact!(octet, _ip_int).is_err() {
+                return Err(AddressLiteralError{kind: BadIpv4Octet, offset});
+            }
+            offset += octet.len() + 1;
+        }
+        return Err(error(BadIpv4Octet, b""));
+    }
+
+    if input.first() == Some(&b':') {
+        return Err(error(EmptyTag, input));
+    }
+
+    match terminated(ldh_str, tag(":"))(input) {
+        Ok((rem, _)) => Err(error(BadValue, rem)),
+        Err(_) => match ldh_str(input) {
+            Ok((rem, _)) => Err(error(BadTag, rem)),
+            Err(_) => Err(error(BadTag, input)),
+        }
+    }
+}
+
+fn _address_literal_with<'a>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, AddressLiteral> {
+    preceded(tag("["), context("invalid address literal",
+                               terminated(|i| _inner_address_literal_with(i, options), tag("]"))))(input)
 }
 
 pub(crate) fn address_literal(input: &[u8]) -> NomResult<AddressLiteral> {
-    delimited(tag("["), _inner_address_literal, tag("]"))(input)
+    _address_literal_with(input, &BASE_OPTIONS)
+}
+
+fn _domain_part_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, DomainPart> {
+    alt((map(|i| _domain_with::<P>(i, options), DomainPart::Domain),
+         map(|i| _address_literal_with(i, options), DomainPart::Address)))(input)
 }
 
 pub(crate) fn _domain_part<P: UTF8Policy>(input: &[u8]) -> NomResult<DomainPart> {
-    alt((map(domain::<P>, DomainPart::Domain), map(address_literal, DomainPart::Address)))(input)
+    _domain_part_with::<P>(input, &BASE_OPTIONS)
 }
 
-pub fn mailbox<P: UTF8Policy>(input: &[u8]) -> NomResult<Mailbox> {
-    map(separated_pair(local_part::<P>, tag("@"), _domain_part::<P>),
+fn _mailbox_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, Mailbox> {
+    map(separated_pair(|i| _local_part_with::<P>(i, options.allow_empty_quoted_local_part),
+                       context("expected '@'", tag("@")),
+                       context("invalid domain", |i| _domain_part_with::<P>(i, options))),
         |(lp, dp)| Mailbox(lp, dp))(input)
 }
 
-fn path<P: UTF8Policy>(input: &[u8]) -> NomResult<Path> {
-    map(delimited(
-        tag("<"),
-        pair(opt(terminated(a_d_l::<P>, tag(":"))), mailbox::<P>),
-        tag(">")),
+pub fn mailbox<P: UTF8Policy>(input: &[u8]) -> NomResult<Mailbox> {
+    _mailbox_with::<P>(input, &BASE_OPTIONS)
+}
+
+/// A mailbox borrowing its parts from the parsed input.
+///
+/// Returned by [`mailbox_ref`]. The parts can not be set directly so
+/// that they are always valid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MailboxRef<'a> {
+    local_part: &'a str,
+    domain_part: &'a str,
+}
+
+impl<'a> MailboxRef<'a> {
+    /// The local part as it appears in the input, including quotes
+    /// and escapes.
+    pub fn local_part(&self) -> &'a str {
+        self.local_part
+    }
+
+    /// The domain or address literal as it appears in the input.
+    pub fn domain_part(&self) -> &'a str {
+        self.domain_part
+    }
+}
+
+impl<'a> From<MailboxRef<'a>> for Mailbox {
+    fn from(value: MailboxRef<'a>) -> Mailbox {
+        // The parts can only be built by mailbox_ref, which validated
+        // them. Intl accepts a superset of the Legacy syntax.
+        let (_, lp) = local_part::<Intl>(value.local_part.as_bytes()).unwrap();
+        let (_, dp) = _domain_part::<Intl>(value.domain_part.as_bytes()).unwrap();
+        Mailbox(lp, dp)
+    }
+}
+
+impl<'a> Display for MailboxRef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}@{}", self.local_part, self.domain_part)
+    }
+}
+
+/// Parse a mailbox without copying its parts out of the input.
+///
+/// Accepts the same syntax as [`mailbox`] but avoids allocating for
+/// local parts and domains. Tagged and zoned IPv6 address literals
+/// still allocate during validation.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::mailbox_ref;
+/// use rustyknife::types::Mailbox;
+///
+/// let (_, mref) = mailbox_ref::<Intl>(b"\"bob smith\"@example.org").unwrap();
+///
+/// assert_eq!(mref.local_part(), "\"bob smith\"");
+/// assert_eq!(mref.domain_part(), "example.org");
+/// assert_eq!(Mailbox::from(mref).to_string(), "\"bob smith\"@example.org");
+/// ```
+pub fn mailbox_ref<P: UTF8Policy>(input: &[u8]) -> NomResult<MailboxRef> {
+    map(separated_pair(map_res(alt((_dot_string_ref::<P>, _quoted_string_ref::<P>)), str::from_utf8),
+                       tag("@"),
+                       alt((_domain_ref::<P>, map_res(recognize(address_literal), str::from_utf8)))),
+        |(local_part, domain_part)| MailboxRef{local_part, domain_part})(input)
+}
+
+fn _path_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, Path> {
+    let at_domain = |i| preceded(tag("@"), |i| _domain_with::<P>(i, options))(i);
+    let a_d_l = fold_prefix0(at_domain, preceded(tag(","), at_domain));
+
+    map(verify(delimited(
+        context("expected '<'", tag("<")),
+        pair(opt(terminated(a_d_l, tag(":"))), |i| _mailbox_with::<P>(i, options)),
+        context("expected '>'", tag(">"))),
+               |(path, _)| options.allow_source_route || path.is_none()),
         |(path, m)| Path(m, path.unwrap_or_default()))(input)
 }
 
-fn reverse_path<P: UTF8Policy>(input: &[u8]) -> NomResult<ReversePath> {
-    alt((map(path::<P>, ReversePath::Path),
+fn path<P: UTF8Policy>(input: &[u8]) -> NomResult<Path> {
+    _path_with::<P>(input, &BASE_OPTIONS)
+}
+
+fn _reverse_path_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, ReversePath> {
+    alt((map(|i| _path_with::<P>(i, options), ReversePath::Path),
          map(tag("<>"), |_| ReversePath::Null)))(input)
 }
 
+pub(crate) fn reverse_path<P: UTF8Policy>(input: &[u8]) -> NomResult<ReversePath> {
+    _reverse_path_with::<P>(input, &BASE_OPTIONS)
+}
+
 /// Parse an SMTP EHLO command.
 pub fn ehlo_command<P: UTF8Policy>(input: &[u8]) -> NomResult<DomainPart> {
     delimited(tag_no_case("EHLO "), _domain_part::<P>, crlf)(input)
@@ -400,6 +767,7 @@ pub fn helo_command<P: UTF8Policy>(input: &[u8]) -> NomResult<Domain> {
 /// Parse an SMTP MAIL FROM command.
 ///
 /// Returns a tuple with the reverse path and ESMTP parameters.
+/// Parameter values are limited to [`MAX_PARAM_VALUE_LENGTH`].
 /// # Examples
 /// ```
 /// use rustyknife::behaviour::Intl;
@@ -411,19 +779,21 @@ pub fn helo_command<P: UTF8Policy>(input: &[u8]) -> NomResult<Domain> {
 /// assert_eq!(params, [Param::new("BODY", Some("8BIT")).unwrap()]);
 /// ```
 pub fn mail_command<P: UTF8Policy>(input: &[u8]) -> NomResult<(ReversePath, Vec<Param>)> {
-    map(delimited(tag_no_case("MAIL FROM:"),
-                  pair(reverse_path::<P>, opt(preceded(tag(" "), _esmtp_params::<P>))),
-                  crlf),
-        |(addr, params)| (addr, params.unwrap_or_default()))(input)
+    _mail_command_with::<P>(input, &BASE_OPTIONS)
 }
 
-fn _forward_path<P: UTF8Policy>(input: &[u8]) -> NomResult<ForwardPath> {
+fn _forward_path_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, ForwardPath> {
     alt((map(tag_no_case("<postmaster>"), |_| ForwardPath::PostMaster(None)),
-         map(delimited(tag_no_case("<postmaster@"), domain::<P>, tag(">")), |d| ForwardPath::PostMaster(Some(d))),
-         map(path::<P>, ForwardPath::Path)
+         map(delimited(tag_no_case("<postmaster@"), |i| _domain_with::<P>(i, options), tag(">")),
+             |d| ForwardPath::PostMaster(Some(d))),
+         map(|i| _path_with::<P>(i, options), ForwardPath::Path)
     ))(input)
 }
 
+fn _forward_path<P: UTF8Policy>(input: &[u8]) -> NomResult<ForwardPath> {
+    _forward_path_with::<P>(input, &BASE_OPTIONS)
+}
+
 /// Parse an SMTP RCPT TO command.
 ///
 /// Returns a tuple with the forward path and ESMTP parameters.
@@ -438,128 +808,2120 @@ fn _forward_path<P: UTF8Policy>(input: &[u8]) -> NomResult<ForwardPath> {
 /// assert_eq!(params, [Param::new("NOTIFY", Some("NEVER")).unwrap()]);
 /// ```
 pub fn rcpt_command<P: UTF8Policy>(input: &[u8]) -> NomResult<(ForwardPath, Vec<Param>)> {
-    map(delimited(
-        tag_no_case("RCPT TO:"),
-        pair(_forward_path::<P>, opt(preceded(tag(" "), _esmtp_params::<P>))),
-        crlf,
-    ), |(path, params)| (path, params.unwrap_or_default()))(input)
+    _rcpt_command_with::<P>(input, &BASE_OPTIONS)
 }
 
-/// Parse an SMTP DATA command.
-pub fn data_command(input: &[u8]) -> NomResult<()> {
-    map(tag_no_case("DATA\r\n"), |_| ())(input)
-}
+// Options of the lenient parsers.
+const LENIENT_OPTIONS: ParseOptions = ParseOptions {
+    allow_space_after_colon: true,
+    allow_missing_angle_brackets: true,
+    allow_extra_whitespace: true,
+    ..BASE_OPTIONS
+};
 
-/// Parse an SMTP RSET command.
-pub fn rset_command(input: &[u8]) -> NomResult<()> {
-    map(tag_no_case("RSET\r\n"), |_| ())(input)
+fn _is_lenient(warnings: &[Warning]) -> bool {
+    warnings.iter().any(|w| matches!(w, Warning::SpaceAfterColon | Warning::MissingAngleBrackets | Warning::ExtraWhitespace))
 }
 
-fn _smtp_string<P: UTF8Policy>(input: &[u8]) -> NomResult<SMTPString> {
-    alt((map(atom::<P>, |a| SMTPString(str::from_utf8(a).unwrap().into())),
-         map(quoted_string::<P>, |qs| SMTPString(qs.into()))))(input)
+/// Parse an SMTP MAIL FROM command, tolerating common client mistakes.
+///
+/// Accepts addresses without angle brackets and extra whitespace
+/// after the colon and before the CRLF, as many MTAs do. The returned
+/// flag is true if the command was not conforming.
+///
+/// Same as [`mail_command_with`] with
+/// [`ParseOptions::allow_space_after_colon`],
+/// [`ParseOptions::allow_missing_angle_brackets`] and
+/// [`ParseOptions::allow_extra_whitespace`] set.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::mail_command_lenient;
+///
+/// let (_, (path, _, lenient)) = mail_command_lenient::<Intl>(b"MAIL FROM: bob@example.org \r\n").unwrap();
+/// assert_eq!(path.to_string(), "<bob@example.org>");
+/// assert!(lenient);
+///
+/// let (_, (_, _, lenient)) = mail_command_lenient::<Intl>(b"MAIL FROM:<bob@example.org>\r\n").unwrap();
+/// assert!(!lenient);
+/// ```
+pub fn mail_command_lenient<P: UTF8Policy>(input: &[u8]) -> NomResult<(ReversePath, Vec<Param>, bool)> {
+    map(|i| _mail_command_full::<P>(i, &LENIENT_OPTIONS),
+        |(path, params, warnings)| (path, params, _is_lenient(&warnings)))(input)
 }
 
-/// Parse an SMTP NOOP command.
-pub fn noop_command<P: UTF8Policy>(input: &[u8]) -> NomResult<Option<SMTPString>> {
-    delimited(tag_no_case("NOOP"),
-              opt(preceded(tag(" "), _smtp_string::<P>)),
-              crlf)(input)
+/// Parse an SMTP RCPT TO command, tolerating common client mistakes.
+///
+/// See [`mail_command_lenient`]. A bare `"postmaster"` is also
+/// accepted.
+pub fn rcpt_command_lenient<P: UTF8Policy>(input: &[u8]) -> NomResult<(ForwardPath, Vec<Param>, bool)> {
+    map(|i| _rcpt_command_full::<P>(i, &LENIENT_OPTIONS),
+        |(path, params, warnings)| (path, params, _is_lenient(&warnings)))(input)
 }
 
-/// Parse an SMTP QUIT command.
-pub fn quit_command(input: &[u8]) -> NomResult<()> {
-    map(tag_no_case("QUIT\r\n"), |_| ())(input)
+/// Discouraged construct reported by [`mail_command_with`] and
+/// [`rcpt_command_with`].
+///
+/// These are accepted by [RFC 5321] or by the enabled
+/// [`ParseOptions`] but should not be generated.
+///
+/// [RFC 5321]: https://tools.ietf.org/html/rfc5321
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Warning {
+    /// The path contains an obsolete source route.
+    SourceRoute,
+    /// The local part is quoted although it does not need to be.
+    UnnecessaryQuoting,
+    /// The last label of the domain is all digits, such as in
+    /// `"192.0.2.1"`. An IP address must be written as an address
+    /// literal in brackets.
+    NumericTopLevelDomain,
+    /// Spaces separate the colon of the verb from the path. Only
+    /// accepted with [`ParseOptions::allow_space_after_colon`].
+    SpaceAfterColon,
+    /// The path is not enclosed in angle brackets. Only accepted with
+    /// [`ParseOptions::allow_missing_angle_brackets`].
+    MissingAngleBrackets,
+    /// Extra whitespace precedes the parameters or the CRLF. Only
+    /// accepted with [`ParseOptions::allow_extra_whitespace`].
+    ExtraWhitespace,
 }
 
-/// Parse an SMTP VRFY command.
-pub fn vrfy_command<P: UTF8Policy>(input: &[u8]) -> NomResult<SMTPString> {
-    delimited(tag_no_case("VRFY "), _smtp_string::<P>, crlf)(input)
+impl Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::SourceRoute => write!(f, "source route present"),
+            Warning::UnnecessaryQuoting => write!(f, "unnecessary quoting in local part"),
+            Warning::NumericTopLevelDomain => write!(f, "numeric top-level domain"),
+            Warning::SpaceAfterColon => write!(f, "space after colon"),
+            Warning::MissingAngleBrackets => write!(f, "missing angle brackets"),
+            Warning::ExtraWhitespace => write!(f, "extra whitespace"),
+        }
+    }
 }
 
-/// Parse an SMTP EXPN command.
-pub fn expn_command<P: UTF8Policy>(input: &[u8]) -> NomResult<SMTPString> {
-    delimited(tag_no_case("EXPN "), _smtp_string::<P>, crlf)(input)
+fn _path_warnings(path: &Path) -> Vec<Warning> {
+    let mut out = Vec::new();
+
+    if !path.1.is_empty() {
+        out.push(Warning::SourceRoute);
+    }
+    if let LocalPart::Quoted(_) = path.0.local_part() {
+        if let LocalPart::DotAtom(_) = path.0.local_part().clone().simplify() {
+            out.push(Warning::UnnecessaryQuoting);
+        }
+    }
+    if let DomainPart::Domain(d) = path.0.domain_part() {
+        if _is_numeric_tld(&d.0) {
+            out.push(Warning::NumericTopLevelDomain);
+        }
+    }
+    out
 }
 
-/// Parse an SMTP HELP command.
-pub fn help_command<P: UTF8Policy>(input: &[u8]) -> NomResult<Option<SMTPString>> {
-    delimited(tag_no_case("HELP"),
-              opt(preceded(tag(" "), _smtp_string::<P>)),
-              crlf)(input)
+/// Parse an SMTP MAIL FROM command and report discouraged constructs.
+///
+/// Same as [`mail_command`] with a list of the [`Warning`]s found in
+/// the reverse path. See [`mail_command_with`] to also choose the
+/// [`ParseOptions`].
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{mail_command_with_warnings, Warning};
+///
+/// let (_, (_, _, warnings)) = mail_command_with_warnings::<Intl>(b"MAIL FROM:<@a.example:\"bob\"@example.org>\r\n").unwrap();
+///
+/// assert_eq!(warnings, [Warning::SourceRoute, Warning::UnnecessaryQuoting]);
+/// ```
+pub fn mail_command_with_warnings<P: UTF8Policy>(input: &[u8]) -> NomResult<(ReversePath, Vec<Param>, Vec<Warning>)> {
+    _mail_command_full::<P>(input, &BASE_OPTIONS)
 }
 
-/// The base SMTP command set
+/// Parse an SMTP RCPT TO command and report discouraged constructs.
 ///
-/// The data on each variant corresponds to the return type of the
-/// *_command functions.
-#[derive(Debug)]
-#[allow(missing_docs)]
-pub enum Command {
-    EHLO(DomainPart),
-    HELO(Domain),
-    MAIL(ReversePath, Vec<Param>),
-    RCPT(ForwardPath, Vec<Param>),
-    DATA,
-    RSET,
-    NOOP(Option<SMTPString>),
-    QUIT,
-    VRFY(SMTPString),
-    EXPN(SMTPString),
-    HELP(Option<SMTPString>),
+/// See [`mail_command_with_warnings`].
+pub fn rcpt_command_with_warnings<P: UTF8Policy>(input: &[u8]) -> NomResult<(ForwardPath, Vec<Param>, Vec<Warning>)> {
+    _rcpt_command_full::<P>(input, &BASE_OPTIONS)
 }
 
-/// Parse any basic SMTP command.
-pub fn command<P: UTF8Policy>(input: &[u8]) -> NomResult<Command> {
-    alt((
-        map(ehlo_command::<P>, Command::EHLO),
-        map(helo_command::<P>, Command::HELO),
-        map(mail_command::<P>, |(a, p)| Command::MAIL(a, p)),
-        map(rcpt_command::<P>, |(a, p)| Command::RCPT(a, p)),
-        map(data_command, |_| Command::DATA),
-        map(rset_command, |_| Command::RSET),
-        map(noop_command::<P>, Command::NOOP),
-        map(quit_command, |_| Command::QUIT),
-        map(vrfy_command::<P>, Command::VRFY),
-        map(expn_command::<P>, Command::EXPN),
-        map(help_command::<P>, Command::HELP),
-    ))(input)
+fn _format_command(verb: &str, path: &dyn Display, params: &[Param]) -> String {
+    if params.is_empty() {
+        format!("{}{}\r\n", verb, path)
+    } else {
+        format!("{}{} {}\r\n", verb, path, Params(params))
+    }
 }
 
-/// Validates an email address.
+/// Format an SMTP MAIL FROM command.
 ///
-/// Does not accept the empty address.
+/// This is the inverse of [`mail_command`], including the trailing CRLF.
 /// # Examples
 /// ```
 /// use rustyknife::behaviour::Intl;
-/// use rustyknife::rfc5321::validate_address;
+/// use rustyknife::rfc5321::{format_mail_command, mail_command, Param, ReversePath};
 ///
-/// assert!(validate_address::<Intl>(b"bob@example.org"));
-/// assert!(validate_address::<Intl>(b"bob@[aoeu:192.0.2.1]"));
-/// assert!(!validate_address::<Intl>(b""));
+/// let params = [Param::new("BODY", Some("8BITMIME")).unwrap(), Param::new("SMTPUTF8", None).unwrap()];
+/// let cmd = format_mail_command(&ReversePath::Null, &params);
+///
+/// assert_eq!(cmd, "MAIL FROM:<> BODY=8BITMIME SMTPUTF8\r\n");
+/// assert_eq!(mail_command::<Intl>(cmd.as_bytes()).unwrap().1, (ReversePath::Null, params.to_vec()));
 /// ```
-pub fn validate_address<P: UTF8Policy>(i: &[u8]) -> bool {
-    exact!(i, mailbox::<P>).is_ok()
+pub fn format_mail_command(path: &ReversePath, params: &[Param]) -> String {
+    _format_command("MAIL FROM:", path, params)
 }
 
-/// Parse a STARTTLS command from RFC 3207
-pub fn starttls_command(input: &[u8]) -> NomResult<()> {
-    map(tag_no_case("STARTTLS\r\n"), |_| ())(input)
+/// Format an SMTP RCPT TO command.
+///
+/// This is the inverse of [`rcpt_command`], including the trailing CRLF.
+pub fn format_rcpt_command(path: &ForwardPath, params: &[Param]) -> String {
+    _format_command("RCPT TO:", path, params)
 }
 
-/// Parse a BDAT command from RFC 3030
-pub fn bdat_command(input: &[u8]) -> NomResult<(u64, bool)> {
-    terminated(
-        pair(
-            preceded(tag_no_case("BDAT "), bdat_chunk_size),
+/// Maximum length of a command line in octets including the CRLF as
+/// per [RFC 5321].
+///
+/// [RFC 5321]: https://tools.ietf.org/html/rfc5321#section-4.5.3.1.4
+pub const MAX_COMMAND_LINE_LENGTH: usize = 512;
+
+/// Error returned by [`validate_command_line`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineTooLong {
+    /// Length of the line seen so far in octets, including the CRLF
+    /// if it was found.
+    pub length: usize,
+    /// The limit that was exceeded.
+    pub limit: usize,
+}
+
+impl LineTooLong {
+    /// Conventional SMTP reply code and text for this error.
+    ///
+    /// See [RFC 5321 section 4.5.3.1.4].
+    ///
+    /// [RFC 5321 section 4.5.3.1.4]: https://tools.ietf.org/html/rfc5321#section-4.5.3.1.4
+    pub fn suggested_response(&self) -> (u16, &'static str) {
+        (500, "Line too long")
+    }
+}
+
+impl Display for LineTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "command line of {} octets exceeds {}", self.length, self.limit)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LineTooLong {}
+
+/// Check that the first command line in `input` fits in
+/// [`MAX_COMMAND_LINE_LENGTH`].
+///
+/// The line ends at the first CRLF. If no CRLF is found, the whole
+/// input is considered part of the line so that a partial line
+/// that is already too long can be rejected early.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{validate_command_line, MAX_COMMAND_LINE_LENGTH};
+///
+/// assert!(validate_command_line(b"NOOP\r\nQUIT\r\n").is_ok());
+///
+/// let long = vec![b'a'; MAX_COMMAND_LINE_LENGTH - 1];
+/// assert_eq!(validate_command_line(&long).unwrap_err().length, 511);
+/// ```
+pub fn validate_command_line(input: &[u8]) -> Result<(), LineTooLong> {
+    validate_command_line_with(input, MAX_COMMAND_LINE_LENGTH)
+}
+
+/// Check that the first command line in `input` fits in `limit`
+/// octets including the CRLF.
+///
+/// Use this when an extension raises the limit, such as the 500
+/// additional octets allowed on MAIL commands by [RFC 4954].
+///
+/// [RFC 4954]: https://tools.ietf.org/html/rfc4954#section-5
+pub fn validate_command_line_with(input: &[u8], limit: usize) -> Result<(), LineTooLong> {
+    let (length, pending) = match input.windows(2).position(|w| w == b"\r\n") {
+        Some(pos) => (pos + 2, 0),
+        None if input.last() == Some(&b'\r') => (input.len(), 1),
+        None => (input.len(), 2),
+    };
+
+    // A partial line must leave room for the rest of the CRLF.
+    if length + pending > limit {
+        Err(LineTooLong{length, limit})
+    } else {
+        Ok(())
+    }
+}
+
+/// The part of a command that failed to parse.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SMTPParseErrorKind {
+    /// The command verb is not recognized.
+    UnknownCommand,
+    /// The reverse or forward path is invalid.
+    InvalidPath,
+    /// The null path was given where a mailbox is required.
+    NullForwardPath,
+    /// An ESMTP parameter or the space preceding it is invalid.
+    InvalidParameter,
+    /// Unexpected data follows an otherwise valid command.
+    TrailingData,
+    /// Any other syntax error.
+    Syntax,
+}
+
+/// Error returned by the detailed command parsers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SMTPParseError {
+    /// The part of the command that failed.
+    pub kind: SMTPParseErrorKind,
+    /// Offset of the byte where parsing failed.
+    pub offset: usize,
+    /// Short description of the failure.
+    pub reason: &'static str,
+}
+
+impl SMTPParseError {
+    /// Conventional SMTP reply code and text for this error.
+    ///
+    /// An unknown verb is a 500 and the null path given to RCPT is a
+    /// 553. Any other syntax error in the arguments is a 501.
+    /// # Examples
+    /// ```
+    /// use rustyknife::behaviour::Intl;
+    /// use rustyknife::rfc5321::rcpt_command_detailed;
+    ///
+    /// let err = rcpt_command_detailed::<Intl>(b"RCPT TO:<bob>\r\n").unwrap_err();
+    ///
+    /// assert_eq!(err.suggested_response(), (501, "Syntax error in parameters or arguments"));
+    /// ```
+    pub fn suggested_response(&self) -> (u16, &'static str) {
+        match self.kind {
+            SMTPParseErrorKind::UnknownCommand => (500, "Syntax error, command unrecognized"),
+            SMTPParseErrorKind::NullForwardPath => (553, "Requested action not taken: mailbox name not allowed"),
+            _ => (501, "Syntax error in parameters or arguments"),
+        }
+    }
+}
+
+impl Display for SMTPParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.reason, self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SMTPParseError {}
+
+// Parse the whole input, reporting where trailing data starts.
+pub(crate) fn _exact<'a, O, F>(input: &'a [u8], parser: F) -> Result<O, SMTPParseError>
+    where F: Fn(&'a [u8]) -> NomResult<'a, O>,
+{
+    match parser(input) {
+        Ok((b"", out)) => Ok(out),
+        Ok((rem, _)) => Err(SMTPParseError{kind: SMTPParseErrorKind::TrailingData, offset: input.len() - rem.len(),
+                                           reason: "trailing data"}),
+        Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) =>
+            Err(SMTPParseError{kind: SMTPParseErrorKind::Syntax, offset: e.offset(input), reason: e.reason()}),
+        Err(nom::Err::Incomplete(_)) =>
+            Err(SMTPParseError{kind: SMTPParseErrorKind::Syntax, offset: input.len(), reason: "incomplete input"}),
+    }
+}
+
+// Parse a MAIL or RCPT command step by step to find out where it
+// failed. Must accept the same syntax as the regular parsers.
+fn _detailed_command<P, O, F>(input: &[u8], verb: CommandKind, keyword: (&'static str, &'static str),
+                              path: F, path_reason: &'static str)
+                              -> Result<(O, Vec<Param>), SMTPParseError>
+    where P: UTF8Policy,
+          F: Fn(&[u8]) -> NomResult<O>,
+{
+    use self::SMTPParseErrorKind::*;
+    let error = |rem: &[u8], kind, reason| SMTPParseError{kind, offset: input.len() - rem.len(), reason};
+
+    let (rem, _) = verify(command_kind, |kind| *kind == verb)(input)
+        .map_err(|_| error(input, UnknownCommand, "unknown command"))?;
+    let (rem, _) = tag_no_case::<_, _, NomError>(keyword.0)(rem)
+        .map_err(|_| error(rem, Syntax, keyword.1))?;
+    let (mut rem, path) = path(rem).map_err(|_| error(rem, InvalidPath, path_reason))?;
+    let mut params = Vec::new();
+
+    let rem = loop {
+        if let Ok((rem, _)) = crlf(rem) {
+            break rem;
+        }
+
+        let sep = if params.is_empty() { recognize(tag(" "))(rem) } else { recognize(many1(wsp))(rem) };
+        let (next, _) = sep.map_err(|_| error(rem, InvalidParameter, "expected space or CRLF"))?;
+        let (next, param) = esmtp_param::<P>(next)
+            .map_err(|_| error(next, InvalidParameter, "invalid ESMTP parameter"))?;
+
+        params.push(param);
+        rem = next;
+    };
+
+    if rem.is_empty() {
+        Ok((path, params))
+    } else {
+        Err(error(rem, TrailingData, "trailing data after CRLF"))
+    }
+}
+
+/// Parse an SMTP MAIL FROM command, reporting the position of errors.
+///
+/// The whole input must consist of a single command.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::mail_command_detailed;
+///
+/// let err = mail_command_detailed::<Intl>(b"MAIL FROM:<bob@example.org> BODY=\r\n").unwrap_err();
+///
+/// assert_eq!(err.offset, 32);
+/// assert_eq!(err.to_string(), "expected space or CRLF at offset 32");
+/// ```
+pub fn mail_command_detailed<P: UTF8Policy>(input: &[u8]) -> Result<(ReversePath, Vec<Param>), SMTPParseError> {
+    _detailed_command::<P, _, _>(input, CommandKind::MAIL, (" FROM:", "expected FROM:"), reverse_path::<P>,
+                                 "invalid reverse path")
+}
+
+/// Parse an SMTP RCPT TO command, reporting the position of errors.
+///
+/// The whole input must consist of a single command.
+pub fn rcpt_command_detailed<P: UTF8Policy>(input: &[u8]) -> Result<(ForwardPath, Vec<Param>), SMTPParseError> {
+    _detailed_command::<P, _, _>(input, CommandKind::RCPT, (" TO:", "expected TO:"), _forward_path::<P>,
+                                 "invalid forward path").map_err(|err| {
+        // The null path is only valid as a reverse path.
+        match input.get(err.offset..) {
+            Some(rem) if err.kind == SMTPParseErrorKind::InvalidPath && rem.starts_with(b"<>") =>
+                SMTPParseError{kind: SMTPParseErrorKind::NullForwardPath, reason: "null forward path", ..err},
+            _ => err,
+        }
+    })
+}
+
+/// Maximum length of an ESMTP parameter value in octets.
+///
+/// No single value can exceed the [`MAX_COMMAND_LINE_LENGTH`] of a
+/// whole command line without its CRLF. Longer values are rejected
+/// by all command parsers unless
+/// [`ParseOptions::max_param_value_length`] is raised.
+pub const MAX_PARAM_VALUE_LENGTH: usize = MAX_COMMAND_LINE_LENGTH - 2;
+
+/// Parsing strictness for [`mail_command_with`] and [`rcpt_command_with`].
+///
+/// The default only accepts what [RFC 5321] allows. Each field relaxes
+/// or tightens one check to accommodate real-world clients.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{mail_command_with, ParseOptions};
+///
+/// let input = b"MAIL FROM:<bob@[010.0.0.1]>\r\n";
+/// assert!(mail_command_with(input, &ParseOptions::default()).is_err());
+///
+/// let options = ParseOptions{allow_leading_zeros: true, ..Default::default()};
+/// let (_, (path, _, _)) = mail_command_with(input, &options).unwrap();
+/// assert_eq!(path.to_string(), "<bob@[10.0.0.1]>");
+/// ```
+///
+/// [RFC 5321]: https://tools.ietf.org/html/rfc5321
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseOptions {
+    /// Accept UTF-8 in local parts, domains and parameter values as
+    /// per [RFC 6531]. Equivalent to parsing with [`Intl`] instead of
+    /// [`Legacy`].
+    ///
+    /// [RFC 6531]: https://tools.ietf.org/html/rfc6531
+    pub utf8: bool,
+    /// Maximum length of a domain label in octets. Internationalized
+    /// labels are measured in their ASCII form.
+    pub max_label_length: usize,
+    /// Maximum length of a domain in octets. Internationalized domains
+    /// are measured in their ASCII form.
+    pub max_domain_length: usize,
+    /// Accept IPv4 address literal octets with leading zeros. They are
+    /// always read as decimal.
+    pub allow_leading_zeros: bool,
+    /// Accept source routes in paths. Servers are required to accept
+    /// them, even if they are ignored.
+    pub allow_source_route: bool,
+    /// Maximum length of an ESMTP parameter value in octets. Longer
+    /// values are rejected before being copied out of the input.
+    pub max_param_value_length: usize,
+    /// Accept `""` as a local part. The syntax allows it but it can
+    /// not designate a mailbox.
+    pub allow_empty_quoted_local_part: bool,
+    /// Accept domains whose last label is all digits such as
+    /// `"example.123"` or an unbracketed `"192.0.2.1"`. The syntax
+    /// allows them but top-level domains are never numeric.
+    pub allow_numeric_tld: bool,
+    /// Accept spaces between the colon of `"MAIL FROM:"` or
+    /// `"RCPT TO:"` and the path, as sent by some legacy clients.
+    /// They are reported as [`Warning::SpaceAfterColon`].
+    pub allow_space_after_colon: bool,
+    /// Accept a path without angle brackets such as
+    /// `"MAIL FROM:bob@example.org"`, or a bare `"postmaster"` in
+    /// RCPT TO. It is reported as [`Warning::MissingAngleBrackets`].
+    pub allow_missing_angle_brackets: bool,
+    /// Accept several spaces or tabs before the parameters and
+    /// trailing ones before the CRLF. They are reported as
+    /// [`Warning::ExtraWhitespace`].
+    pub allow_extra_whitespace: bool,
+    /// Require the CRLF at the end of the command. When false, a
+    /// command line without it is also accepted at the end of the
+    /// input.
+    pub require_crlf: bool,
+}
+
+// Options of the parsers that take none, such as mail_command. Their
+// UTF-8 syntax is selected by the policy instead.
+const BASE_OPTIONS: ParseOptions = ParseOptions {
+    utf8: false,
+    max_label_length: MAX_LABEL_LENGTH,
+    max_domain_length: MAX_DOMAIN_LENGTH,
+    allow_leading_zeros: false,
+    allow_source_route: true,
+    max_param_value_length: MAX_PARAM_VALUE_LENGTH,
+    allow_empty_quoted_local_part: false,
+    allow_numeric_tld: true,
+    allow_space_after_colon: false,
+    allow_missing_angle_brackets: false,
+    allow_extra_whitespace: false,
+    require_crlf: true,
+};
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        BASE_OPTIONS
+    }
+}
+
+// Returns true if spaces were skipped.
+fn _space_after_colon_with<'a>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, bool> {
+    if options.allow_space_after_colon {
+        map(many0(wsp), |spaces| !spaces.is_empty())(input)
+    } else {
+        Ok((input, false))
+    }
+}
+
+fn _crlf_with<'a>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, ()> {
+    if options.require_crlf {
+        map(context("expected CRLF", crlf), |_| ())(input)
+    } else {
+        map(context("expected CRLF", alt((crlf, eof))), |_| ())(input)
+    }
+}
+
+// Also returns true if the bare form without angle brackets matched.
+fn _bracketed_with<'a, O, F, G>(input: &'a [u8], options: &ParseOptions, path: F, bare: G) -> NomResult<'a, (O, bool)>
+    where F: FnMut(&'a [u8]) -> NomResult<'a, O>,
+          G: FnMut(&'a [u8]) -> NomResult<'a, O>,
+{
+    if options.allow_missing_angle_brackets {
+        alt((map(path, |p| (p, false)), map(bare, |p| (p, true))))(input)
+    } else {
+        map(path, |p| (p, false))(input)
+    }
+}
+
+// Parameters up to and including the CRLF. Also returns true if extra
+// whitespace was skipped.
+fn _params_crlf_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, (Vec<Param>, bool)> {
+    let params = |i| _esmtp_params_with::<P>(i, options);
+
+    if options.allow_extra_whitespace {
+        map(tuple((opt(pair(many1(wsp), params)), many0(wsp), |i| _crlf_with(i, options))),
+            |(params, trailing, _)| {
+                let extra = !trailing.is_empty() || params.as_ref().map_or(false, |(spaces, _)| *spaces != b" ");
+                (params.map(|(_, p)| p).unwrap_or_default(), extra)
+            })(input)
+    } else {
+        map(terminated(opt(preceded(tag(" "), params)), |i| _crlf_with(i, options)),
+            |params| (params.unwrap_or_default(), false))(input)
+    }
+}
+
+fn _command_warnings(spaced: bool, bare: bool, extra: bool, path: Option<&Path>) -> Vec<Warning> {
+    let mut out = Vec::new();
+
+    if spaced {
+        out.push(Warning::SpaceAfterColon);
+    }
+    if bare {
+        out.push(Warning::MissingAngleBrackets);
+    }
+    if extra {
+        out.push(Warning::ExtraWhitespace);
+    }
+    out.extend(path.map(_path_warnings).unwrap_or_default());
+    out
+}
+
+fn _mail_command_full<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, (ReversePath, Vec<Param>, Vec<Warning>)> {
+    let path = |i| _reverse_path_with::<P>(i, options);
+    let bare = |i| map(|i| _mailbox_with::<P>(i, options), |m| ReversePath::Path(Path(m, Vec::new())))(i);
+
+    map(tuple((preceded(context("expected MAIL FROM:", tag_no_case("MAIL FROM:")), |i| _space_after_colon_with(i, options)),
+               move |i| _bracketed_with(i, options, path, bare),
+               |i| _params_crlf_with::<P>(i, options))),
+        |(spaced, (path, bare), (params, extra))| {
+            let warnings = match &path {
+                ReversePath::Path(p) => _command_warnings(spaced, bare, extra, Some(p)),
+                ReversePath::Null => _command_warnings(spaced, bare, extra, None),
+            };
+            (path, params, warnings)
+        })(input)
+}
+
+fn _mail_command_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, (ReversePath, Vec<Param>)> {
+    map(|i| _mail_command_full::<P>(i, options), |(path, params, _)| (path, params))(input)
+}
+
+fn _rcpt_command_full<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, (ForwardPath, Vec<Param>, Vec<Warning>)> {
+    let path = |i| _forward_path_with::<P>(i, options);
+    let bare = |i| alt((map(|i| _mailbox_with::<P>(i, options), |m| ForwardPath::Path(Path(m, Vec::new()))),
+                        map(tag_no_case("postmaster"), |_| ForwardPath::PostMaster(None))))(i);
+
+    map(tuple((preceded(context("expected RCPT TO:", tag_no_case("RCPT TO:")), |i| _space_after_colon_with(i, options)),
+               move |i| _bracketed_with(i, options, path, bare),
+               |i| _params_crlf_with::<P>(i, options))),
+        |(spaced, (path, bare), (params, extra))| {
+            let warnings = match &path {
+                ForwardPath::Path(p) => _command_warnings(spaced, bare, extra, Some(p)),
+                ForwardPath::PostMaster(_) => _command_warnings(spaced, bare, extra, None),
+            };
+            (path, params, warnings)
+        })(input)
+}
+
+fn _rcpt_command_with<'a, P: UTF8Policy>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, (ForwardPath, Vec<Param>)> {
+    map(|i| _rcpt_command_full::<P>(i, options), |(path, params, _)| (path, params))(input)
+}
+
+/// Parse an SMTP MAIL FROM command with configurable strictness.
+///
+/// Returns a tuple with the reverse path, the ESMTP parameters and
+/// the [`Warning`]s for discouraged constructs, including those
+/// tolerated by the options. With the default options this accepts
+/// the same syntax as [`mail_command`] with [`Legacy`].
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{mail_command_with, ParseOptions, Warning};
+///
+/// let options = ParseOptions{allow_space_after_colon: true, ..Default::default()};
+/// let (_, (_, _, warnings)) = mail_command_with(b"MAIL FROM: <bob@example.org>\r\n", &options).unwrap();
+///
+/// assert_eq!(warnings, [Warning::SpaceAfterColon]);
+/// ```
+pub fn mail_command_with<'a>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, (ReversePath, Vec<Param>, Vec<Warning>)> {
+    if options.utf8 {
+        _mail_command_full::<Intl>(input, options)
+    } else {
+        _mail_command_full::<Legacy>(input, options)
+    }
+}
+
+/// Parse an SMTP RCPT TO command with configurable strictness.
+///
+/// See [`mail_command_with`].
+pub fn rcpt_command_with<'a>(input: &'a [u8], options: &ParseOptions) -> NomResult<'a, (ForwardPath, Vec<Param>, Vec<Warning>)> {
+    if options.utf8 {
+        _rcpt_command_full::<Intl>(input, options)
+    } else {
+        _rcpt_command_full::<Legacy>(input, options)
+    }
+}
+
+/// Parse an SMTP DATA command.
+pub fn data_command(input: &[u8]) -> NomResult<()> {
+    map(tag_no_case("DATA\r\n"), |_| ())(input)
+}
+
+/// Parse an SMTP RSET command.
+pub fn rset_command(input: &[u8]) -> NomResult<()> {
+    map(tag_no_case("RSET\r\n"), |_| ())(input)
+}
+
+fn _smtp_string<P: UTF8Policy>(input: &[u8]) -> NomResult<SMTPString> {
+    alt((map(map_res(atom::<P>, str::from_utf8), |a| SMTPString(a.into())),
+         map(quoted_string::<P>, |qs| SMTPString(qs.into()))))(input)
+}
+
+/// Parse an SMTP NOOP command.
+pub fn noop_command<P: UTF8Policy>(input: &[u8]) -> NomResult<Option<SMTPString>> {
+    delimited(tag_no_case("NOOP"),
+              opt(preceded(tag(" "), _smtp_string::<P>)),
+              crlf)(input)
+}
+
+/// Parse an SMTP QUIT command.
+pub fn quit_command(input: &[u8]) -> NomResult<()> {
+    map(tag_no_case("QUIT\r\n"), |_| ())(input)
+}
+
+/// Parse an SMTP VRFY command.
+///
+/// The argument is an atom or a quoted string, which is unquoted.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::vrfy_command;
+///
+/// let (_, user) = vrfy_command::<Intl>(b"vrfy \"John Smith\"\r\n").unwrap();
+///
+/// assert_eq!(&*user, "John Smith");
+/// ```
+pub fn vrfy_command<P: UTF8Policy>(input: &[u8]) -> NomResult<SMTPString> {
+    delimited(tag_no_case("VRFY "), _smtp_string::<P>, crlf)(input)
+}
+
+/// Parse an SMTP EXPN command.
+pub fn expn_command<P: UTF8Policy>(input: &[u8]) -> NomResult<SMTPString> {
+    delimited(tag_no_case("EXPN "), _smtp_string::<P>, crlf)(input)
+}
+
+/// Parse an SMTP HELP command.
+///
+/// The optional topic is an atom or a quoted string, which is
+/// unquoted.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::help_command;
+///
+/// assert_eq!(help_command::<Intl>(b"HELP\r\n").unwrap().1, None);
+///
+/// let (_, topic) = help_command::<Intl>(b"help MAIL\r\n").unwrap();
+/// assert_eq!(topic.as_deref(), Some("MAIL"));
+/// ```
+pub fn help_command<P: UTF8Policy>(input: &[u8]) -> NomResult<Option<SMTPString>> {
+    delimited(tag_no_case("HELP"),
+              opt(preceded(tag(" "), _smtp_string::<P>)),
+              crlf)(input)
+}
+
+/// Argument of an [RFC 1985] ETRN command.
+///
+/// [RFC 1985]: https://tools.ietf.org/html/rfc1985
+#[derive(Clone, Debug, PartialEq)]
+pub enum EtrnArg {
+    /// Dequeue mail for this domain.
+    Domain(Domain),
+    /// Dequeue mail for this domain and its subdomains, requested with
+    /// an `"@"` prefix.
+    Subdomains(Domain),
+    /// Dequeue a named queue, requested with a `"#"` prefix.
+    Queue(String),
+}
+
+/// Parse an SMTP ETRN command.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{etrn_command, EtrnArg};
+///
+/// let (_, arg) = etrn_command::<Intl>(b"ETRN @example.org\r\n").unwrap();
+/// assert!(matches!(arg, EtrnArg::Subdomains(d) if d.to_string() == "example.org"));
+///
+/// let (_, arg) = etrn_command::<Intl>(b"ETRN #queue1\r\n").unwrap();
+/// assert_eq!(arg, EtrnArg::Queue("queue1".into()));
+/// ```
+pub fn etrn_command<P: UTF8Policy>(input: &[u8]) -> NomResult<EtrnArg> {
+    delimited(tag_no_case("ETRN "),
+              alt((map(preceded(tag("@"), domain::<P>), EtrnArg::Subdomains),
+                   map(preceded(tag("#"), map_res(atom::<P>, str::from_utf8)), |q| EtrnArg::Queue(q.into())),
+                   map(domain::<P>, EtrnArg::Domain))),
+              crlf)(input)
+}
+
+fn _reverse_path_command<'a, P: UTF8Policy>(input: &'a [u8], verb: &'static str) -> NomResult<'a, (ReversePath, Vec<Param>)> {
+    map(delimited(tag_no_case(verb),
+                  pair(reverse_path::<P>, opt(preceded(tag(" "), _esmtp_params::<P>))),
+                  context("expected CRLF", crlf)),
+        |(addr, params)| (addr, params.unwrap_or_default()))(input)
+}
+
+/// Parse an obsolete SEND FROM command from [RFC 821].
+///
+/// The arguments are the same as [`mail_command`].
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::send_command;
+///
+/// let (_, (rp, _)) = send_command::<Intl>(b"SEND FROM:<bob@example.org>\r\n").unwrap();
+/// assert_eq!(rp.to_string(), "<bob@example.org>");
+/// ```
+///
+/// [RFC 821]: https://tools.ietf.org/html/rfc821#section-3.4
+pub fn send_command<P: UTF8Policy>(input: &[u8]) -> NomResult<(ReversePath, Vec<Param>)> {
+    _reverse_path_command::<P>(input, "SEND FROM:")
+}
+
+/// Parse an obsolete SOML FROM command from RFC 821.
+///
+/// The arguments are the same as [`mail_command`].
+pub fn soml_command<P: UTF8Policy>(input: &[u8]) -> NomResult<(ReversePath, Vec<Param>)> {
+    _reverse_path_command::<P>(input, "SOML FROM:")
+}
+
+/// Parse an obsolete SAML FROM command from RFC 821.
+///
+/// The arguments are the same as [`mail_command`].
+pub fn saml_command<P: UTF8Policy>(input: &[u8]) -> NomResult<(ReversePath, Vec<Param>)> {
+    _reverse_path_command::<P>(input, "SAML FROM:")
+}
+
+/// Parse an obsolete TURN command from RFC 821.
+///
+/// The command takes no arguments.
+pub fn turn_command(input: &[u8]) -> NomResult<()> {
+    map(tag_no_case("TURN\r\n"), |_| ())(input)
+}
+
+/// The base SMTP command set
+///
+/// The data on each variant corresponds to the return type of the
+/// *_command functions. Parsing with [`FromStr`] or [`TryFrom`]
+/// expects a single complete command line including the CRLF.
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum Command {
+    EHLO(DomainPart),
+    HELO(Domain),
+    MAIL(ReversePath, Vec<Param>),
+    RCPT(ForwardPath, Vec<Param>),
+    DATA,
+    RSET,
+    NOOP(Option<SMTPString>),
+    QUIT,
+    VRFY(SMTPString),
+    EXPN(SMTPString),
+    HELP(Option<SMTPString>),
+    STARTTLS,
+    BDAT(u64, bool),
+    AUTH(AuthCommand),
+    ETRN(EtrnArg),
+    SEND(ReversePath, Vec<Param>),
+    SOML(ReversePath, Vec<Param>),
+    SAML(ReversePath, Vec<Param>),
+    TURN,
+}
+
+impl FromStr for Command {
+    type Err = SMTPParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_command::<Intl>(s.as_bytes())
+    }
+}
+
+impl TryFrom<&[u8]> for Command {
+    type Error = SMTPParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        parse_command::<Intl>(value)
+    }
+}
+
+impl TryFrom<&str> for Command {
+    type Error = SMTPParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        parse_command::<Intl>(value.as_bytes())
+    }
+}
+
+impl Command {
+    /// Returns the kind of this command.
+    pub fn kind(&self) -> CommandKind {
+        match self {
+            Command::EHLO(_) => CommandKind::EHLO,
+            Command::HELO(_) => CommandKind::HELO,
+            Command::MAIL(_, _) => CommandKind::MAIL,
+            Command::RCPT(_, _) => CommandKind::RCPT,
+            Command::DATA => CommandKind::DATA,
+            Command::RSET => CommandKind::RSET,
+            Command::NOOP(_) => CommandKind::NOOP,
+            Command::QUIT => CommandKind::QUIT,
+            Command::VRFY(_) => CommandKind::VRFY,
+            Command::EXPN(_) => CommandKind::EXPN,
+            Command::HELP(_) => CommandKind::HELP,
+            Command::STARTTLS => CommandKind::STARTTLS,
+            Command::BDAT(_, _) => CommandKind::BDAT,
+            Command::AUTH(_) => CommandKind::AUTH,
+            Command::ETRN(_) => CommandKind::ETRN,
+            Command::SEND(_, _) => CommandKind::SEND,
+            Command::SOML(_, _) => CommandKind::SOML,
+            Command::SAML(_, _) => CommandKind::SAML,
+            Command::TURN => CommandKind::TURN,
+        }
+    }
+}
+
+/// The verb of a [`Command`] without its arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum CommandKind {
+    EHLO,
+    HELO,
+    MAIL,
+    RCPT,
+    DATA,
+    RSET,
+    NOOP,
+    QUIT,
+    VRFY,
+    EXPN,
+    HELP,
+    STARTTLS,
+    BDAT,
+    AUTH,
+    ETRN,
+    SEND,
+    SOML,
+    SAML,
+    TURN,
+}
+
+impl CommandKind {
+    const ALL: &'static [CommandKind] = &[
+        CommandKind::EHLO, CommandKind::HELO, CommandKind::MAIL, CommandKind::RCPT,
+        CommandKind::DATA, CommandKind::RSET, CommandKind::NOOP, CommandKind::QUIT,
+        CommandKind::VRFY, CommandKind::EXPN, CommandKind::HELP, CommandKind::STARTTLS,
+        CommandKind::BDAT, CommandKind::AUTH, CommandKind::ETRN, CommandKind::SEND,
+        CommandKind::SOML, CommandKind::SAML, CommandKind::TURN,
+    ];
+
+    /// Returns the verb in upper case.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CommandKind::EHLO => "EHLO",
+            CommandKind::HELO => "HELO",
+            CommandKind::MAIL => "MAIL",
+            CommandKind::RCPT => "RCPT",
+            CommandKind::DATA => "DATA",
+            CommandKind::RSET => "RSET",
+            CommandKind::NOOP => "NOOP",
+            CommandKind::QUIT => "QUIT",
+            CommandKind::VRFY => "VRFY",
+            CommandKind::EXPN => "EXPN",
+            CommandKind::HELP => "HELP",
+            CommandKind::STARTTLS => "STARTTLS",
+            CommandKind::BDAT => "BDAT",
+            CommandKind::AUTH => "AUTH",
+            CommandKind::ETRN => "ETRN",
+            CommandKind::SEND => "SEND",
+            CommandKind::SOML => "SOML",
+            CommandKind::SAML => "SAML",
+            CommandKind::TURN => "TURN",
+        }
+    }
+}
+
+impl Display for CommandKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse the verb of an SMTP command as it appears in the input.
+///
+/// The verb is a run of letters followed by a space, a CRLF or the
+/// end of the input. The arguments are not consumed or validated.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::command_verb;
+///
+/// let (rem, verb) = command_verb(b"rcpt TO:<not valid>\r\n").unwrap();
+///
+/// assert_eq!(verb, "rcpt");
+/// assert_eq!(rem, b" TO:<not valid>\r\n");
+/// ```
+pub fn command_verb(input: &[u8]) -> NomResult<&str> {
+    map_res(terminated(take_while1(|c: u8| c.is_ascii_alphabetic()),
+                       peek(alt((recognize(tag(" ")), crlf, eof)))),
+            str::from_utf8)(input)
+}
+
+/// Identify a known SMTP command from its verb.
+///
+/// See [`command_verb`]. Fails for unknown verbs.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{command_kind, CommandKind};
+///
+/// let (_, kind) = command_kind(b"Mail FROM:<bad address>\r\n").unwrap();
+/// assert_eq!(kind, CommandKind::MAIL);
+/// assert_eq!(kind.to_string(), "MAIL");
+/// assert!(command_kind(b"XYZZY\r\n").is_err());
+/// ```
+pub fn command_kind(input: &[u8]) -> NomResult<CommandKind> {
+    map_res(command_verb, |verb| {
+        CommandKind::ALL.iter().copied().find(|k| verb.eq_ignore_ascii_case(k.as_str())).ok_or(())
+    })(input)
+}
+
+/// Parse any basic SMTP command.
+///
+/// The verb is identified with [`command_kind`] and the input is then
+/// handed to the parser of that command. An unknown verb fails with
+/// the `"unknown command"` context while invalid arguments fail with
+/// the error of the verb's parser.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{command, Command};
+///
+/// let (rem, cmd) = command::<Intl>(b"BDAT 512 LAST\r\nQUIT\r\n").unwrap();
+///
+/// assert!(matches!(cmd, Command::BDAT(512, true)));
+/// assert_eq!(rem, b"QUIT\r\n");
+/// ```
+pub fn command<P: UTF8Policy>(input: &[u8]) -> NomResult<Command> {
+    let (_, kind) = context("unknown command", command_kind)(input)?;
+
+    match kind {
+        CommandKind::EHLO => map(ehlo_command::<P>, Command::EHLO)(input),
+        CommandKind::HELO => map(helo_command::<P>, Command::HELO)(input),
+        CommandKind::MAIL => map(mail_command::<P>, |(a, p)| Command::MAIL(a, p))(input),
+        CommandKind::RCPT => map(rcpt_command::<P>, |(a, p)| Command::RCPT(a, p))(input),
+        CommandKind::DATA => map(data_command, |_| Command::DATA)(input),
+        CommandKind::RSET => map(rset_command, |_| Command::RSET)(input),
+        CommandKind::NOOP => map(noop_command::<P>, Command::NOOP)(input),
+        CommandKind::QUIT => map(quit_command, |_| Command::QUIT)(input),
+        CommandKind::VRFY => map(vrfy_command::<P>, Command::VRFY)(input),
+        CommandKind::EXPN => map(expn_command::<P>, Command::EXPN)(input),
+        CommandKind::HELP => map(help_command::<P>, Command::HELP)(input),
+        CommandKind::STARTTLS => map(starttls_command, |_| Command::STARTTLS)(input),
+        CommandKind::BDAT => map(bdat_command, |(size, last)| Command::BDAT(size, last))(input),
+        CommandKind::AUTH => map(auth_command, Command::AUTH)(input),
+        CommandKind::ETRN => map(etrn_command::<P>, Command::ETRN)(input),
+        CommandKind::SEND => map(send_command::<P>, |(a, p)| Command::SEND(a, p))(input),
+        CommandKind::SOML => map(soml_command::<P>, |(a, p)| Command::SOML(a, p))(input),
+        CommandKind::SAML => map(saml_command::<P>, |(a, p)| Command::SAML(a, p))(input),
+        CommandKind::TURN => map(turn_command, |_| Command::TURN)(input),
+    }
+}
+
+/// Parse a single complete SMTP command line.
+///
+/// The line must end with a CRLF and nothing may follow it. The verb
+/// is identified first so that an unknown verb is reported as
+/// [`SMTPParseErrorKind::UnknownCommand`] and any other failure
+/// points at the invalid argument. Errors in MAIL and RCPT commands
+/// are reported as by [`mail_command_detailed`] and
+/// [`rcpt_command_detailed`].
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{parse_command, Command, SMTPParseErrorKind};
+///
+/// assert!(matches!(parse_command::<Intl>(b"NOOP hello\r\n"), Ok(Command::NOOP(Some(_)))));
+///
+/// let err = parse_command::<Intl>(b"XYZZY foo\r\n").unwrap_err();
+/// assert_eq!(err.kind, SMTPParseErrorKind::UnknownCommand);
+/// assert_eq!(err.to_string(), "unknown command at offset 0");
+/// ```
+pub fn parse_command<P: UTF8Policy>(line: &[u8]) -> Result<Command, SMTPParseError> {
+    let kind = match command_kind(line) {
+        Ok((_, kind)) => kind,
+        Err(_) => return Err(SMTPParseError{kind: SMTPParseErrorKind::UnknownCommand, offset: 0, reason: "unknown command"}),
+    };
+
+    _exact(line, command::<P>).map_err(|err| {
+        // Only the detailed parsers tell which argument is invalid.
+        let detailed = match kind {
+            CommandKind::MAIL => mail_command_detailed::<P>(line).err(),
+            CommandKind::RCPT => rcpt_command_detailed::<P>(line).err(),
+            _ => None,
+        };
+        detailed.unwrap_or(err)
+    })
+}
+
+/// Parse any basic SMTP command and return the raw bytes it was
+/// parsed from.
+///
+/// Same as [`command`], the first element of the output is the
+/// consumed input including the CRLF. Useful for relaying the exact
+/// command line that was received.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{command_with_raw, Command};
+///
+/// let (rem, (raw, cmd)) = command_with_raw::<Intl>(b"mail FROM:<bob@example.org>\r\nQUIT\r\n").unwrap();
+///
+/// assert_eq!(raw, b"mail FROM:<bob@example.org>\r\n");
+/// assert!(matches!(cmd, Command::MAIL(_, _)));
+/// assert_eq!(rem, b"QUIT\r\n");
+/// ```
+pub fn command_with_raw<P: UTF8Policy>(input: &[u8]) -> NomResult<(&[u8], Command)> {
+    consumed(command::<P>)(input)
+}
+
+/// Remove the CRLF terminating a command line.
+///
+/// The line is returned unchanged if it does not end with a CRLF.
+/// Only a single CRLF is removed.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::strip_crlf;
+///
+/// assert_eq!(strip_crlf(b"QUIT\r\n"), b"QUIT");
+/// assert_eq!(strip_crlf(b"QUIT"), b"QUIT");
+/// assert_eq!(strip_crlf(b"QUIT\n"), b"QUIT\n");
+/// ```
+pub fn strip_crlf(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\r\n").unwrap_or(line)
+}
+
+/// Parse the complete command lines of a pipelined buffer.
+///
+/// Each CRLF terminated line is parsed with [`parse_command`] and the
+/// result for every line is returned in order, so that an invalid
+/// command does not prevent parsing the rest of the batch. The error
+/// offset is relative to the start of the line.
+///
+/// Parsing stops after a DATA, BDAT or STARTTLS command since what
+/// follows is not made of commands ([RFC 2920]). The unparsed tail is
+/// returned, which includes an incomplete last line.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{parse_commands, Command};
+///
+/// let (cmds, rem) = parse_commands::<Intl>(b"MAIL FROM:<>\r\nRCPT TO:<bad>\r\nRCPT TO:<bob@example.org>\r\nDA");
+///
+/// assert!(matches!(cmds[0], Ok(Command::MAIL(_, _))));
+/// assert!(cmds[1].is_err());
+/// assert!(matches!(cmds[2], Ok(Command::RCPT(_, _))));
+/// assert_eq!(rem, b"DA");
+/// ```
+///
+/// [RFC 2920]: https://tools.ietf.org/html/rfc2920
+pub fn parse_commands<P: UTF8Policy>(buf: &[u8]) -> (Vec<Result<Command, SMTPParseError>>, &[u8]) {
+    let mut out = Vec::new();
+    let mut rem = buf;
+
+    while let Some(end) = rem.windows(2).position(|w| w == b"\r\n") {
+        let (line, tail) = rem.split_at(end + 2);
+        let cmd = parse_command::<P>(line);
+        let last = matches!(cmd, Ok(Command::DATA) | Ok(Command::BDAT(_, _)) | Ok(Command::STARTTLS));
+
+        out.push(cmd);
+        rem = tail;
+        if last {
+            break;
+        }
+    }
+
+    (out, rem)
+}
+
+// Streaming recognizers for the lines that carry a path or a domain.
+// They accept a superset of the real syntax, non ASCII characters are
+// allowed regardless of the policy for instance, so that a line is
+// only rejected early once no more input can make it valid. The
+// complete parsers decide as soon as the CRLF is received.
+
+fn _s_atext(c: u8) -> bool {
+    is_alphanumeric(c) || b"!#$%&'*+-/=?^_`{|}~".contains(&c) || c >= 0x80
+}
+
+fn _s_dot_string(input: &[u8]) -> NomResult<&[u8]> {
+    recognize(pair(streaming::take_while1(_s_atext),
+                   many0(pair(streaming::tag("."), streaming::take_while1(_s_atext)))))(input)
+}
+
+fn _s_quoted_string(input: &[u8]) -> NomResult<&[u8]> {
+    recognize(tuple((streaming::tag("\""),
+                     many0(alt((streaming::take_while1(|c| matches!(c, 32..=33 | 35..=91 | 93..=126) || c >= 0x80),
+                                recognize(pair(streaming::tag("\\"), streaming::take_while_m_n(1, 1, |c| (32..=126).contains(&c))))))),
+                     streaming::tag("\""))))(input)
+}
+
+fn _s_sub_domain(input: &[u8]) -> NomResult<&[u8]> {
+    verify(streaming::take_while1(|c| _is_ldh(c) || c >= 0x80),
+           |label: &[u8]| label[0] != b'-' && label[label.len()-1] != b'-')(input)
+}
+
+fn _s_domain(input: &[u8]) -> NomResult<&[u8]> {
+    recognize(pair(_s_sub_domain, many0(pair(streaming::tag("."), _s_sub_domain))))(input)
+}
+
+fn _s_octet(input: &[u8]) -> NomResult<&[u8]> {
+    verify(streaming::take_while_m_n(1, 3, is_digit),
+           |digits: &[u8]| str::from_utf8(digits).ok().and_then(|d| d.parse::<u8>().ok()).is_some())(input)
+}
+
+fn _s_address_literal(input: &[u8]) -> NomResult<&[u8]> {
+    let ipv4 = recognize(pair(_s_octet, many_m_n(3, 3, pair(streaming::tag("."), _s_octet))));
+    let general = recognize(tuple((streaming::take_while1(_is_ldh), streaming::tag(":"),
+                                   streaming::take_while1(|c| matches!(c, 33..=90 | 94..=126)))));
+
+    recognize(tuple((streaming::tag("["), alt((ipv4, general)), streaming::tag("]"))))(input)
+}
+
+fn _s_domain_part(input: &[u8]) -> NomResult<&[u8]> {
+    alt((_s_domain, _s_address_literal))(input)
+}
+
+fn _s_path(input: &[u8]) -> NomResult<&[u8]> {
+    let adl = recognize(pair(preceded(streaming::tag("@"), _s_domain),
+                             many0(preceded(streaming::tag(",@"), _s_domain))));
+    let mailbox = recognize(pair(alt((_s_dot_string, _s_quoted_string)),
+                                 opt(preceded(streaming::tag("@"), _s_domain_part))));
+
+    recognize(tuple((streaming::tag("<"), opt(terminated(adl, streaming::tag(":"))),
+                     opt(mailbox), streaming::tag(">"))))(input)
+}
+
+fn _s_params(input: &[u8]) -> NomResult<&[u8]> {
+    let param = recognize(pair(verify(streaming::take_while1(_is_ldh), |k: &[u8]| k[0] != b'-'),
+                               opt(preceded(streaming::tag("="),
+                                            streaming::take_while1(|c| matches!(c, 33..=60 | 62..=126) || c >= 0x80)))));
+
+    recognize(many0(preceded(streaming::take_while1(|c| c == b' ' || c == b'\t'), param)))(input)
+}
+
+fn _s_path_line<'a>(verb: &'static str, input: &'a [u8]) -> NomResult<'a, ()> {
+    map(tuple((streaming::tag_no_case(verb), _s_path, _s_params, streaming::tag("\r\n"))), |_| ())(input)
+}
+
+fn _s_domain_line<'a>(verb: &'static str, input: &'a [u8]) -> NomResult<'a, ()> {
+    map(tuple((streaming::tag_no_case(verb), _s_domain_part, streaming::tag("\r\n"))), |_| ())(input)
+}
+
+// Fixed leading text of the other commands, up to the first byte that
+// can vary.
+const COMMAND_PREFIXES: &[&str] = &[
+    "DATA\r", "RSET\r", "NOOP", "QUIT\r", "VRFY ", "EXPN ", "HELP", "STARTTLS\r",
+    "BDAT ", "AUTH ", "ETRN ", "TURN\r",
+];
+
+// Check whether a line without CRLF may still become one of the
+// commands starting with `prefixes`.
+fn _may_complete(input: &[u8], prefixes: &[&str]) -> bool {
+    let body = input.strip_suffix(b"\r").unwrap_or(input);
+
+    prefixes.iter().any(|prefix| {
+        let prefix = prefix.as_bytes();
+        let len = prefix.len().min(input.len());
+
+        input[..len].eq_ignore_ascii_case(&prefix[..len]) &&
+            body.get(len..).unwrap_or_default().iter().all(|&c| c >= b' ' && c != 0x7f)
+    })
+}
+
+fn _s_command(input: &[u8]) -> NomResult<()> {
+    alt((|i| _s_path_line("MAIL FROM:", i),
+         |i| _s_path_line("RCPT TO:", i),
+         |i| _s_path_line("SEND FROM:", i),
+         |i| _s_path_line("SOML FROM:", i),
+         |i| _s_path_line("SAML FROM:", i),
+         |i| _s_domain_line("EHLO ", i),
+         |i| _s_domain_line("HELO ", i),
+         |i| if _may_complete(i, COMMAND_PREFIXES) {
+             Err(nom::Err::Incomplete(Needed::Unknown))
+         } else {
+             Err(nom::Err::Error(NomError::from_error_kind(i, ErrorKind::Verify)))
+         }))(input)
+}
+
+fn _streaming<'a, O, R, F>(input: &'a [u8], mut recognizer: R, mut parser: F) -> NomResult<'a, O>
+    where R: FnMut(&'a [u8]) -> NomResult<'a, ()>,
+          F: FnMut(&'a [u8]) -> NomResult<'a, O>
+{
+    if input.windows(2).any(|w| w == b"\r\n") {
+        parser(input)
+    } else {
+        match recognizer(input) {
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(nom::Err::Error(e)),
+            _ => Err(nom::Err::Incomplete(Needed::Unknown)),
+        }
+    }
+}
+
+/// Parse an SMTP MAIL FROM command from a buffer that may not hold
+/// the whole line yet.
+///
+/// Returns [`nom::Err::Incomplete`] while no CRLF was received unless
+/// no more input can make the partial line valid, such as a wrong
+/// verb, an empty domain label or a control character. Use
+/// [`validate_command_line`] to limit how much is buffered.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::mail_command_streaming;
+///
+/// assert!(matches!(mail_command_streaming::<Intl>(b"MAIL FROM:<bob@exa"), Err(nom::Err::Incomplete(_))));
+/// assert!(matches!(mail_command_streaming::<Intl>(b"MAIL FROM:bob@exa"), Err(nom::Err::Error(_))));
+/// assert!(matches!(mail_command_streaming::<Intl>(b"MAIL FROM:<bob@exa.."), Err(nom::Err::Error(_))));
+/// assert!(mail_command_streaming::<Intl>(b"MAIL FROM:<bob@example.org>\r\n").is_ok());
+/// ```
+pub fn mail_command_streaming<P: UTF8Policy>(input: &[u8]) -> NomResult<(ReversePath, Vec<Param>)> {
+    _streaming(input, |i| _s_path_line("MAIL FROM:", i), mail_command::<P>)
+}
+
+/// Parse an SMTP RCPT TO command from a buffer that may not hold the
+/// whole line yet.
+///
+/// See [`mail_command_streaming`].
+pub fn rcpt_command_streaming<P: UTF8Policy>(input: &[u8]) -> NomResult<(ForwardPath, Vec<Param>)> {
+    _streaming(input, |i| _s_path_line("RCPT TO:", i), rcpt_command::<P>)
+}
+
+/// Parse any basic SMTP command from a buffer that may not hold the
+/// whole line yet.
+///
+/// Paths and domains are checked as they arrive like in
+/// [`mail_command_streaming`], the arguments of the other commands
+/// only for control characters. See also [`command`].
+pub fn command_streaming<P: UTF8Policy>(input: &[u8]) -> NomResult<Command> {
+    _streaming(input, _s_command, command::<P>)
+}
+
+/// Validates an email address.
+///
+/// Does not accept the empty address.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::validate_address;
+///
+/// assert!(validate_address::<Intl>(b"bob@example.org"));
+/// assert!(validate_address::<Intl>(b"bob@[aoeu:192.0.2.1]"));
+/// assert!(!validate_address::<Intl>(b""));
+/// ```
+pub fn validate_address<P: UTF8Policy>(i: &[u8]) -> bool {
+    exact!(i, mailbox_ref::<P>).is_ok()
+}
+
+/// Check whether a raw local part must be quoted.
+///
+/// Returns `false` if `local` is a valid unquoted dot-string.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::{Intl, Legacy};
+/// use rustyknife::rfc5321::needs_quoting;
+///
+/// assert!(!needs_quoting::<Intl>("john.doe"));
+/// assert!(needs_quoting::<Intl>("john..doe"));
+/// assert!(needs_quoting::<Intl>("john doe"));
+/// assert!(needs_quoting::<Legacy>("josé"));
+/// assert!(!needs_quoting::<Intl>("josé"));
+/// ```
+pub fn needs_quoting<P: UTF8Policy>(local: &str) -> bool {
+    exact!(local.as_bytes(), dot_string::<P>).is_err()
+}
+
+/// Parse a STARTTLS command from RFC 3207
+///
+/// The command takes no arguments.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::starttls_command;
+///
+/// assert!(starttls_command(b"StartTLS\r\n").is_ok());
+/// assert!(starttls_command(b"STARTTLS now\r\n").is_err());
+/// ```
+pub fn starttls_command(input: &[u8]) -> NomResult<()> {
+    map(tag_no_case("STARTTLS\r\n"), |_| ())(input)
+}
+
+/// Parse a BDAT command from RFC 3030
+///
+/// Returns a tuple with the chunk size and whether this is the last
+/// chunk.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::bdat_command;
+///
+/// assert_eq!(bdat_command(b"BDAT 1024\r\n").unwrap().1, (1024, false));
+/// assert_eq!(bdat_command(b"BDAT 0 LAST\r\n").unwrap().1, (0, true));
+/// ```
+pub fn bdat_command(input: &[u8]) -> NomResult<(u64, bool)> {
+    terminated(
+        pair(
+            preceded(tag_no_case("BDAT "), bdat_chunk_size),
             map(opt(tag_no_case(" LAST")), |l| l.is_some()),
         ),
         crlf,
     )(input)
 }
 
-fn bdat_chunk_size(input: &[u8]) -> NomResult<u64> {
-    map_res(take_while_m_n(1, 20, is_digit), |s| {
-        std::str::from_utf8(s).unwrap().parse()
-    })(input)
+fn bdat_chunk_size(input: &[u8]) -> NomResult<u64> {
+    map_res(map_res(take_while_m_n(1, 20, is_digit), str::from_utf8), u64::from_str)(input)
+}
+
+/// An AUTH command from [RFC 4954].
+///
+/// [RFC 4954]: https://tools.ietf.org/html/rfc4954
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthCommand {
+    /// The SASL mechanism name as sent by the client.
+    pub mechanism: String,
+    /// The base64 decoded initial response.
+    ///
+    /// `"="` decodes to an empty response. `None` if not specified.
+    pub initial_response: Option<Vec<u8>>,
+}
+
+/// Parse an AUTH command from RFC 4954
+///
+/// Fails if the initial response is not valid base64.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::auth_command;
+///
+/// let (_, auth) = auth_command(b"AUTH PLAIN AGJvYgBzZWNyZXQ=\r\n").unwrap();
+///
+/// assert_eq!(auth.mechanism, "PLAIN");
+/// assert_eq!(auth.initial_response.unwrap(), b"\0bob\0secret");
+/// assert_eq!(auth_command(b"AUTH LOGIN =\r\n").unwrap().1.initial_response, Some(vec![]));
+/// ```
+pub fn auth_command(input: &[u8]) -> NomResult<AuthCommand> {
+    map(delimited(tag_no_case("AUTH "),
+                  pair(map_res(sasl_mech, str::from_utf8), opt(preceded(tag(" "), initial_response))),
+                  crlf),
+        |(mech, initial_response)| AuthCommand{
+            mechanism: mech.into(),
+            initial_response,
+        })(input)
+}
+
+fn sasl_mech(input: &[u8]) -> NomResult<&[u8]> {
+    take_while_m_n(1, 20, |c: u8| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')(input)
+}
+
+fn initial_response(input: &[u8]) -> NomResult<Vec<u8>> {
+    alt((map(terminated(tag("="), peek(crlf)), |_| Vec::new()),
+         map_res(verify(take_while1(|c: u8| c.is_ascii_alphanumeric() || c == b'+' || c == b'/' || c == b'='),
+                        |b: &[u8]| b.len() % 4 == 0),
+                 base64::decode)))(input)
+}
+
+/// Find the first parameter matching `keyword`.
+///
+/// Keywords are compared case-insensitively.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{find_param, get_value, has_param, Param};
+///
+/// let params = [Param::new("BODY", Some("8BITMIME")).unwrap(),
+///               Param::new("SMTPUTF8", None).unwrap()];
+///
+/// assert_eq!(find_param(&params, "body"), Some(&params[0]));
+/// assert_eq!(get_value(&params, "Body"), Some("8BITMIME"));
+/// assert!(has_param(&params, "smtputf8"));
+/// assert!(!has_param(&params, "SIZE"));
+/// ```
+pub fn find_param<'a>(params: &'a [Param], keyword: &str) -> Option<&'a Param> {
+    params.iter().find(|p| p.0.eq_ignore_ascii_case(keyword))
+}
+
+/// Return the value of the first parameter matching `keyword`.
+///
+/// Returns `None` if the parameter is absent or has no value.
+pub fn get_value<'a>(params: &'a [Param], keyword: &str) -> Option<&'a str> {
+    find_param(params, keyword).and_then(|p| p.1.as_deref())
+}
+
+/// Check if a parameter matching `keyword` is present.
+pub fn has_param(params: &[Param], keyword: &str) -> bool {
+    find_param(params, keyword).is_some()
+}
+
+/// Error returned by [`validate_params`].
+///
+/// Holds the first repeated keyword.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DuplicateParam(pub Keyword);
+
+impl Display for DuplicateParam {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Duplicate {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DuplicateParam {}
+
+/// Check that no parameter keyword is repeated.
+///
+/// [RFC 5321] forbids specifying the same parameter more than once on
+/// a command. Returns the first repeated keyword on failure.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{mail_command, validate_params};
+///
+/// let (_, (_, params)) = mail_command::<Intl>(b"MAIL FROM:<> SIZE=10 BODY=7BIT size=20\r\n").unwrap();
+///
+/// assert_eq!(validate_params(&params).unwrap_err().to_string(), "Duplicate size");
+/// assert!(validate_params(&params[..2]).is_ok());
+/// ```
+///
+/// [RFC 5321]: https://tools.ietf.org/html/rfc5321#section-4.1.2
+pub fn validate_params(params: &[Param]) -> Result<(), DuplicateParam> {
+    for (i, param) in params.iter().enumerate() {
+        if params[..i].iter().any(|p| p.0.eq_ignore_ascii_case(&param.0)) {
+            return Err(DuplicateParam(param.0.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+// Find a parameter by keyword, failing if it is present more than once.
+fn _unique_param<'a>(params: &'a [Param], keyword: &str) -> Result<Option<&'a Param>, ()> {
+    let mut found = params.iter().filter(|p| p.0.eq_ignore_ascii_case(keyword));
+
+    match (found.next(), found.next()) {
+        (Some(_), Some(_)) => Err(()),
+        (param, _) => Ok(param),
+    }
+}
+
+/// Error returned by [`size_param`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SizeError {
+    /// The SIZE parameter was specified more than once.
+    Duplicate,
+    /// The SIZE parameter has no value.
+    MissingValue,
+    /// The value is not a decimal number.
+    Invalid,
+    /// The value does not fit in 64 bits.
+    Overflow,
+}
+
+impl Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            SizeError::Duplicate => "Duplicate SIZE",
+            SizeError::MissingValue => "SIZE without value",
+            SizeError::Invalid => "Invalid SIZE",
+            SizeError::Overflow => "SIZE too large",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SizeError {}
+
+/// Parse the SIZE parameter from [RFC 1870] that may be present on a
+/// MAIL FROM command.
+///
+/// Returns the declared message size or `None` if it was not
+/// specified.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{size_param, Param, SizeError};
+///
+/// let params = [Param::new("BODY", Some("8BITMIME")).unwrap(),
+///               Param::new("size", Some("12345")).unwrap()];
+///
+/// assert_eq!(size_param(&params), Ok(Some(12345)));
+/// assert_eq!(size_param(&params[..1]), Ok(None));
+/// assert_eq!(size_param(&[Param::new("SIZE", Some("12k")).unwrap()]), Err(SizeError::Invalid));
+/// ```
+///
+/// [RFC 1870]: https://tools.ietf.org/html/rfc1870
+pub fn size_param(params: &[Param]) -> Result<Option<u64>, SizeError> {
+    match _unique_param(params, "SIZE").map_err(|_| SizeError::Duplicate)? {
+        Some(Param(_, Some(value))) => {
+            if !value.bytes().all(|c| c.is_ascii_digit()) {
+                return Err(SizeError::Invalid);
+            }
+            value.parse().map(Some).map_err(|_| SizeError::Overflow)
+        }
+        Some(Param(_, None)) => Err(SizeError::MissingValue),
+        None => Ok(None),
+    }
+}
+
+/// The message body type declared with the BODY parameter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BodyType {
+    /// `"7BIT"`
+    SevenBit,
+    /// `"8BITMIME"` from [RFC 6152](https://tools.ietf.org/html/rfc6152)
+    EightBitMime,
+    /// `"BINARYMIME"` from [RFC 3030](https://tools.ietf.org/html/rfc3030)
+    BinaryMime,
+}
+
+/// Error returned by [`body_param`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BodyError {
+    /// The BODY parameter was specified more than once.
+    Duplicate,
+    /// The BODY parameter has no value.
+    MissingValue,
+    /// The value is not a known body type.
+    Invalid,
+}
+
+impl Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            BodyError::Duplicate => "Duplicate BODY",
+            BodyError::MissingValue => "BODY without value",
+            BodyError::Invalid => "Invalid BODY",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BodyError {}
+
+/// Parse the BODY parameter that may be present on a MAIL FROM command.
+///
+/// Returns the declared body type or `None` if it was not specified.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{body_param, BodyError, BodyType, Param};
+///
+/// let params = [Param::new("BODY", Some("8bitmime")).unwrap()];
+///
+/// assert_eq!(body_param(&params), Ok(Some(BodyType::EightBitMime)));
+/// assert_eq!(body_param(&[]), Ok(None));
+/// assert_eq!(body_param(&[Param::new("BODY", Some("8BIT")).unwrap()]), Err(BodyError::Invalid));
+/// ```
+pub fn body_param(params: &[Param]) -> Result<Option<BodyType>, BodyError> {
+    match _unique_param(params, "BODY").map_err(|_| BodyError::Duplicate)? {
+        Some(Param(_, Some(value))) => match value.to_ascii_lowercase().as_str() {
+            "7bit" => Ok(Some(BodyType::SevenBit)),
+            "8bitmime" => Ok(Some(BodyType::EightBitMime)),
+            "binarymime" => Ok(Some(BodyType::BinaryMime)),
+            _ => Err(BodyError::Invalid),
+        },
+        Some(Param(_, None)) => Err(BodyError::MissingValue),
+        None => Ok(None),
+    }
+}
+
+/// Error returned by [`auth_param`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuthParamError {
+    /// The AUTH parameter was specified more than once.
+    Duplicate,
+    /// The AUTH parameter has no value.
+    MissingValue,
+    /// The value is not valid xtext.
+    BadXtext,
+    /// The decoded value is neither a mailbox nor `"<>"`.
+    BadMailbox,
+}
+
+impl Display for AuthParamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            AuthParamError::Duplicate => "Duplicate AUTH",
+            AuthParamError::MissingValue => "AUTH without value",
+            AuthParamError::BadXtext => "Invalid xtext in AUTH",
+            AuthParamError::BadMailbox => "Invalid mailbox in AUTH",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AuthParamError {}
+
+/// Parse the [RFC 4954] AUTH parameter that may be present on a MAIL
+/// FROM command.
+///
+/// The value is xtext decoded and parsed as a mailbox. Returns
+/// `Some(None)` for the `"<>"` value that indicates an unknown
+/// identity and `None` if the parameter was not specified.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{auth_param, Param};
+///
+/// let params = [Param::new("AUTH", Some("bob+2Bsmith@example.org")).unwrap()];
+/// let mailbox = auth_param(&params).unwrap().unwrap().unwrap();
+///
+/// assert_eq!(mailbox.to_string(), "bob+smith@example.org");
+/// assert_eq!(auth_param(&[Param::new("AUTH", Some("<>")).unwrap()]), Ok(Some(None)));
+/// ```
+///
+/// [RFC 4954]: https://tools.ietf.org/html/rfc4954
+pub fn auth_param(params: &[Param]) -> Result<Option<Option<Mailbox>>, AuthParamError> {
+    match _unique_param(params, "AUTH").map_err(|_| AuthParamError::Duplicate)? {
+        Some(Param(_, Some(value))) => {
+            let decoded = crate::rfc3461::xtext_decode(&value.0).map_err(|_| AuthParamError::BadXtext)?;
+            if decoded == "<>" {
+                return Ok(Some(None));
+            }
+            exact!(decoded.as_bytes(), mailbox::<Legacy>)
+                .map(|(_, m)| Some(Some(m)))
+                .map_err(|_| AuthParamError::BadMailbox)
+        }
+        Some(Param(_, None)) => Err(AuthParamError::MissingValue),
+        None => Ok(None),
+    }
+}
+
+/// Error returned by [`mt_priority_param`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MtPriorityError {
+    /// The MT-PRIORITY parameter was specified more than once.
+    Duplicate,
+    /// The MT-PRIORITY parameter has no value.
+    MissingValue,
+    /// The value is not a single digit with an optional sign.
+    Invalid,
+}
+
+impl Display for MtPriorityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            MtPriorityError::Duplicate => "Duplicate MT-PRIORITY",
+            MtPriorityError::MissingValue => "MT-PRIORITY without value",
+            MtPriorityError::Invalid => "Invalid MT-PRIORITY",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MtPriorityError {}
+
+/// Parse the [RFC 6710] MT-PRIORITY parameter that may be present on
+/// a MAIL FROM command.
+///
+/// Returns the priority in the -9 to 9 range or `None` if it was not
+/// specified.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{mt_priority_param, MtPriorityError, Param};
+///
+/// assert_eq!(mt_priority_param(&[Param::new("MT-PRIORITY", Some("-4")).unwrap()]), Ok(Some(-4)));
+/// assert_eq!(mt_priority_param(&[Param::new("MT-PRIORITY", Some("12")).unwrap()]),
+///            Err(MtPriorityError::Invalid));
+/// assert_eq!(mt_priority_param(&[]), Ok(None));
+/// ```
+///
+/// [RFC 6710]: https://tools.ietf.org/html/rfc6710
+pub fn mt_priority_param(params: &[Param]) -> Result<Option<i8>, MtPriorityError> {
+    match _unique_param(params, "MT-PRIORITY").map_err(|_| MtPriorityError::Duplicate)? {
+        Some(Param(_, Some(value))) => {
+            let (negative, digit) = match value.as_bytes() {
+                [b'-', digit] => (true, *digit),
+                [b'+', digit] | [digit] => (false, *digit),
+                _ => return Err(MtPriorityError::Invalid),
+            };
+            if !digit.is_ascii_digit() {
+                return Err(MtPriorityError::Invalid);
+            }
+            let priority = (digit - b'0') as i8;
+            Ok(Some(if negative { -priority } else { priority }))
+        }
+        Some(Param(_, None)) => Err(MtPriorityError::MissingValue),
+        None => Ok(None),
+    }
+}
+
+/// Action requested when a message cannot be delivered in time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeliverByMode {
+    /// Send a delayed DSN and keep trying to deliver.
+    Notify,
+    /// Return the message as undeliverable.
+    Return,
+}
+
+/// The value of the [RFC 2852] BY parameter.
+///
+/// [RFC 2852]: https://tools.ietf.org/html/rfc2852
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeliverBy {
+    /// Seconds from the time of the MAIL command until the deadline.
+    /// May be negative in [`DeliverByMode::Notify`] mode.
+    pub seconds: i64,
+    /// What to do once the deadline has passed.
+    pub mode: DeliverByMode,
+    /// Whether the trace flag was given.
+    pub trace: bool,
+}
+
+/// Error returned by [`deliverby_param`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeliverByError {
+    /// The BY parameter was specified more than once.
+    Duplicate,
+    /// The BY parameter has no value.
+    MissingValue,
+    /// The value does not follow the `by-time;by-mode[by-trace]` syntax.
+    Invalid,
+    /// The time is not positive in [`DeliverByMode::Return`] mode.
+    NotPositive,
+}
+
+impl Display for DeliverByError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DeliverByError::Duplicate => "Duplicate BY",
+            DeliverByError::MissingValue => "BY without value",
+            DeliverByError::Invalid => "Invalid BY",
+            DeliverByError::NotPositive => "BY time must be positive with R mode",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DeliverByError {}
+
+fn _deliverby_value(input: &[u8]) -> NomResult<DeliverBy> {
+    let by_time = map_res(map_res(recognize(pair(opt(alt((tag("-"), tag("+")))), take_while_m_n(1, 9, is_digit))),
+                                  str::from_utf8), i64::from_str);
+    let by_mode = alt((map(tag_no_case("N"), |_| DeliverByMode::Notify),
+                       map(tag_no_case("R"), |_| DeliverByMode::Return)));
+
+    map(separated_pair(by_time, tag(";"), pair(by_mode, opt(tag_no_case("T")))),
+        |(seconds, (mode, trace))| DeliverBy{seconds, mode, trace: trace.is_some()})(input)
+}
+
+/// Parse the BY parameter of the [RFC 2852] DELIVERBY extension that
+/// may be present on a MAIL FROM command.
+///
+/// The value is a time in seconds and a mode, optionally followed by
+/// the trace flag such as in `"120;RT"`.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{deliverby_param, DeliverBy, DeliverByMode, Param};
+///
+/// let params = [Param::new("BY", Some("240;NT")).unwrap()];
+///
+/// assert_eq!(deliverby_param(&params),
+///            Ok(Some(DeliverBy{seconds: 240, mode: DeliverByMode::Notify, trace: true})));
+/// assert_eq!(deliverby_param(&[]), Ok(None));
+/// ```
+///
+/// [RFC 2852]: https://tools.ietf.org/html/rfc2852
+pub fn deliverby_param(params: &[Param]) -> Result<Option<DeliverBy>, DeliverByError> {
+    match _unique_param(params, "BY").map_err(|_| DeliverByError::Duplicate)? {
+        Some(Param(_, Some(value))) => {
+            let (_, by) = exact!(value.as_bytes(), _deliverby_value).map_err(|_| DeliverByError::Invalid)?;
+            if by.mode == DeliverByMode::Return && by.seconds <= 0 {
+                return Err(DeliverByError::NotPositive);
+            }
+            Ok(Some(by))
+        }
+        Some(Param(_, None)) => Err(DeliverByError::MissingValue),
+        None => Ok(None),
+    }
+}
+
+/// Check for the [RFC 8689] REQUIRETLS parameter that may be present
+/// on a MAIL FROM command.
+///
+/// Use [`requiretls_param_checked`] to reject invalid uses of the
+/// parameter.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{requiretls_param, Param};
+///
+/// assert!(requiretls_param(&[Param::new("requiretls", None).unwrap()]));
+/// assert!(!requiretls_param(&[]));
+/// ```
+///
+/// [RFC 8689]: https://tools.ietf.org/html/rfc8689
+pub fn requiretls_param(params: &[Param]) -> bool {
+    has_param(params, "REQUIRETLS")
+}
+
+/// Error returned by the helpers for parameters that take no value
+/// such as [`requiretls_param_checked`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlagParamError {
+    /// The parameter was specified more than once.
+    Duplicate,
+    /// The parameter has a value.
+    UnexpectedValue,
+}
+
+impl Display for FlagParamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            FlagParamError::Duplicate => "Duplicate parameter",
+            FlagParamError::UnexpectedValue => "Parameter with value",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FlagParamError {}
+
+fn _flag_param(params: &[Param], keyword: &str) -> Result<bool, FlagParamError> {
+    match _unique_param(params, keyword).map_err(|_| FlagParamError::Duplicate)? {
+        Some(Param(_, Some(_))) => Err(FlagParamError::UnexpectedValue),
+        Some(Param(_, None)) => Ok(true),
+        None => Ok(false),
+    }
+}
+
+/// Check for the [RFC 8689] REQUIRETLS parameter, failing if it has a
+/// value or is repeated.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{requiretls_param_checked, FlagParamError, Param};
+///
+/// assert_eq!(requiretls_param_checked(&[Param::new("REQUIRETLS", None).unwrap()]), Ok(true));
+/// assert_eq!(requiretls_param_checked(&[Param::new("REQUIRETLS", Some("YES")).unwrap()]),
+///            Err(FlagParamError::UnexpectedValue));
+/// ```
+///
+/// [RFC 8689]: https://tools.ietf.org/html/rfc8689
+pub fn requiretls_param_checked(params: &[Param]) -> Result<bool, FlagParamError> {
+    _flag_param(params, "REQUIRETLS")
+}
+
+/// Check for the [RFC 6531] SMTPUTF8 parameter that may be present
+/// on a MAIL FROM command.
+///
+/// When present, the following RCPT TO commands should be parsed
+/// with [`Intl`]. Only the keyword is looked at: the parameter is
+/// not validated, so a value or a repeated keyword still counts as a
+/// request. Use [`smtputf8_param`] to reject invalid uses of the
+/// parameter.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{smtputf8_requested, Param};
+///
+/// assert!(smtputf8_requested(&[Param::new("smtputf8", None).unwrap()]));
+/// assert!(smtputf8_requested(&[Param::new("SMTPUTF8", Some("yes")).unwrap()]));
+/// assert!(!smtputf8_requested(&[]));
+/// ```
+///
+/// [RFC 6531]: https://tools.ietf.org/html/rfc6531
+pub fn smtputf8_requested(params: &[Param]) -> bool {
+    has_param(params, "SMTPUTF8")
+}
+
+/// Check for the [RFC 6531] SMTPUTF8 parameter, failing if it has a
+/// value or is repeated.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Legacy;
+/// use rustyknife::rfc5321::{mail_command, smtputf8_param, FlagParamError};
+///
+/// let (_, (_, params)) = mail_command::<Legacy>(b"MAIL FROM:<bob@example.org> smtputf8\r\n").unwrap();
+/// assert_eq!(smtputf8_param(&params), Ok(true));
+///
+/// let (_, (_, params)) = mail_command::<Legacy>(b"MAIL FROM:<bob@example.org> SMTPUTF8=yes\r\n").unwrap();
+/// assert_eq!(smtputf8_param(&params), Err(FlagParamError::UnexpectedValue));
+/// ```
+///
+/// [RFC 6531]: https://tools.ietf.org/html/rfc6531
+pub fn smtputf8_param(params: &[Param]) -> Result<bool, FlagParamError> {
+    _flag_param(params, "SMTPUTF8")
+}
+
+fn _dsn_input(params: &[Param]) -> Vec<(&str, Option<&str>)> {
+    params.iter().map(|p| (&*p.0, p.1.as_deref())).collect()
+}
+
+/// MAIL FROM parameters with the well-known extensions parsed.
+///
+/// Built from the raw parameters returned by [`mail_command`]. Each
+/// known parameter is validated as by its dedicated helper such as
+/// [`size_param`].
+/// # Examples
+/// ```
+/// use std::convert::TryFrom;
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{mail_command, BodyType, MailParams, Param};
+///
+/// let (_, (_, params)) = mail_command::<Intl>(b"MAIL FROM:<> SIZE=1000 BODY=8BITMIME SMTPUTF8 X-FOO=bar\r\n").unwrap();
+/// let params = MailParams::try_from(params).unwrap();
+///
+/// assert_eq!(params.size, Some(1000));
+/// assert_eq!(params.body, Some(BodyType::EightBitMime));
+/// assert!(params.smtputf8);
+/// assert_eq!(params.extensions, [Param::new("X-FOO", Some("bar")).unwrap()]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MailParams {
+    /// The SIZE parameter. See [`size_param`].
+    pub size: Option<u64>,
+    /// The BODY parameter. See [`body_param`].
+    pub body: Option<BodyType>,
+    /// The AUTH parameter. See [`auth_param`].
+    pub auth: Option<Option<Mailbox>>,
+    /// Whether the SMTPUTF8 flag from [RFC 6531] was given.
+    ///
+    /// [RFC 6531]: https://tools.ietf.org/html/rfc6531
+    pub smtputf8: bool,
+    /// The DSN RET parameter. See [`dsn_mail_params`].
+    pub ret: Option<DSNRet>,
+    /// The DSN ENVID parameter. See [`dsn_mail_params`].
+    pub envid: Option<String>,
+    /// The remaining parameters in their original order.
+    pub extensions: Vec<Param>,
+}
+
+impl MailParams {
+    const KNOWN: &'static [&'static str] = &["SIZE", "BODY", "AUTH", "SMTPUTF8", "RET", "ENVID"];
+}
+
+/// Error returned when building [`MailParams`].
+///
+/// Holds the error of the helper that rejected the parameter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MailParamsError {
+    /// Invalid SIZE parameter.
+    Size(SizeError),
+    /// Invalid BODY parameter.
+    Body(BodyError),
+    /// Invalid AUTH parameter.
+    Auth(AuthParamError),
+    /// Invalid SMTPUTF8 parameter.
+    Smtputf8(FlagParamError),
+    /// Invalid RET or ENVID parameter as reported by
+    /// [`dsn_mail_params`].
+    Dsn(&'static str),
+}
+
+impl Display for MailParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MailParamsError::Size(e) => e.fmt(f),
+            MailParamsError::Body(e) => e.fmt(f),
+            MailParamsError::Auth(e) => e.fmt(f),
+            MailParamsError::Smtputf8(e) => write!(f, "{}: SMTPUTF8", e),
+            MailParamsError::Dsn(reason) => f.write_str(reason),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MailParamsError {}
+
+impl TryFrom<Vec<Param>> for MailParams {
+    type Error = MailParamsError;
+
+    fn try_from(params: Vec<Param>) -> Result<Self, Self::Error> {
+        let (dsn, _) = dsn_mail_params(&_dsn_input(&params)).map_err(MailParamsError::Dsn)?;
+
+        Ok(MailParams {
+            size: size_param(&params).map_err(MailParamsError::Size)?,
+            body: body_param(&params).map_err(MailParamsError::Body)?,
+            auth: auth_param(&params).map_err(MailParamsError::Auth)?,
+            smtputf8: smtputf8_param(&params).map_err(MailParamsError::Smtputf8)?,
+            ret: dsn.ret,
+            envid: dsn.envid,
+            extensions: params.into_iter().filter(|p| !Self::KNOWN.iter().any(|k| p.0.eq_ignore_ascii_case(k))).collect(),
+        })
+    }
+}
+
+/// RCPT TO parameters with the well-known extensions parsed.
+///
+/// Built from the raw parameters returned by [`rcpt_command`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RcptParams {
+    /// The DSN NOTIFY parameter. See [`notify_param`].
+    pub notify: Option<Notify>,
+    /// The DSN ORCPT parameter. See [`orcpt_param`].
+    pub orcpt: Option<(String, String)>,
+    /// The remaining parameters in their original order.
+    pub extensions: Vec<Param>,
+}
+
+impl RcptParams {
+    const KNOWN: &'static [&'static str] = &["NOTIFY", "ORCPT"];
+}
+
+/// Error returned when building [`RcptParams`].
+///
+/// Holds the error of the helper that rejected the parameter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RcptParamsError {
+    /// Invalid NOTIFY parameter.
+    Notify(NotifyError),
+    /// Invalid ORCPT parameter.
+    Orcpt(OrcptError),
+}
+
+impl Display for RcptParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RcptParamsError::Notify(e) => e.fmt(f),
+            RcptParamsError::Orcpt(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RcptParamsError {}
+
+impl TryFrom<Vec<Param>> for RcptParams {
+    type Error = RcptParamsError;
+
+    fn try_from(params: Vec<Param>) -> Result<Self, Self::Error> {
+        let dsn = _dsn_input(&params);
+
+        Ok(RcptParams {
+            notify: notify_param(&dsn).map_err(RcptParamsError::Notify)?,
+            orcpt: orcpt_param(&dsn).map_err(RcptParamsError::Orcpt)?,
+            extensions: params.into_iter().filter(|p| !Self::KNOWN.iter().any(|k| p.0.eq_ignore_ascii_case(k))).collect(),
+        })
+    }
+}
+
+/// Error returned by [`mail_command_typed`] and [`rcpt_command_typed`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypedCommandError<E> {
+    /// The command itself is invalid.
+    Command(SMTPParseError),
+    /// The command is valid but one of its known parameters is not.
+    Params(E),
+}
+
+impl<E: Display> Display for TypedCommandError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypedCommandError::Command(e) => e.fmt(f),
+            TypedCommandError::Params(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug + Display> std::error::Error for TypedCommandError<E> {}
+
+/// Parse an SMTP MAIL FROM command with typed parameters.
+///
+/// The whole input must consist of a single command. Fails if
+/// [`MailParams`] cannot be built from the parameters.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::{mail_command_typed, MailParamsError, SizeError, TypedCommandError};
+///
+/// let (_, params) = mail_command_typed::<Intl>(b"MAIL FROM:<> SIZE=10\r\n").unwrap();
+/// assert_eq!(params.size, Some(10));
+///
+/// assert_eq!(mail_command_typed::<Intl>(b"MAIL FROM:<> SIZE=abc\r\n"),
+///            Err(TypedCommandError::Params(MailParamsError::Size(SizeError::Invalid))));
+/// ```
+pub fn mail_command_typed<P: UTF8Policy>(input: &[u8]) -> Result<(ReversePath, MailParams), TypedCommandError<MailParamsError>> {
+    let (path, params) = mail_command_detailed::<P>(input).map_err(TypedCommandError::Command)?;
+
+    Ok((path, MailParams::try_from(params).map_err(TypedCommandError::Params)?))
+}
+
+/// Parse an SMTP RCPT TO command with typed parameters.
+///
+/// The whole input must consist of a single command. Fails if
+/// [`RcptParams`] cannot be built from the parameters.
+pub fn rcpt_command_typed<P: UTF8Policy>(input: &[u8]) -> Result<(ForwardPath, RcptParams), TypedCommandError<RcptParamsError>> {
+    let (path, params) = rcpt_command_detailed::<P>(input).map_err(TypedCommandError::Command)?;
+
+    Ok((path, RcptParams::try_from(params).map_err(TypedCommandError::Params)?))
 }
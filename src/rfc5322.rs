@@ -5,20 +5,35 @@
 //! [Internet Message Format]: https://tools.ietf.org/html/rfc5322
 //! [RFC 2047]: https://tools.ietf.org/html/rfc2047
 
-use std::borrow::Cow;
-use std::str;
-use std::mem;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+#[cfg(feature = "std")]
+use std::net::Ipv6Addr;
+#[cfg(not(feature = "std"))]
+use core::net::Ipv6Addr;
+use core::str::{self, FromStr};
+use core::mem;
 
 use nom::branch::alt;
-use nom::bytes::complete::{tag, take};
-use nom::combinator::{map, map_opt, opt, recognize};
+use nom::bytes::complete::{tag, tag_no_case, take, take_while1};
+use nom::combinator::{eof, map, map_opt, map_res, opt, recognize, rest, verify};
+use nom::error::ErrorKind;
 use nom::multi::{fold_many0, many0, many1};
-use nom::sequence::{delimited, pair, preceded, separated_pair, terminated};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, TimeZone};
+#[cfg(feature = "chrono")]
+use nom::bytes::complete::take_while_m_n;
+#[cfg(feature = "chrono")]
+use nom::character::is_digit;
 
 use crate::behaviour::*;
 use crate::rfc2047::encoded_word;
+use crate::rfc5321::{self, ReversePath};
 use crate::rfc5234::*;
 use crate::types::{self, *};
+use crate::prelude::*;
 use crate::util::*;
 
 #[allow(missing_docs)] // Mostly internal
@@ -89,10 +104,16 @@ enum CommentContent<'a> {
     QP(char),
 }
 
-fn ccontent<P: UTF8Policy>(input: &[u8]) -> NomResult<CommentContent> {
+/// Maximum nesting depth of comments.
+///
+/// Deeper comments fail the whole parse with a `"comment nested too
+/// deeply"` error instead of exhausting the stack.
+pub const MAX_COMMENT_DEPTH: usize = 50;
+
+fn ccontent<P: UTF8Policy>(input: &[u8], depth: usize) -> NomResult<CommentContent> {
     alt((alt((map(recognize_many1(P::ctext), |ct| CommentContent::Text(str::from_utf8(ct).unwrap().into())),
               map(quoted_pair::<P>, CommentContent::QP))),
-         map(comment::<P>, CommentContent::Comment)))(input)
+         map(|i| _comment::<P>(i, depth), CommentContent::Comment)))(input)
 }
 
 fn fws(input: &[u8]) -> NomResult<Cow<str>> {
@@ -137,21 +158,87 @@ fn _concat_comment<'a, I: IntoIterator<Item=CommentContent<'a>>>(comments: I) ->
     out
 }
 
+// `depth` is the number of nesting levels still allowed, including
+// this comment.
+fn _comment<P: UTF8Policy>(input: &[u8], depth: usize) -> NomResult<Vec<CommentContent>> {
+    let (rem, _) = tag("(")(input)?;
+
+    if depth == 0 {
+        return Err(nom::Err::Failure(NomError{input, kind: ErrorKind::TooLarge, context: Some("comment nested too deeply")}));
+    }
+
+    map(terminated(pair(fold_many0(pair(ofws, |i| ccontent::<P>(i, depth - 1)), Vec::new(), |mut acc, (fws, cc)| {
+                            acc.push(CommentContent::Text(fws));
+                            acc.push(cc);
+                            acc
+                        }), ofws),
+                   tag(")")),
+        |(a, b)| _concat_comment(a.into_iter().chain(core::iter::once(CommentContent::Text(b)))))(rem)
+}
+
 fn comment<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<CommentContent>> {
-    map(delimited(tag("("),
-                  pair(fold_many0(pair(ofws, ccontent::<P>), Vec::new(), |mut acc, (fws, cc)| {
-                      acc.push(CommentContent::Text(fws));
-                      acc.push(cc);
-                      acc
-                  }), ofws),
-                  tag(")")),
-        |(a, b)| _concat_comment(a.into_iter().chain(std::iter::once(CommentContent::Text(b)))))(input)
+    _comment::<P>(input, MAX_COMMENT_DEPTH)
 }
 
-fn cfws<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
+pub(crate) fn cfws<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
     alt((recognize(pair(many1(pair(ofws, comment::<P>)), ofws)), recognize(fws)))(input)
 }
 
+fn _render_comment(content: &[CommentContent], out: &mut String) {
+    for c in content {
+        match c {
+            CommentContent::Text(text) => out.push_str(text),
+            CommentContent::QP(qp) => out.push(*qp),
+            CommentContent::Comment(inner) => {
+                out.push('(');
+                _render_comment(inner, out);
+                out.push(')');
+            }
+        }
+    }
+}
+
+/// Parse comments and folding whitespace, returning the comments.
+///
+/// Each top level comment is returned without its enclosing
+/// parentheses. Nested comments are kept with their parentheses,
+/// quoted pairs are unescaped and folding is removed.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::cfws_comments;
+///
+/// let (rem, comments) = cfws_comments::<Intl>(b" (really y) (a (nested) \\) comment)\r\n (x)by").unwrap();
+///
+/// assert_eq!(comments, ["really y", "a (nested) ) comment", "x"]);
+/// assert_eq!(rem, b"by");
+/// ```
+pub fn cfws_comments<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<String>> {
+    cfws_comments_with_depth::<P>(input, MAX_COMMENT_DEPTH)
+}
+
+/// Parse comments and folding whitespace with a custom nesting limit.
+///
+/// Same as [`cfws_comments`] but comments may nest at most
+/// `max_depth` levels deep instead of [`MAX_COMMENT_DEPTH`].
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::cfws_comments_with_depth;
+///
+/// assert!(cfws_comments_with_depth::<Intl>(b"(a (b))", 2).is_ok());
+/// assert!(cfws_comments_with_depth::<Intl>(b"(a (b (c)))", 2).is_err());
+/// ```
+pub fn cfws_comments_with_depth<P: UTF8Policy>(input: &[u8], max_depth: usize) -> NomResult<Vec<String>> {
+    alt((map(terminated(many1(preceded(ofws, |i| _comment::<P>(i, max_depth))), ofws), |comments| {
+        comments.iter().map(|c| {
+            let mut out = String::new();
+            _render_comment(c, &mut out);
+            out
+        }).collect()
+    }), map(fws, |_| Vec::new())))(input)
+}
+
 #[cfg(feature = "quoted-string-rfc2047")]
 fn qcontent<P: UTF8Policy>(input: &[u8]) -> NomResult<QContent> {
     alt((map(encoded_word, QContent::EncodedWord),
@@ -194,7 +281,7 @@ pub(crate) fn quoted_string<P: UTF8Policy>(input: &[u8]) -> NomResult<QuotedStri
 }
 
 /// A single mailbox with an optional display name.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Mailbox {
     /// The display name.
     pub dname: Option<String>,
@@ -203,7 +290,7 @@ pub struct Mailbox {
 }
 
 /// A group of many [`Mailbox`].
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Group {
     /// This group's display name.
     pub dname: String,
@@ -212,7 +299,7 @@ pub struct Group {
 }
 
 /// An address is either a single [`Mailbox`] or a [`Group`].
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Address {
     /// Single [`Mailbox`].
     Mailbox(Mailbox),
@@ -273,12 +360,46 @@ pub(crate) fn utf8_non_ascii(input: &[u8]) -> NomResult<char> {
     alt((_single_char(4), _single_char(3), _single_char(2)))(input)
 }
 
-pub(crate) fn dot_atom<P: UTF8Policy>(input: &[u8]) -> NomResult<DotAtom> {
-    map(delimited(opt(cfws::<P>), recognize(pair(recognize_many1(P::atext), recognize_many0(pair(tag("."), recognize_many1(P::atext))))), opt(cfws::<P>)),
+fn dot_atom_text<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
+    recognize(pair(recognize_many1(P::atext), recognize_many0(pair(tag("."), recognize_many1(P::atext)))))(input)
+}
+
+/// Parse a dot-atom such as `"john.doe"`.
+///
+/// Surrounding comments and folding whitespace are skipped.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::dot_atom;
+///
+/// let (rem, da) = dot_atom::<Intl>(b" (x) john.doe rest").unwrap();
+///
+/// assert_eq!(da.to_string(), "john.doe");
+/// assert_eq!(rem, b"rest");
+/// assert!(dot_atom::<Intl>(b".john").is_err());
+/// ```
+pub fn dot_atom<P: UTF8Policy>(input: &[u8]) -> NomResult<DotAtom> {
+    map(delimited(opt(cfws::<P>), dot_atom_text::<P>, opt(cfws::<P>)),
         |a| (DotAtom(str::from_utf8(a).unwrap().into())))(input)
 }
 
-pub(crate) fn atom<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
+/// Parse an atom, a run of `atext` characters.
+///
+/// Surrounding comments and folding whitespace are skipped and the
+/// atom is returned as a slice of the input. It is valid UTF-8 with
+/// both policies since [`Legacy`] only accepts ASCII.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Legacy;
+/// use rustyknife::rfc5322::atom;
+///
+/// let (rem, a) = atom::<Legacy>(b" hello (comment) world").unwrap();
+///
+/// assert_eq!(a, b"hello");
+/// assert_eq!(rem, b"world");
+/// assert!(atom::<Legacy>(b"\"quoted\"").is_err());
+/// ```
+pub fn atom<P: UTF8Policy>(input: &[u8]) -> NomResult<&[u8]> {
     delimited(opt(cfws::<P>), recognize_many1(P::atext), opt(cfws::<P>))(input)
 }
 
@@ -341,17 +462,64 @@ pub(crate) fn domain<P: UTF8Policy>(input: &[u8]) -> NomResult<DomainPart> {
          map(domain_literal::<P>, DomainPart::Address)))(input)
 }
 
-pub(crate) fn addr_spec<P: UTF8Policy>(input: &[u8]) -> NomResult<types::Mailbox> {
+/// Parse a bare address such as `"bob@example.org"`.
+///
+/// Comments and folding whitespace are allowed around the local part
+/// and the domain and are discarded.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::addr_spec;
+///
+/// let (_, mailbox) = addr_spec::<Intl>(b"bob (Bob) @ (home)\r\n example.org").unwrap();
+/// assert_eq!(mailbox.to_string(), "bob@example.org");
+/// ```
+pub fn addr_spec<P: UTF8Policy>(input: &[u8]) -> NomResult<types::Mailbox> {
     map(separated_pair(local_part::<P>, tag("@"), domain::<P>),
         |(lp, domain)| types::Mailbox(lp, domain))(input)
 }
 
-fn angle_addr<P: UTF8Policy>(input: &[u8]) -> NomResult<types::Mailbox> {
+/// Parse an address enclosed in angle brackets such as `"<bob@example.org>"`.
+///
+/// Comments and folding whitespace are allowed around the brackets.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::angle_addr;
+///
+/// let (_, mailbox) = angle_addr::<Intl>(b" (Bob) <\"bob\"@example.org> ").unwrap();
+/// assert_eq!(mailbox.to_string(), "\"bob\"@example.org");
+/// ```
+pub fn angle_addr<P: UTF8Policy>(input: &[u8]) -> NomResult<types::Mailbox> {
     delimited(pair(opt(cfws::<P>), tag("<")),
               addr_spec::<P>,
               pair(tag(">"), opt(cfws::<P>)))(input)
 }
 
+/// Validates an email address as written in a message header.
+///
+/// Uses the [`addr_spec`] grammar. Unlike
+/// [`rfc5321::validate_address`] which checks envelope addresses,
+/// comments and folding whitespace are allowed around each part and
+/// a domain literal may contain any text. Use both functions when an
+/// address must be valid in the header and in the envelope. The
+/// address must not be enclosed in angle brackets.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321;
+/// use rustyknife::rfc5322::validate_address;
+///
+/// assert!(validate_address::<Intl>(b"bob (Bob Smith) @example.org"));
+/// assert!(!rfc5321::validate_address::<Intl>(b"bob (Bob Smith) @example.org"));
+/// assert!(!validate_address::<Intl>(b"<bob@example.org>"));
+/// ```
+///
+/// [`rfc5321::validate_address`]: crate::rfc5321::validate_address
+pub fn validate_address<P: UTF8Policy>(i: &[u8]) -> bool {
+    exact!(i, addr_spec::<P>).is_ok()
+}
+
 fn name_addr<P: UTF8Policy>(input: &[u8]) -> NomResult<Mailbox> {
     map(pair(opt(display_name::<P>), angle_addr::<P>),
         |(dname, address)| Mailbox{dname, address})(input)
@@ -397,6 +565,39 @@ fn _8bit_char(input: &[u8]) -> NomResult<char> {
     map(take1_filter(|c| (0x80..=0xff).contains(&c)), |_| '\u{fffd}')(input)
 }
 
+/// Unfold a header value.
+///
+/// Removes each CRLF that is immediately followed by a space or a
+/// tab, keeping the whitespace itself. Other CRLF are left
+/// untouched. The value is returned as bytes since header values may
+/// contain any 8 bit data.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5322::unfold;
+///
+/// assert_eq!(unfold(b"a long\r\n\tsubject\r\n"), &b"a long\tsubject\r\n"[..]);
+/// ```
+pub fn unfold(value: &[u8]) -> Cow<[u8]> {
+    let is_fold = |i: usize| value[i..].starts_with(b"\r\n") && matches!(value.get(i+2), Some(b' ') | Some(b'\t'));
+
+    if !(0..value.len()).any(is_fold) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut out = Vec::with_capacity(value.len());
+    let mut i = 0;
+    while i < value.len() {
+        if is_fold(i) {
+            i += 2;
+        } else {
+            out.push(value[i]);
+            i += 1;
+        }
+    }
+
+    Cow::Owned(out)
+}
+
 /// Parse an unstructured header such as `"Subject:"`.
 ///
 /// Returns a fully decoded string.
@@ -422,6 +623,22 @@ pub fn unstructured<P: UTF8Policy>(input: &[u8]) -> NomResult<String> {
 ///
 /// Returns a list of addresses, since [RFC 6854] allows multiple mail
 /// authors.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::{from, Address};
+///
+/// let (_, addresses) = from::<Intl>(b"\"John Doe\" <john@example.org>, jane@example.org\r\n").unwrap();
+///
+/// assert_eq!(addresses.len(), 2);
+/// match &addresses[0] {
+///     Address::Mailbox(john) => {
+///         assert_eq!(john.dname.as_deref(), Some("John Doe"));
+///         assert_eq!(john.address.to_string(), "john@example.org");
+///     }
+///     other => panic!("unexpected {:?}", other),
+/// }
+/// ```
 ///
 /// [RFC 6854]: https://tools.ietf.org/html/rfc6854
 pub fn from<P: UTF8Policy>(i: &[u8]) -> NomResult<Vec<Address>> {
@@ -435,9 +652,367 @@ pub fn sender<P: UTF8Policy>(i: &[u8]) -> NomResult<Address> {
     address_crlf::<P>(i)
 }
 
+/// Parse the content of a `"Sender:"` header as a single mailbox.
+///
+/// Unlike [`sender`], which also accepts a group as allowed by
+/// [RFC 6854], this enforces the [RFC 5322] syntax of exactly one
+/// mailbox. The whole input must be consumed, up to an optional
+/// CRLF, so a list of mailboxes is an error.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::sender_mailbox;
+///
+/// let (_, mailbox) = sender_mailbox::<Intl>(b"Bob <bob@example.org>\r\n").unwrap();
+/// assert_eq!(mailbox.address.to_string(), "bob@example.org");
+///
+/// assert!(sender_mailbox::<Intl>(b"Team: bob@example.org;\r\n").is_err());
+/// assert!(sender_mailbox::<Intl>(b"bob@example.org, alice@example.org\r\n").is_err());
+/// ```
+///
+/// [RFC 5322]: https://tools.ietf.org/html/rfc5322#section-3.6.2
+/// [RFC 6854]: https://tools.ietf.org/html/rfc6854
+pub fn sender_mailbox<P: UTF8Policy>(i: &[u8]) -> NomResult<Mailbox> {
+    terminated(mailbox::<P>, pair(opt(crlf), eof))(i)
+}
+
+/// Parse the content of a `"Return-Path:"` header.
+///
+/// The path uses the SMTP reverse path syntax and may be surrounded
+/// by comments and folding whitespace. The null path `"<>"` may also
+/// contain them.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::ReversePath;
+/// use rustyknife::rfc5322::return_path;
+///
+/// let (_, path) = return_path::<Intl>(b" <bob@example.org> (bounces)\r\n").unwrap();
+/// assert_eq!(path.to_string(), "<bob@example.org>");
+///
+/// let (_, path) = return_path::<Intl>(b"<>\r\n").unwrap();
+/// assert_eq!(path, ReversePath::Null);
+/// ```
+pub fn return_path<P: UTF8Policy + rfc5321::UTF8Policy>(i: &[u8]) -> NomResult<ReversePath> {
+    delimited(opt(cfws::<P>),
+              alt((rfc5321::reverse_path::<P>,
+                   map(tuple((tag("<"), opt(cfws::<P>), tag(">"))), |_| ReversePath::Null))),
+              pair(opt(cfws::<P>), opt(crlf)))(i)
+}
+
 /// Parse the content of a `"Reply-To:"` header.
 ///
 /// Returns a list of addresses.
 pub fn reply_to<P: UTF8Policy>(i: &[u8]) -> NomResult<Vec<Address>> {
     address_list_crlf::<P>(i)
 }
+
+/// Parse the content of a `"To:"` header.
+///
+/// Returns a list of addresses. Group names are preserved.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::{to, Address};
+///
+/// let (_, addresses) = to::<Intl>(b"Team: a@example.org, \"B\" <b@example.org>;, c@example.org\r\n").unwrap();
+///
+/// assert_eq!(addresses.len(), 2);
+/// match &addresses[0] {
+///     Address::Group(team) => {
+///         assert_eq!(team.dname, "Team");
+///         assert_eq!(team.members.len(), 2);
+///     }
+///     other => panic!("unexpected {:?}", other),
+/// }
+/// ```
+pub fn to<P: UTF8Policy>(i: &[u8]) -> NomResult<Vec<Address>> {
+    address_list_crlf::<P>(i)
+}
+
+/// Parse the content of a `"Cc:"` header.
+///
+/// Returns a list of addresses.
+pub fn cc<P: UTF8Policy>(i: &[u8]) -> NomResult<Vec<Address>> {
+    address_list_crlf::<P>(i)
+}
+
+/// Collect the unique domains of an address list.
+///
+/// Group members are included but not the group names. Domains are
+/// compared case-insensitively and address literals by their canonical
+/// form. The first spelling of each domain is kept, in order of
+/// appearance.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::{to, unique_domains};
+///
+/// let (_, addresses) = to::<Intl>(b"Team: a@example.org, b@Example.ORG;, c@[192.0.2.1], d@example.net\r\n").unwrap();
+/// let domains: Vec<_> = unique_domains(&addresses).iter().map(|d| d.to_string()).collect();
+///
+/// assert_eq!(domains, ["example.org", "[192.0.2.1]", "example.net"]);
+/// ```
+pub fn unique_domains(addrs: &[Address]) -> Vec<DomainPart> {
+    let mut seen = BTreeSet::new();
+
+    addrs.iter()
+        .flat_map(|a| match a {
+            Address::Mailbox(m) => core::slice::from_ref(m),
+            Address::Group(g) => &g.members[..],
+        })
+        .map(|m| m.address.domain_part())
+        .filter(|d| seen.insert(match d {
+            DomainPart::Domain(_) => d.normalized(),
+            DomainPart::Address(a) => DomainPart::Address(a.canonical()),
+        }))
+        .cloned()
+        .collect()
+}
+
+fn no_fold_literal<P: UTF8Policy>(input: &[u8]) -> NomResult<AddressLiteral> {
+    map(delimited(tag("["), recognize_many0(P::dtext), tag("]")), |l| {
+        let literal = AddressLiteral::FreeForm(str::from_utf8(l).unwrap().into());
+        literal.upgrade().unwrap_or(literal)
+    })(input)
+}
+
+fn msg_id<P: UTF8Policy>(input: &[u8]) -> NomResult<(String, DomainPart)> {
+    delimited(pair(opt(cfws::<P>), tag("<")),
+              separated_pair(map(dot_atom_text::<P>, |l| str::from_utf8(l).unwrap().into()),
+                             tag("@"),
+                             alt((map(dot_atom_text::<P>, |r| DomainPart::Domain(Domain(str::from_utf8(r).unwrap().into()))),
+                                  map(no_fold_literal::<P>, DomainPart::Address)))),
+              pair(tag(">"), opt(cfws::<P>)))(input)
+}
+
+#[cfg(feature = "chrono")]
+fn _digits(min: usize, max: usize) -> impl Fn(&[u8]) -> NomResult<(usize, u32)> {
+    move |input| map(take_while_m_n(min, max, is_digit),
+                     |d: &[u8]| (d.len(), str::from_utf8(d).unwrap().parse().unwrap()))(input)
+}
+
+#[cfg(feature = "chrono")]
+fn day_name(input: &[u8]) -> NomResult<&[u8]> {
+    alt((tag_no_case("Mon"), tag_no_case("Tue"), tag_no_case("Wed"), tag_no_case("Thu"),
+         tag_no_case("Fri"), tag_no_case("Sat"), tag_no_case("Sun")))(input)
+}
+
+#[cfg(feature = "chrono")]
+fn month(input: &[u8]) -> NomResult<u32> {
+    alt((map(tag_no_case("Jan"), |_| 1), map(tag_no_case("Feb"), |_| 2), map(tag_no_case("Mar"), |_| 3),
+         map(tag_no_case("Apr"), |_| 4), map(tag_no_case("May"), |_| 5), map(tag_no_case("Jun"), |_| 6),
+         map(tag_no_case("Jul"), |_| 7), map(tag_no_case("Aug"), |_| 8), map(tag_no_case("Sep"), |_| 9),
+         map(tag_no_case("Oct"), |_| 10), map(tag_no_case("Nov"), |_| 11), map(tag_no_case("Dec"), |_| 12)))(input)
+}
+
+// Two and three digit years are interpreted as per RFC 5322 section 4.3.
+#[cfg(feature = "chrono")]
+fn year(input: &[u8]) -> NomResult<i32> {
+    map(_digits(2, 9), |(len, year)| match (len, year as i32) {
+        (2, year) if year < 50 => year + 2000,
+        (2, year) | (3, year) => year + 1900,
+        (_, year) => year,
+    })(input)
+}
+
+// Returns the offset from UTC in seconds.
+#[cfg(feature = "chrono")]
+fn zone(input: &[u8]) -> NomResult<i32> {
+    let numeric = map(pair(alt((map(tag("+"), |_| 1), map(tag("-"), |_| -1))),
+                           pair(_digits(2, 2), verify(_digits(2, 2), |(_, m)| *m < 60))),
+                      |(sign, ((_, h), (_, m)))| sign * (h * 3600 + m * 60) as i32);
+    let named = alt((map(alt((tag_no_case("UT"), tag_no_case("GMT"))), |_| 0),
+                     map(tag_no_case("EST"), |_| -5), map(tag_no_case("EDT"), |_| -4),
+                     map(tag_no_case("CST"), |_| -6), map(tag_no_case("CDT"), |_| -5),
+                     map(tag_no_case("MST"), |_| -7), map(tag_no_case("MDT"), |_| -6),
+                     map(tag_no_case("PST"), |_| -8), map(tag_no_case("PDT"), |_| -7)));
+    // Military zones are treated as "-0000" since their sign was
+    // historically inverted.
+    let military = take1_filter(|c| c.is_ascii_alphabetic() && !c.eq_ignore_ascii_case(&b'J'));
+
+    alt((numeric, map(named, |h| h * 3600), map(military, |_| 0)))(input)
+}
+
+#[cfg(feature = "chrono")]
+fn date_time<P: UTF8Policy>(input: &[u8]) -> NomResult<DateTime<FixedOffset>> {
+    let colon = || delimited(opt(cfws::<P>), tag(":"), opt(cfws::<P>));
+
+    map_opt(tuple((opt(terminated(delimited(opt(cfws::<P>), day_name, opt(cfws::<P>)), tag(","))),
+                   delimited(opt(cfws::<P>), _digits(1, 2), opt(cfws::<P>)),
+                   month,
+                   delimited(opt(cfws::<P>), year, opt(cfws::<P>)),
+                   _digits(2, 2),
+                   preceded(colon(), _digits(2, 2)),
+                   opt(preceded(colon(), _digits(2, 2))),
+                   delimited(opt(cfws::<P>), zone, opt(cfws::<P>)))),
+            |(_, (_, day), month, year, (_, hour), (_, minute), second, offset)| {
+                let date = NaiveDate::from_ymd_opt(year, month, day)?;
+                let time = match second.map(|(_, s)| s).unwrap_or(0) {
+                    60 => NaiveTime::from_hms_milli_opt(hour, minute, 59, 1_000),
+                    second => NaiveTime::from_hms_opt(hour, minute, second),
+                }?;
+                FixedOffset::east_opt(offset)?.from_local_datetime(&date.and_time(time)).single()
+            })(input)
+}
+
+/// Parse the content of a `"Date:"` header.
+///
+/// The obsolete syntax, including two digit years and named time
+/// zones, is accepted. The day of the week is ignored.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::date;
+///
+/// let (_, parsed) = date::<Intl>(b"Fri, 21 Nov 1997 09:55:06 -0600\r\n").unwrap();
+/// assert_eq!(parsed.to_rfc3339(), "1997-11-21T09:55:06-06:00");
+///
+/// let (_, parsed) = date::<Intl>(b"21 Nov 97 09:55 EST (Eastern)").unwrap();
+/// assert_eq!(parsed.to_rfc3339(), "1997-11-21T09:55:00-05:00");
+/// ```
+#[cfg(feature = "chrono")]
+pub fn date<P: UTF8Policy>(i: &[u8]) -> NomResult<DateTime<FixedOffset>> {
+    terminated(date_time::<P>, opt(crlf))(i)
+}
+
+/// Parse the content of a `"Message-ID:"` header.
+///
+/// Returns the left and right parts of the identifier. The right part
+/// is parsed as a domain or an address literal but is not required to
+/// be a valid DNS name.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::message_id;
+///
+/// let (_, (left, right)) = message_id::<Intl>(b" <1234.5678@mail.example.org> (comment)\r\n").unwrap();
+///
+/// assert_eq!(left, "1234.5678");
+/// assert_eq!(right.to_string(), "mail.example.org");
+/// ```
+pub fn message_id<P: UTF8Policy>(i: &[u8]) -> NomResult<(String, DomainPart)> {
+    terminated(msg_id::<P>, opt(crlf))(i)
+}
+
+/// A host in the FROM or BY clause of a `"Received:"` header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReceivedHost {
+    /// The host name or address literal.
+    pub domain: DomainPart,
+    /// The comments following the host, usually containing the
+    /// reverse DNS name and IP address of the peer.
+    pub comments: Vec<String>,
+}
+
+/// The content of a `"Received:"` header.
+///
+/// Each clause is `None` if not present.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Received {
+    /// The FROM clause, with the host name given by the client.
+    pub from: Option<ReceivedHost>,
+    /// The BY clause, identifying the receiving host.
+    pub by: Option<ReceivedHost>,
+    /// The VIA clause link type.
+    pub via: Option<String>,
+    /// The WITH clause protocol such as `"ESMTP"`.
+    pub with: Option<String>,
+    /// The ID clause, either a dot-atom or a `"<msg-id>"`.
+    pub id: Option<String>,
+    /// The FOR clause recipient.
+    pub recipient: Option<types::Mailbox>,
+    /// The unfolded date and time following the `";"`.
+    ///
+    /// See [`date`] with the `chrono` feature to parse it.
+    pub date: String,
+}
+
+enum ReceivedClause {
+    From(ReceivedHost),
+    By(ReceivedHost),
+    Via(String),
+    With(String),
+    Id(String),
+    For(types::Mailbox),
+}
+
+// Bare IPv6 addresses are used by some large providers.
+fn received_host<P: UTF8Policy>(input: &[u8]) -> NomResult<ReceivedHost> {
+    let bare_ipv6 = map_res(verify(take_while1(|c: u8| c.is_ascii_hexdigit() || c == b':' || c == b'.'),
+                                   |ip: &[u8]| ip.contains(&b':')),
+                            |ip| Ipv6Addr::from_str(str::from_utf8(ip).unwrap()));
+
+    map(pair(alt((map(bare_ipv6, |ip| DomainPart::Address(AddressLiteral::IP(ip.into()))),
+                  map(dot_atom_text::<P>, |d| DomainPart::Domain(Domain(str::from_utf8(d).unwrap().into()))),
+                  map(no_fold_literal::<P>, DomainPart::Address))),
+             opt(cfws_comments::<P>)),
+        |(domain, comments)| ReceivedHost{domain, comments: comments.unwrap_or_default()})(input)
+}
+
+fn received_clause<P: UTF8Policy>(input: &[u8]) -> NomResult<ReceivedClause> {
+    let keyword = |kw| terminated(tag_no_case(kw), cfws::<P>);
+    let text = |t: &[u8]| str::from_utf8(t).unwrap().to_string();
+
+    preceded(opt(cfws::<P>), alt((
+        map(preceded(keyword("from"), received_host::<P>), ReceivedClause::From),
+        map(preceded(keyword("by"), received_host::<P>), ReceivedClause::By),
+        map(preceded(keyword("via"), atom::<P>), move |a| ReceivedClause::Via(text(a))),
+        map(preceded(keyword("with"), atom::<P>), move |a| ReceivedClause::With(text(a))),
+        map(preceded(keyword("id"), alt((map(msg_id::<P>, |(l, r)| format!("<{}@{}>", l, r)),
+                                         map(dot_atom::<P>, |a| a.0)))), ReceivedClause::Id),
+        map(preceded(keyword("for"), alt((angle_addr::<P>, addr_spec::<P>))), ReceivedClause::For),
+    )))(input)
+}
+
+/// Parse the content of a `"Received:"` header.
+///
+/// The clauses from [RFC 5321] are accepted in any order. Only the
+/// first occurrence of a clause is kept.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5322::received;
+///
+/// let (_, parsed) = received::<Intl>(b"from mail.example.org (mail.example.org [192.0.2.1])\r
+///  by mx.example.com (Postfix) with ESMTPS id 4ABC123 for <bob@example.com>;\r
+///  Tue, 1 Jul 2003 10:52:37 +0200\r\n").unwrap();
+///
+/// let from = parsed.from.unwrap();
+/// assert_eq!(from.domain.to_string(), "mail.example.org");
+/// assert_eq!(from.comments, ["mail.example.org [192.0.2.1]"]);
+/// assert_eq!(parsed.by.unwrap().comments, ["Postfix"]);
+/// assert_eq!(parsed.with.as_deref(), Some("ESMTPS"));
+/// assert_eq!(parsed.id.as_deref(), Some("4ABC123"));
+/// assert_eq!(parsed.recipient.unwrap().to_string(), "bob@example.com");
+/// assert_eq!(parsed.date, "Tue, 1 Jul 2003 10:52:37 +0200");
+/// ```
+///
+/// [RFC 5321]: https://tools.ietf.org/html/rfc5321#section-4.4
+pub fn received<P: UTF8Policy>(i: &[u8]) -> NomResult<Received> {
+    map(pair(many0(received_clause::<P>), preceded(pair(opt(cfws::<P>), tag(";")), rest)),
+        |(clauses, date): (_, &[u8])| {
+            let mut out = Received{from: None, by: None, via: None, with: None, id: None, recipient: None,
+                                   date: String::from_utf8_lossy(&unfold(date)).trim().into()};
+
+            for clause in clauses {
+                match clause {
+                    ReceivedClause::From(h) => { out.from.get_or_insert(h); },
+                    ReceivedClause::By(h) => { out.by.get_or_insert(h); },
+                    ReceivedClause::Via(v) => { out.via.get_or_insert(v); },
+                    ReceivedClause::With(w) => { out.with.get_or_insert(w); },
+                    ReceivedClause::Id(id) => { out.id.get_or_insert(id); },
+                    ReceivedClause::For(m) => { out.recipient.get_or_insert(m); },
+                }
+            }
+            out
+        })(i)
+}
+
+#[cfg(feature = "chrono")]
+impl Received {
+    /// Parse the date and time of this header.
+    pub fn date_time(&self) -> Option<DateTime<FixedOffset>> {
+        exact!(self.date.as_bytes(), date::<Intl>).ok().map(|(_, d)| d)
+    }
+}
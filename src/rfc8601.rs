@@ -0,0 +1,159 @@
+//! [Authentication-Results] header parser
+//!
+//! Comments are ignored. Keywords such as method names and results
+//! are returned in lowercase.
+//!
+//! [Authentication-Results]: https://tools.ietf.org/html/rfc8601
+
+use core::str::{self, FromStr};
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case, take_while1};
+use nom::character::complete::digit1;
+use nom::combinator::{eof, map, map_res, opt};
+use nom::multi::{many0, many1};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+
+use crate::rfc2231::token;
+use crate::rfc5234::crlf;
+use crate::rfc5321::ldh_str;
+use crate::rfc5322::{_domain, cfws, local_part, quoted_string, UTF8Policy};
+use crate::prelude::*;
+use crate::util::*;
+
+/// Content of an `"Authentication-Results:"` header.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthenticationResults {
+    /// Identifier of the host that performed the checks.
+    pub authserv_id: String,
+    /// Version of the header syntax, if given.
+    pub version: Option<u32>,
+    /// Results of each check. Empty if the header says `"none"`.
+    pub results: Vec<AuthResult>,
+}
+
+/// Result of a single authentication method such as `"dkim=pass"`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthResult {
+    /// Method name such as `"spf"` or `"dkim"`.
+    pub method: String,
+    /// Version of the method, if given.
+    pub method_version: Option<u32>,
+    /// Result such as `"pass"` or `"fail"`.
+    pub result: String,
+    /// Value of the `reason` property.
+    pub reason: Option<String>,
+    /// Properties describing what was checked.
+    pub properties: Vec<AuthProperty>,
+}
+
+/// Property of a result such as `"header.d=example.org"`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthProperty {
+    /// Type of the property such as `"smtp"` or `"header"`.
+    pub ptype: String,
+    /// Name of the property such as `"mailfrom"` or `"d"`.
+    pub property: String,
+    /// Value of the property as it appears in the header.
+    pub value: String,
+}
+
+impl AuthResult {
+    /// Look up the value of a property such as `("header", "d")`.
+    pub fn property(&self, ptype: &str, property: &str) -> Option<&str> {
+        self.properties.iter()
+            .find(|p| p.ptype.eq_ignore_ascii_case(ptype) && p.property.eq_ignore_ascii_case(property))
+            .map(|p| p.value.as_str())
+    }
+}
+
+fn keyword(input: &[u8]) -> NomResult<String> {
+    map(map_res(ldh_str, str::from_utf8), str::to_lowercase)(input)
+}
+
+fn value<P: UTF8Policy>(input: &[u8]) -> NomResult<String> {
+    alt((map(token, String::from), map(quoted_string::<P>, String::from)))(input)
+}
+
+fn version<P: UTF8Policy>(input: &[u8]) -> NomResult<u32> {
+    terminated(map_res(map_res(digit1, str::from_utf8), u32::from_str), opt(cfws::<P>))(input)
+}
+
+fn _equals<P: UTF8Policy>(input: &[u8]) -> NomResult<()> {
+    map(tuple((opt(cfws::<P>), tag("="), opt(cfws::<P>))), |_| ())(input)
+}
+
+// An address or domain, as in smtp.mailfrom=bob@example.org.
+fn _address_value<P: UTF8Policy>(input: &[u8]) -> NomResult<String> {
+    map(separated_pair(opt(local_part::<P>), tag("@"), _domain::<P>), |(lp, domain)| match lp {
+        Some(lp) => format!("{}@{}", lp, domain),
+        None => format!("@{}", domain),
+    })(input)
+}
+
+// A token that may also contain "/" and "=". Real MTAs send base64
+// values such as header.b=LSE0y4/K unquoted.
+fn _base64_token(input: &[u8]) -> NomResult<String> {
+    map(take_while1(|c| (33..=126).contains(&c) && !b"()<>@,;:\\\"[]?".contains(&c)),
+        |t| str::from_utf8(t).unwrap().into())(input)
+}
+
+fn pvalue<P: UTF8Policy>(input: &[u8]) -> NomResult<String> {
+    delimited(opt(cfws::<P>), alt((_address_value::<P>, _base64_token, value::<P>)), opt(cfws::<P>))(input)
+}
+
+fn propspec<P: UTF8Policy>(input: &[u8]) -> NomResult<AuthProperty> {
+    map(tuple((keyword,
+               preceded(tuple((opt(cfws::<P>), tag("."), opt(cfws::<P>))), keyword),
+               preceded(pair(opt(cfws::<P>), tag("=")), pvalue::<P>))),
+        |(ptype, property, value)| AuthProperty{ptype, property, value})(input)
+}
+
+fn reasonspec<P: UTF8Policy>(input: &[u8]) -> NomResult<String> {
+    preceded(pair(tag_no_case("reason"), _equals::<P>), value::<P>)(input)
+}
+
+fn methodspec<P: UTF8Policy>(input: &[u8]) -> NomResult<((String, Option<u32>), String)> {
+    separated_pair(pair(keyword, opt(preceded(tuple((opt(cfws::<P>), tag("/"), opt(cfws::<P>))), version::<P>))),
+                   _equals::<P>,
+                   keyword)(input)
+}
+
+fn resinfo<P: UTF8Policy>(input: &[u8]) -> NomResult<AuthResult> {
+    map(tuple((preceded(tuple((opt(cfws::<P>), tag(";"), opt(cfws::<P>))), methodspec::<P>),
+               opt(preceded(opt(cfws::<P>), reasonspec::<P>)),
+               many0(preceded(opt(cfws::<P>), propspec::<P>)))),
+        |(((method, method_version), result), reason, properties)| AuthResult {
+            method, method_version, result, reason, properties,
+        })(input)
+}
+
+fn no_result<P: UTF8Policy>(input: &[u8]) -> NomResult<Vec<AuthResult>> {
+    map(tuple((opt(cfws::<P>), tag(";"), opt(cfws::<P>), tag_no_case("none"))), |_| Vec::new())(input)
+}
+
+/// Parse the content of an `"Authentication-Results:"` header.
+///
+/// A trailing semicolon is tolerated. The whole input must be
+/// consumed, up to an optional CRLF.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc8601::authentication_results;
+///
+/// let input = b"mx.example.com;\r\n spf=pass (sender is authorized) smtp.mailfrom=bob@example.org;\r\n dkim=pass header.d=example.org\r\n";
+/// let (_, ar) = authentication_results::<Intl>(input).unwrap();
+///
+/// assert_eq!(ar.authserv_id, "mx.example.com");
+/// assert_eq!(ar.results[0].method, "spf");
+/// assert_eq!(ar.results[0].property("smtp", "mailfrom"), Some("bob@example.org"));
+/// assert_eq!(ar.results[1].result, "pass");
+/// assert_eq!(ar.results[1].property("header", "d"), Some("example.org"));
+/// ```
+pub fn authentication_results<P: UTF8Policy>(input: &[u8]) -> NomResult<AuthenticationResults> {
+    map(terminated(tuple((preceded(opt(cfws::<P>), value::<P>),
+                          opt(preceded(opt(cfws::<P>), version::<P>)),
+                          alt((many1(resinfo::<P>), no_result::<P>)))),
+                   tuple((opt(cfws::<P>), opt(tag(";")), opt(cfws::<P>), opt(crlf), eof))),
+        |(authserv_id, version, results)| AuthenticationResults{authserv_id, version, results})(input)
+}
@@ -1,4 +1,17 @@
+#[cfg(feature = "chrono")]
+mod test_date;
 mod test_headersection;
+#[cfg(feature = "std")]
+mod test_mime;
+mod test_proxy_protocol;
+mod test_rfc2047;
 mod test_rfc2231;
+mod test_rfc3461;
+mod test_rfc3463;
 mod test_rfc5321;
 mod test_rfc5322;
+mod test_rfc8601;
+mod test_roundtrip;
+#[cfg(feature = "serde")]
+mod test_serde;
+mod test_xclient;
@@ -0,0 +1,64 @@
+use chrono::{DateTime, FixedOffset};
+
+use crate::behaviour::Intl;
+use crate::rfc5322::date;
+
+fn parse(input: &str) -> Option<DateTime<FixedOffset>> {
+    exact!(input.as_bytes(), date::<Intl>).ok().map(|(_, d)| d)
+}
+
+fn rfc3339(input: &str) -> Option<String> {
+    parse(input).map(|d| d.to_rfc3339())
+}
+
+#[test]
+fn modern() {
+    assert_eq!(rfc3339("Tue, 1 Jul 2003 10:52:37 +0200"), Some("2003-07-01T10:52:37+02:00".into()));
+    assert_eq!(rfc3339("1 Jul 2003 10:52 -0330\r\n"), Some("2003-07-01T10:52:00-03:30".into()));
+    assert_eq!(rfc3339("Thu, 13 Feb 1969 23:32:54 -0000"), Some("1969-02-13T23:32:54+00:00".into()));
+}
+
+#[test]
+fn obsolete_years() {
+    assert_eq!(rfc3339("1 Jan 49 00:00 +0000"), Some("2049-01-01T00:00:00+00:00".into()));
+    assert_eq!(rfc3339("1 Jan 50 00:00 +0000"), Some("1950-01-01T00:00:00+00:00".into()));
+    assert_eq!(rfc3339("1 Jan 103 00:00 +0000"), Some("2003-01-01T00:00:00+00:00".into()));
+}
+
+#[test]
+fn obsolete_zones() {
+    assert_eq!(rfc3339("1 Jan 2000 12:00 GMT"), Some("2000-01-01T12:00:00+00:00".into()));
+    assert_eq!(rfc3339("1 Jan 2000 12:00 UT"), Some("2000-01-01T12:00:00+00:00".into()));
+    assert_eq!(rfc3339("1 Jan 2000 12:00 pdt"), Some("2000-01-01T12:00:00-07:00".into()));
+    assert_eq!(rfc3339("1 Jan 2000 12:00 Z"), Some("2000-01-01T12:00:00+00:00".into()));
+    assert_eq!(parse("1 Jan 2000 12:00 J"), None);
+}
+
+#[test]
+fn cfws() {
+    assert_eq!(rfc3339("Fri(day), 21\r\n Nov 1997 09 (hour) : 55 : 06 (sec) -0600 (CST)"),
+               Some("1997-11-21T09:55:06-06:00".into()));
+    assert_eq!(rfc3339(" Fri , 21 Nov 1997 09:55:06 -0600 "), Some("1997-11-21T09:55:06-06:00".into()));
+}
+
+#[test]
+fn leap_second() {
+    assert_eq!(rfc3339("31 Dec 2016 23:59:60 +0000"), Some("2016-12-31T23:59:60+00:00".into()));
+}
+
+#[test]
+fn invalid() {
+    assert_eq!(parse("31 Feb 2000 12:00 +0000"), None);
+    assert_eq!(parse("1 Foo 2000 12:00 +0000"), None);
+    assert_eq!(parse("1 Jan 2000 24:00 +0000"), None);
+    assert_eq!(parse("1 Jan 2000 12:60 +0000"), None);
+    assert_eq!(parse("1 Jan 2000 12:00 +0060"), None);
+    assert_eq!(parse("1 Jan 2000 12:00"), None);
+    assert_eq!(parse("Jan 1 2000 12:00 +0000"), None);
+}
+
+#[test]
+fn received_date_time() {
+    let (_, r) = crate::rfc5322::received::<Intl>(b"by mx.example.com; Tue, 1 Jul 2003 10:52:37 +0200 (CEST)\r\n").unwrap();
+    assert_eq!(r.date_time().map(|d| d.to_rfc3339()), Some("2003-07-01T10:52:37+02:00".into()));
+}
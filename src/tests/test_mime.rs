@@ -0,0 +1,37 @@
+use crate::mime::*;
+
+#[test]
+fn content_type_struct() {
+    let ct = content_type(b" multipart/MIXED; BOUNDARY=\"xyz; zy\"; charset=utf-8;\r\n").unwrap();
+    assert_eq!(ct.media_type, "multipart");
+    assert_eq!(ct.subtype, "mixed");
+    assert_eq!(ct.params.len(), 2);
+    assert_eq!(ct.param("boundary"), Some("xyz; zy"));
+    assert_eq!(ct.param("charset"), Some("utf-8"));
+    assert_eq!(ct.param("name"), None);
+}
+
+#[test]
+fn continuations() {
+    let ct = content_type(b"Text/Plain; title*0=\"a \"; title*1=b\r\n").unwrap();
+    assert_eq!(ct.to_string(), "text/plain");
+    assert_eq!(ct.param("title"), Some("a b"));
+    assert!(content_type(b"text/plain; title*0=a; title*1=b").is_ok());
+}
+
+#[test]
+fn duplicates() {
+    let dup = |name: &str| Err(MimeError::DuplicateParameter(name.into()));
+
+    assert_eq!(content_type(b"text/plain; charset=utf-8; Charset=latin1"), dup("charset"));
+    assert_eq!(content_type(b"text/plain; title=a; title*0=b"), dup("title"));
+    assert_eq!(content_type(b"text/plain; title*0=a; title*0=b"), dup("title"));
+    assert_eq!(content_type(b"text/plain; title=a; title*=''b"), dup("title"));
+}
+
+#[test]
+fn invalid() {
+    assert_eq!(content_type(b"text"), Err(MimeError::Syntax(4)));
+    assert!(content_type(b"text/").is_err());
+    assert_eq!(content_type(b"text/plain junk"), Err(MimeError::Syntax(11)));
+}
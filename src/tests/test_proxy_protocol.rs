@@ -0,0 +1,70 @@
+use std::net::SocketAddr;
+
+use crate::proxy_protocol::*;
+
+fn tcp(source: &str, destination: &str) -> ProxyInfo {
+    ProxyInfo::Tcp {
+        source: source.parse::<SocketAddr>().unwrap(),
+        destination: destination.parse::<SocketAddr>().unwrap(),
+    }
+}
+
+#[test]
+fn tcp4() {
+    assert_eq!(parse_proxy_v1(b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 25\r\n"),
+               Ok(tcp("192.0.2.1:56324", "198.51.100.1:25")));
+    assert_eq!(parse_proxy_v1(b"PROXY TCP4 0.0.0.0 255.255.255.255 0 65535\r\n"),
+               Ok(tcp("0.0.0.0:0", "255.255.255.255:65535")));
+}
+
+#[test]
+fn tcp6() {
+    assert_eq!(parse_proxy_v1(b"PROXY TCP6 2001:db8::1 ::1 56324 587\r\n"),
+               Ok(tcp("[2001:db8::1]:56324", "[::1]:587")));
+}
+
+#[test]
+fn unknown() {
+    assert_eq!(parse_proxy_v1(b"PROXY UNKNOWN\r\n"), Ok(ProxyInfo::Unknown));
+    assert_eq!(parse_proxy_v1(b"PROXY UNKNOWN ffff:f...f:ffff 1.2.3.4 65535 65535\r\n"), Ok(ProxyInfo::Unknown));
+}
+
+#[test]
+fn mismatched_family() {
+    assert_eq!(parse_proxy_v1(b"PROXY TCP4 2001:db8::1 ::1 1 2\r\n"), Err(ProxyError::Invalid));
+    assert_eq!(parse_proxy_v1(b"PROXY TCP6 192.0.2.1 198.51.100.1 1 2\r\n"), Err(ProxyError::Invalid));
+}
+
+#[test]
+fn invalid() {
+    for input in &[&b"PROXY TCP4 192.0.2.1 198.51.100.1 56324\r\n"[..],
+                   b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 65536\r\n",
+                   b"PROXY TCP4 192.0.2.1 198.51.100.1 056324 25\r\n",
+                   b"PROXY TCP4 192.0.2.01 198.51.100.1 56324 25\r\n",
+                   b"PROXY TCP4  192.0.2.1 198.51.100.1 56324 25\r\n",
+                   b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 25\r\nEHLO",
+                   b"proxy TCP4 192.0.2.1 198.51.100.1 56324 25\r\n",
+                   b"EHLO example.org\r\n",
+                   b"EHLO"] {
+        assert_eq!(parse_proxy_v1(input), Err(ProxyError::Invalid));
+    }
+}
+
+#[test]
+fn incomplete() {
+    assert_eq!(parse_proxy_v1(b""), Err(ProxyError::Incomplete));
+    assert_eq!(parse_proxy_v1(b"PRO"), Err(ProxyError::Incomplete));
+    assert_eq!(parse_proxy_v1(b"PROXY TCP4 192.0.2.1"), Err(ProxyError::Incomplete));
+}
+
+#[test]
+fn too_long() {
+    let mut long = b"PROXY UNKNOWN ".to_vec();
+    long.resize(MAX_V1_LENGTH, b'x');
+    assert_eq!(parse_proxy_v1(&long), Err(ProxyError::TooLong));
+    long.extend_from_slice(b"\r\n");
+    assert_eq!(parse_proxy_v1(&long), Err(ProxyError::TooLong));
+    long.truncate(MAX_V1_LENGTH - 2);
+    long.extend_from_slice(b"\r\n");
+    assert_eq!(parse_proxy_v1(&long), Ok(ProxyInfo::Unknown));
+}
@@ -0,0 +1,34 @@
+use crate::rfc2047::*;
+
+#[test]
+fn decode_q_and_b() {
+    assert_eq!(decode_encoded_words("=?utf-8?q?caf=C3=A9_cr=C3=A8me?="), "café crème");
+    assert_eq!(decode_encoded_words("=?UTF-8?B?Y2Fmw6k=?="), "café");
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn decode_legacy_charset() {
+    assert_eq!(decode_encoded_words("=?iso-8859-1?q?caf=E9?="), "café");
+}
+
+// Only ASCII and UTF-8 are known without std.
+#[test]
+#[cfg(not(feature = "std"))]
+fn decode_unknown_charset() {
+    assert_eq!(decode_encoded_words("=?iso-8859-1?q?caf=E9?="), "caf\u{fffd}");
+}
+
+#[test]
+fn decode_adjacent() {
+    assert_eq!(decode_encoded_words("=?UTF-8?Q?a?= =?UTF-8?Q?b?=\r\n =?UTF-8?Q?c?="), "abc");
+    assert_eq!(decode_encoded_words("=?UTF-8?Q?a?= b =?UTF-8?Q?c?="), "a b c");
+}
+
+#[test]
+fn decode_plain() {
+    assert_eq!(decode_encoded_words(""), "");
+    assert_eq!(decode_encoded_words("plain text"), "plain text");
+    assert_eq!(decode_encoded_words("not=?utf-8?q?encoded?="), "not=?utf-8?q?encoded?=");
+    assert_eq!(decode_encoded_words("=?bogus"), "=?bogus");
+}
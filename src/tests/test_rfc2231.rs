@@ -24,6 +24,7 @@ fn header_lf() {
 
 #[cfg_attr(not(feature = "quoted-string-rfc2047"), should_panic)]
 #[test]
+#[cfg(feature = "std")]
 fn header_crlf() {
     let (rem, (mtype, params)) = content_type(b"application/pdf; name=\r\n\t\"=?Windows-1252?Q?Fiche_d=92information_relative_=E0_la_garantie_facultati?=\r\n =?Windows-1252?Q?ve.pdf?=\"\r\n").unwrap();
     assert_eq!(rem.len(), 0);
@@ -33,6 +34,7 @@ fn header_crlf() {
 
 #[cfg_attr(not(feature = "quoted-string-rfc2047"), should_panic)]
 #[test]
+#[cfg(feature = "std")]
 fn attmsg1() {
     let (rem, (mtype, params)) = content_type(b"message/rfc822;\r\n name=\"=?windows-1252?Q?=5BThe_Listserve=5D_Have_you_ever_seen_somet?=\r\n =?windows-1252?Q?hing_you_couldn=92t_explain=3F=2Eeml?=\"").unwrap();
     assert_eq!(rem.len(), 0);
@@ -41,6 +43,7 @@ fn attmsg1() {
 }
 
 #[test]
+#[cfg(feature = "std")]
 fn attmsg2() {
     let (rem, (disp, params)) = content_disposition(b" attachment;\r\n filename*0*=windows-1252''%5B%54%68%65%20%4C%69%73%74%73%65%72%76%65%5D%20;\r\n filename*1*=%48%61%76%65%20%79%6F%75%20%65%76%65%72%20%73%65%65%6E%20%73;\r\n filename*2*=%6F%6D%65%74%68%69%6E%67%20%79%6F%75%20%63%6F%75%6C%64%6E%92;\r\n filename*3*=%74%20%65%78%70%6C%61%69%6E%3F%2E%65%6D%6C").unwrap();
     assert_eq!(rem.len(), 0);
@@ -50,6 +53,7 @@ fn attmsg2() {
 
 #[cfg_attr(not(feature = "quoted-string-rfc2047"), should_panic)]
 #[test]
+#[cfg(feature = "std")]
 fn attmsg3() {
     let (rem, (mtype, params)) = content_type(b"message/rfc822;\r\n name=\"[decoupe CNC] Re: H_S_ envoyer de =?windows-1252?Q?=AB_gros_=BB_fic?=\r\n =?windows-1252?Q?hiers=2Eeml?=\"").unwrap();
     assert_eq!(rem.len(), 0);
@@ -58,6 +62,7 @@ fn attmsg3() {
 }
 
 #[test]
+#[cfg(feature = "std")]
 fn attmsg4() {
     let (rem, (disp, params)) = content_disposition(b"attachment;\r\n filename*0*=windows-1252''%5B%64%65%63%6F%75%70%65%20%43%4E%43%5D%20%52%65;\r\n filename*1*=%3A%20%48%5F%53%5F%20%65%6E%76%6F%79%65%72%20%64%65%20%AB%20;\r\n filename*2*=%67%72%6F%73%20%BB%20%66%69%63%68%69%65%72%73%2E%65%6D%6C").unwrap();
     assert_eq!(rem.len(), 0);
@@ -151,6 +156,7 @@ macro_rules! green_tc {
 
 green_tc!(attfnboth, b"attachment; filename=\"foo-ae.html\"; filename*=UTF-8''foo-%c3%a4.html;", CD::Attachment, "foo-ä.html");
 green_tc!(attfnboth2, b"attachment; filename*=UTF-8''foo-%c3%a4.html; filename=\"foo-ae.html\"", CD::Attachment, "foo-ä.html");
+#[cfg(feature = "std")]
 green_tc!(attfnboth3, b"attachment; filename*0*=ISO-8859-15''euro-sign%3d%a4; filename*=ISO-8859-1''currency-sign%3d%a4", CD::Attachment, "euro-sign=€");
 green_tc!(attfncontenc, b"attachment; filename*0*=UTF-8''foo-%c3%a4; filename*1=\".html\"", CD::Attachment, "foo-ä.html");
 green_tc!(attfncontord, b"attachment; filename*1=\"bar\"; filename*0=\"foo\"", CD::Attachment, "foobar");
@@ -174,3 +180,9 @@ fn attfnbrokentokenutf() {
     let (rem, _) = content_disposition(b"attachment; filename=foo-\xC3\xA4.html").unwrap();
     assert_eq!(rem.len(), 0);
 }
+
+#[test]
+fn content_type_duplicates() {
+    // Unlike mime::content_type, the tuple parser accepts duplicates.
+    assert!(content_type(b"text/plain; a=1; a=2").is_ok());
+}
@@ -0,0 +1,96 @@
+use std::borrow::Cow;
+
+use crate::rfc3461::*;
+
+fn notify(value: &str) -> Result<Option<Notify>, NotifyError> {
+    notify_param(&[("NOTIFY", Some(value))])
+}
+
+#[test]
+fn notify_never() {
+    assert_eq!(notify("never"), Ok(Some(Notify{on_success: false, on_failure: false, delay: false})));
+}
+
+#[test]
+fn notify_list() {
+    assert_eq!(notify("Failure"), Ok(Some(Notify{on_success: false, on_failure: true, delay: false})));
+    assert_eq!(notify("SUCCESS,FAILURE,DELAY"), Ok(Some(Notify{on_success: true, on_failure: true, delay: true})));
+}
+
+#[test]
+fn notify_invalid() {
+    assert!(notify("NEVER,SUCCESS").is_err());
+    assert!(notify("SUCCESS,NEVER").is_err());
+    assert!(notify("SUCCESS,BOUNCE").is_err());
+    assert!(notify("SUCCESS,").is_err());
+    assert_eq!(notify(""), Err(NotifyError::Invalid));
+    assert_eq!(notify_param(&[("NOTIFY", None)]), Err(NotifyError::MissingValue));
+    assert_eq!(notify_param(&[("NOTIFY", Some("NEVER")), ("notify", Some("NEVER"))]), Err(NotifyError::Duplicate));
+    assert_eq!(dsn_rcpt_params(&[("NOTIFY", Some("BOUNCE"))]), Err("Invalid NOTIFY"));
+    assert!(dsn_rcpt_params(&[("NOTIFY", None)]).is_err());
+    assert!(dsn_rcpt_params(&[("NOTIFY", Some("NEVER")), ("notify", Some("NEVER"))]).is_err());
+}
+
+#[test]
+fn notify_absent() {
+    let (params, other) = dsn_rcpt_params(&[("SIZE", Some("100"))]).unwrap();
+    assert_eq!(params.notify, None);
+    assert_eq!(other, [("SIZE", Some("100"))]);
+}
+
+#[test]
+fn orcpt_decode() {
+    assert_eq!(orcpt_param(&[("orcpt", Some("rfc822;bob+2Bsmith+3D@example.org"))]),
+               Ok(Some(("rfc822".into(), "bob+smith=@example.org".into()))));
+    assert_eq!(orcpt_param(&[("NOTIFY", Some("NEVER"))]), Ok(None));
+}
+
+#[test]
+fn orcpt_invalid() {
+    assert_eq!(orcpt_param(&[("ORCPT", Some("rfc822;bob+@example.org"))]), Err(OrcptError::Invalid));
+    assert_eq!(orcpt_param(&[("ORCPT", Some("rfc822;bob+"))]), Err(OrcptError::Invalid));
+    assert_eq!(orcpt_param(&[("ORCPT", Some("rfc822;bob+Z1@example.org"))]), Err(OrcptError::Invalid));
+    assert_eq!(orcpt_param(&[("ORCPT", Some("bob@example.org"))]), Err(OrcptError::Invalid));
+    assert_eq!(orcpt_param(&[("ORCPT", None)]), Err(OrcptError::MissingValue));
+    assert_eq!(orcpt_param(&[("ORCPT", Some("rfc822;a@b")), ("ORCPT", Some("rfc822;a@b"))]),
+               Err(OrcptError::Duplicate));
+}
+
+#[test]
+fn rcpt_params_orcpt() {
+    let (params, _) = dsn_rcpt_params(&[("ORCPT", Some("rfc822;bob+2B1@example.org"))]).unwrap();
+    assert_eq!(params.orcpt, Some(("rfc822".into(), "bob+1@example.org".into())));
+    assert_eq!(dsn_rcpt_params(&[("ORCPT", Some("rfc822;bob+"))]), Err("Invalid ORCPT"));
+}
+
+#[test]
+fn xtext_roundtrip() {
+    for s in &["", "plain", "a+b=c", "tab\tspace ", "\u{7f}\u{1}", "~!", "é", "josé+garcía=例え"] {
+        assert_eq!(xtext_decode(&xtext_encode(s)).as_deref(), Ok(*s));
+    }
+}
+
+#[test]
+fn xtext_decode_invalid() {
+    assert_eq!(xtext_decode("+"), Err(XtextError{offset: 0}));
+    assert_eq!(xtext_decode("a+4"), Err(XtextError{offset: 1}));
+    assert_eq!(xtext_decode("ab+ZZ"), Err(XtextError{offset: 2}));
+    assert_eq!(xtext_decode("a+2b"), Err(XtextError{offset: 1}));
+    assert_eq!(xtext_decode("a=b"), Err(XtextError{offset: 1}));
+    assert_eq!(xtext_decode("a b"), Err(XtextError{offset: 1}));
+    // Escapes must decode to UTF-8.
+    assert_eq!(xtext_decode("+FF"), Err(XtextError{offset: 0}));
+    assert_eq!(xtext_decode("ab+C3"), Err(XtextError{offset: 2}));
+    assert_eq!(xtext_decode("a+C3+A9+C3b"), Err(XtextError{offset: 7}));
+}
+
+#[test]
+fn lower_case_hexchar() {
+    // The parameter parsers accept lower case escapes, unlike xtext_decode.
+    assert_eq!(orcpt_address(b"rfc822;a+2bb@x").unwrap().1, (Cow::from("rfc822"), Cow::from("a+b@x")));
+    let (params, _) = dsn_rcpt_params(&[("ORCPT", Some("rfc822;bob+2b1@example.org"))]).unwrap();
+    assert_eq!(params.orcpt, Some(("rfc822".into(), "bob+1@example.org".into())));
+    let (params, _) = dsn_mail_params(&[("ENVID", Some("a+2bb"))]).unwrap();
+    assert_eq!(params.envid, Some("a+b".into()));
+    assert!(xtext_decode("a+2bb").is_err());
+}
@@ -0,0 +1,46 @@
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use crate::rfc3463::*;
+
+#[test]
+fn valid() {
+    for (input, class, subject, detail) in &[("2.0.0", 2, 0, 0), ("4.7.1", 4, 7, 1), ("5.1.1", 5, 1, 1),
+                                             ("5.999.999", 5, 999, 999), ("4.04.010", 4, 4, 10)] {
+        let status = EnhancedStatus::from_str(input).unwrap();
+        assert_eq!((status.class(), status.subject(), status.detail()), (*class, *subject, *detail), "{}", input);
+    }
+    assert_eq!(EnhancedStatus::from_str("4.04.010").unwrap().to_string(), "4.4.10");
+}
+
+#[test]
+fn invalid() {
+    for input in &["", "5", "5.1", "5.1.", "5..1", "5.1.1.1", " 5.1.1", "5.a.1", "5.1.1 "] {
+        assert_eq!(EnhancedStatus::from_str(input), Err(StatusError::Syntax), "{}", input);
+    }
+    for input in &["1.1.1", "3.0.0", "6.1.1", "55.1.1"] {
+        assert_eq!(EnhancedStatus::from_str(input), Err(StatusError::InvalidClass), "{}", input);
+    }
+    for input in &["5.1000.1", "5.1.1000", "5.99999999999.1"] {
+        assert_eq!(EnhancedStatus::from_str(input), Err(StatusError::OutOfRange), "{}", input);
+    }
+}
+
+#[test]
+fn reply_text() {
+    let (rem, status) = enhanced_status_code(b"4.2.2 Mailbox full\r\n").unwrap();
+    assert_eq!(status, EnhancedStatus::try_from((4, 2, 2)).unwrap());
+    assert_eq!(rem, b" Mailbox full\r\n");
+    assert!(enhanced_status_code(b"4.2.2222 Mailbox full\r\n").is_err());
+}
+
+#[test]
+fn from_tuple() {
+    let status = EnhancedStatus::try_from((5, 7, 26)).unwrap();
+    assert_eq!(status.to_string(), "5.7.26");
+    assert!(status.is_permanent());
+    assert!(!status.is_transient());
+    assert!(EnhancedStatus::try_from((2, 0, 0)).unwrap().is_success());
+    assert_eq!(EnhancedStatus::try_from((3, 0, 0)), Err(StatusError::InvalidClass));
+    assert_eq!(EnhancedStatus::try_from((5, 1000, 0)), Err(StatusError::OutOfRange));
+}
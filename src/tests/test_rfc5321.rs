@@ -1,9 +1,12 @@
+use std::convert::TryFrom;
 use std::str::FromStr;
-use std::net::{IpAddr, Ipv4Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use crate::behaviour::*;
 use crate::rfc5321::*;
+use crate::rfc3461::{NotifyError, OrcptError};
 use crate::types::*;
+use crate::util::NomResult;
 
 fn dp<T: Into<String>>(value: T) -> DomainPart {
     DomainPart::Domain(Domain(value.into()))
@@ -79,6 +82,13 @@ fn postmaster_rcpt() {
     let (_, (path, params)) = rcpt_command::<Intl>(b"RCPT TO:<pOstmaster@Domain.example.org>\r\n").unwrap();
     assert_eq!(path, ForwardPath::PostMaster(Some(Domain::from_smtp(b"Domain.example.org").unwrap())));
     assert_eq!(params, []);
+
+    let (_, (path, _)) = rcpt_command::<Intl>(b"RCPT TO:<POSTMASTER@example.org> NOTIFY=NEVER\r\n").unwrap();
+    assert_eq!(path, ForwardPath::PostMaster(Some(Domain::from_smtp(b"example.org").unwrap())));
+
+    // Only a domain may follow the postmaster local part.
+    let (_, (path, _)) = rcpt_command::<Intl>(b"RCPT TO:<postmaster@[192.0.2.1]>\r\n").unwrap();
+    assert!(matches!(path, ForwardPath::Path(_)));
 }
 
 #[test]
@@ -100,3 +110,1651 @@ fn normal_quoted_lp() {
     lp.smtp_try_unquote();
     assert_eq!(lp, LocalPart::Quoted(QuotedString("a b".into())));
 }
+
+#[test]
+fn ehlo_domain() {
+    let (rem, dp) = ehlo_command::<Intl>(b"EHLO mail.example.org\r\n").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(dp, DomainPart::from_smtp(b"mail.example.org").unwrap());
+}
+
+#[test]
+fn ehlo_literal() {
+    let (_, dp) = ehlo_command::<Intl>(b"ehlo [192.0.2.1]\r\n").unwrap();
+    assert_eq!(dp, DomainPart::Address(AddressLiteral::IP("192.0.2.1".parse().unwrap())));
+
+    let (_, dp) = ehlo_command::<Intl>(b"EhLo [IPv6:2001:db8::1]\r\n").unwrap();
+    assert_eq!(dp, DomainPart::Address(AddressLiteral::IP("2001:db8::1".parse().unwrap())));
+}
+
+#[test]
+fn helo_domain() {
+    let (rem, d) = helo_command::<Intl>(b"helo mail.example.org\r\n").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(d, Domain::from_smtp(b"mail.example.org").unwrap());
+}
+
+#[test]
+fn helo_literal() {
+    assert!(helo_command::<Intl>(b"HELO [192.0.2.1]\r\n").is_err());
+}
+
+#[test]
+fn ehlo_junk() {
+    assert!(ehlo_command::<Intl>(b"EHLO example.org junk\r\n").is_err());
+    assert!(ehlo_command::<Intl>(b"EHLO\r\n").is_err());
+    assert!(helo_command::<Intl>(b"HELO example.org junk\r\n").is_err());
+}
+
+#[test]
+fn size() {
+    let (_, (_, params)) = mail_command::<Intl>(b"MAIL FROM:<bob@example.org> SIZE=12345\r\n").unwrap();
+    assert_eq!(size_param(&params), Ok(Some(12345)));
+    assert_eq!(size_param(&[]), Ok(None));
+    assert_eq!(size_param(&[Param::new("SIZE", Some("18446744073709551615")).unwrap()]), Ok(Some(u64::MAX)));
+}
+
+#[test]
+fn size_invalid() {
+    assert_eq!(size_param(&[Param::new("SIZE", None).unwrap()]), Err(SizeError::MissingValue));
+    assert_eq!(size_param(&[Param::new("SIZE", Some("12a")).unwrap()]), Err(SizeError::Invalid));
+    assert_eq!(size_param(&[Param::new("SIZE", Some("+12")).unwrap()]), Err(SizeError::Invalid));
+    assert_eq!(size_param(&[Param::new("SIZE", Some("18446744073709551616")).unwrap()]), Err(SizeError::Overflow));
+    assert_eq!(size_param(&[Param::new("SIZE", Some("1")).unwrap(),
+                            Param::new("size", Some("1")).unwrap()]), Err(SizeError::Duplicate));
+    assert_eq!(SizeError::Duplicate.to_string(), "Duplicate SIZE");
+    assert!(mail_command::<Intl>(b"MAIL FROM:<bob@example.org> SIZE=\r\n").is_err());
+}
+
+#[test]
+fn body() {
+    let (_, (_, params)) = mail_command::<Intl>(b"MAIL FROM:<bob@example.org> body=BinaryMIME\r\n").unwrap();
+    assert_eq!(body_param(&params), Ok(Some(BodyType::BinaryMime)));
+    assert_eq!(body_param(&[Param::new("BODY", Some("7BIT")).unwrap()]), Ok(Some(BodyType::SevenBit)));
+    assert_eq!(body_param(&[Param::new("SIZE", Some("10")).unwrap()]), Ok(None));
+}
+
+#[test]
+fn body_invalid() {
+    assert_eq!(body_param(&[Param::new("BODY", None).unwrap()]), Err(BodyError::MissingValue));
+    assert_eq!(body_param(&[Param::new("BODY", Some("8BIT")).unwrap()]), Err(BodyError::Invalid));
+    assert_eq!(body_param(&[Param::new("BODY", Some("7BIT")).unwrap(),
+                            Param::new("BODY", Some("8BITMIME")).unwrap()]), Err(BodyError::Duplicate));
+    assert_eq!(BodyError::Invalid.to_string(), "Invalid BODY");
+}
+
+#[test]
+fn bdat() {
+    assert_eq!(bdat_command(b"BDAT 86\r\n").unwrap(), (b"".as_ref(), (86, false)));
+    assert_eq!(bdat_command(b"bdat 86 last\r\n").unwrap(), (b"".as_ref(), (86, true)));
+    assert_eq!(bdat_command(b"BDAT 18446744073709551615 LAST\r\n").unwrap().1, (u64::MAX, true));
+}
+
+#[test]
+fn bdat_invalid() {
+    assert!(bdat_command(b"BDAT\r\n").is_err());
+    assert!(bdat_command(b"BDAT \r\n").is_err());
+    assert!(bdat_command(b"BDAT -1\r\n").is_err());
+    assert!(bdat_command(b"BDAT 1a\r\n").is_err());
+    assert!(bdat_command(b"BDAT 18446744073709551616\r\n").is_err());
+    assert!(bdat_command(b"BDAT 10 LASTX\r\n").is_err());
+    assert!(bdat_command(b"BDAT 10 FIRST\r\n").is_err());
+}
+
+#[test]
+fn verb_only() {
+    assert_eq!(data_command(b"DATA\r\n").unwrap(), (b"".as_ref(), ()));
+    assert_eq!(rset_command(b"rset\r\n").unwrap(), (b"".as_ref(), ()));
+    assert_eq!(quit_command(b"Quit\r\n").unwrap(), (b"".as_ref(), ()));
+
+    assert!(data_command(b"DATA now\r\n").is_err());
+    assert!(rset_command(b"RSET \r\n").is_err());
+    assert!(quit_command(b"QUIT bye\r\n").is_err());
+}
+
+#[test]
+fn noop() {
+    assert_eq!(noop_command::<Intl>(b"NOOP\r\n").unwrap().1, None);
+
+    let (_, s) = noop_command::<Intl>(b"noop hello\r\n").unwrap();
+    assert_eq!(s.as_deref(), Some("hello"));
+
+    let (_, s) = noop_command::<Intl>(b"NOOP \"hello world\"\r\n").unwrap();
+    assert_eq!(s.as_deref(), Some("hello world"));
+
+    assert!(noop_command::<Intl>(b"NOOP hello world\r\n").is_err());
+}
+
+#[test]
+fn dispatch() {
+    let (_, cmd) = command::<Intl>(b"EHLO example.org\r\n").unwrap();
+    assert!(matches!(cmd, Command::EHLO(_)));
+    let (_, cmd) = command::<Intl>(b"MAIL FROM:<> BODY=8BITMIME\r\n").unwrap();
+    assert!(matches!(cmd, Command::MAIL(ReversePath::Null, _)));
+    let (_, cmd) = command::<Intl>(b"RCPT TO:<postmaster>\r\n").unwrap();
+    assert!(matches!(cmd, Command::RCPT(ForwardPath::PostMaster(None), _)));
+    let (_, cmd) = command::<Intl>(b"STARTTLS\r\n").unwrap();
+    assert!(matches!(cmd, Command::STARTTLS));
+    let (_, cmd) = command::<Intl>(b"BDAT 12\r\n").unwrap();
+    assert!(matches!(cmd, Command::BDAT(12, false)));
+    let (_, cmd) = command::<Intl>(b"AUTH LOGIN\r\n").unwrap();
+    assert!(matches!(cmd, Command::AUTH(_)));
+    let (_, cmd) = command::<Intl>(b"quit\r\n").unwrap();
+    assert!(matches!(cmd, Command::QUIT));
+}
+
+#[test]
+fn dispatch_unknown() {
+    assert!(command::<Intl>(b"FOO bar\r\n").is_err());
+    assert!(command::<Intl>(b"\r\n").is_err());
+}
+
+#[test]
+fn address_literal_errors() {
+    use AddressLiteralErrorKind::*;
+
+    fn err(input: &str) -> (AddressLiteralErrorKind, usize) {
+        let e = input.parse::<AddressLiteral>().unwrap_err();
+        (e.kind, e.offset)
+    }
+
+    assert_eq!(err("192.0.2.1"), (MissingBracket, 0));
+    assert_eq!(err("[192.0.2.1"), (MissingBracket, 10));
+    assert_eq!(err("[999.1.1.1]"), (BadIpv4Octet, 1));
+    assert_eq!(err("[1.2.3]"), (BadIpv4Octet, 6));
+    assert_eq!(err("[1.2.3.4.5]"), (TrailingData, 8));
+    assert_eq!(err("[IPv6:zzz]"), (BadIpv6, 6));
+    assert_eq!(err("[:value]"), (EmptyTag, 1));
+    assert_eq!(err("[foo]"), (BadTag, 4));
+    assert_eq!(err("[foo-:bar]"), (BadTag, 4));
+    assert_eq!(err("[foo:]"), (BadValue, 5));
+    assert_eq!(err("[foo--:bar]"), (BadTag, 4));
+    assert_eq!(err("[ipv6:bar]"), (BadIpv6, 6));
+}
+
+#[test]
+fn address_literal_tags() {
+    assert_eq!("[foo-bar:baz]".parse(), Ok(AddressLiteral::Tagged("foo-bar".into(), "baz".into())));
+    assert_eq!("[1x:baz]".parse(), Ok(AddressLiteral::Tagged("1x".into(), "baz".into())));
+    // Ldh-str only requires the last character to be a letter or digit.
+    assert_eq!("[-x:baz]".parse(), Ok(AddressLiteral::Tagged("-x".into(), "baz".into())));
+    assert!("[IPV6:baz]".parse::<AddressLiteral>().is_err());
+
+    assert!(AddressLiteral::Tagged("ipv6".into(), "::1".into()).is_registered());
+    assert!(!AddressLiteral::Tagged("x400".into(), "cn=bob".into()).is_registered());
+    assert!(!AddressLiteral::FreeForm("192.0.2.1".into()).is_registered());
+    assert!(AddressLiteral::IP("192.0.2.1".parse().unwrap()).is_registered());
+}
+
+#[test]
+fn address_literal_fromstr() {
+    assert_eq!("[IPv6:2001:db8::1]".parse(), Ok(AddressLiteral::IP("2001:db8::1".parse().unwrap())));
+    assert_eq!("[x400:cn=bob]".parse(), Ok(AddressLiteral::Tagged("x400".into(), "cn=bob".into())));
+    assert_eq!(AddressLiteral::IP("192.0.2.1".parse().unwrap()).upgrade().unwrap_err().kind,
+               AddressLiteralErrorKind::NotFreeForm);
+}
+
+#[test]
+fn detailed_errors() {
+    fn offset(input: &[u8]) -> (usize, &'static str) {
+        let err = mail_command_detailed::<Intl>(input).unwrap_err();
+        (err.offset, err.reason)
+    }
+
+    assert_eq!(offset(b"MAIL TO:<bob@example.org>\r\n"), (4, "expected FROM:"));
+    assert_eq!(offset(b"XYZZY FROM:<bob@example.org>\r\n"), (0, "unknown command"));
+    assert_eq!(offset(b"RCPT TO:<bob@example.org>\r\n"), (0, "unknown command"));
+    assert_eq!(offset(b"MAIL FROM:<bad\r\n"), (10, "invalid reverse path"));
+    assert_eq!(offset(b"MAIL FROM:<bob@example.org>X\r\n"), (27, "expected space or CRLF"));
+    assert_eq!(offset(b"MAIL FROM:<bob@example.org>  SIZE=1\r\n"), (28, "invalid ESMTP parameter"));
+    assert_eq!(offset(b"MAIL FROM:<bob@example.org> SIZE=1 \r\n"), (35, "invalid ESMTP parameter"));
+    assert_eq!(offset(b"MAIL FROM:<bob@example.org>"), (27, "expected space or CRLF"));
+    assert_eq!(offset(b"MAIL FROM:<>\r\nQUIT\r\n"), (14, "trailing data after CRLF"));
+
+    let err = rcpt_command_detailed::<Intl>(b"RCPT TO:<>\r\n").unwrap_err();
+    assert_eq!(err, SMTPParseError{kind: SMTPParseErrorKind::NullForwardPath, offset: 8, reason: "null forward path"});
+    let err = rcpt_command_detailed::<Intl>(b"RCPT TO:<bob\r\n").unwrap_err();
+    assert_eq!(err, SMTPParseError{kind: SMTPParseErrorKind::InvalidPath, offset: 8, reason: "invalid forward path"});
+}
+
+#[test]
+fn null_sender() {
+    assert!(ReversePath::Null.is_null_sender());
+    assert!(ReversePath::from_str("<>").unwrap().is_null_sender());
+    assert!(!ReversePath::from_str("<bob@example.org>").unwrap().is_null_sender());
+    assert!(rcpt_command::<Intl>(b"RCPT TO:<>\r\n").is_err());
+}
+
+#[test]
+fn detailed_same_syntax() {
+    for input in [b"MAIL FROM:<>\r\n".as_ref(),
+                  b"MAIL FROM:<bob@example.org> SIZE=1\t\tBODY=8BITMIME\r\n",
+                  b"MAIL FROM:<@a,@b:bob@example.org> SMTPUTF8\r\n",
+                  b"mail from:<\"bob smith\"@[192.0.2.1]>\r\n"].iter() {
+        assert_eq!(mail_command_detailed::<Intl>(input).unwrap(), mail_command::<Intl>(input).unwrap().1);
+    }
+
+    let input = b"RCPT TO:<Postmaster> NOTIFY=NEVER\r\n";
+    assert_eq!(rcpt_command_detailed::<Intl>(input).unwrap(), rcpt_command::<Intl>(input).unwrap().1);
+}
+
+#[test]
+fn utf8_local_part() {
+    let (_, (path, _)) = mail_command::<Intl>("MAIL FROM:<müller@example.org>\r\n".as_bytes()).unwrap();
+    assert_eq!(path, ReversePath::Path(Path(Mailbox(DotAtom("müller".into()).into(), dp("example.org")), vec![])));
+    assert!(validate_address::<Intl>("\"jörg müller\"@example.org".as_bytes()));
+
+    assert!(mail_command::<Legacy>("MAIL FROM:<müller@example.org>\r\n".as_bytes()).is_err());
+    assert!(!validate_address::<Legacy>("müller@example.org".as_bytes()));
+    assert!(!validate_address::<Legacy>("\"jörg müller\"@example.org".as_bytes()));
+}
+
+#[test]
+fn invalid_utf8_local_part() {
+    assert!(!validate_address::<Intl>(b"m\xfcller@example.org"));
+    assert!(!validate_address::<Intl>(b"\xc3@example.org"));
+}
+
+#[test]
+fn utf8_domain() {
+    let (_, (path, _)) = mail_command::<Intl>("MAIL FROM:<bob@例え.jp>\r\n".as_bytes()).unwrap();
+    assert_eq!(path, ReversePath::Path(Path(Mailbox(DotAtom("bob".into()).into(), dp("例え.jp")), vec![])));
+    assert!(validate_address::<Intl>("bob@münchen-ost.example".as_bytes()));
+    assert!(!validate_address::<Legacy>("bob@例え.jp".as_bytes()));
+}
+
+#[test]
+fn utf8_domain_hyphens() {
+    for domain in &["-例え.jp", "例え-.jp", "ü.-a.example", "ü.a-.example", "example.ü-"] {
+        assert!(!validate_address::<Intl>(format!("bob@{}", domain).as_bytes()), "{}", domain);
+    }
+}
+
+#[test]
+#[cfg(feature = "idna")]
+fn punycode() {
+    let unicode = DomainPart::from_smtp("bücher.例え.jp".as_bytes()).unwrap();
+    let ascii = DomainPart::from_smtp(b"xn--bcher-kva.xn--r8jz45g.jp").unwrap();
+
+    assert_eq!(unicode.to_ascii().unwrap(), ascii);
+    assert_eq!(ascii.to_unicode(), unicode);
+    assert_eq!(ascii.to_ascii().unwrap(), ascii);
+    assert_eq!(dp("Example.ORG").to_ascii().unwrap(), dp("example.org"));
+
+    let literal = DomainPart::from_smtp(b"[192.0.2.1]").unwrap();
+    assert_eq!(literal.to_ascii().unwrap(), literal);
+    assert_eq!(literal.to_unicode(), literal);
+}
+
+#[test]
+#[cfg(feature = "idna")]
+fn punycode_invalid() {
+    let bad = dp("xn--a.example");
+    assert_eq!(bad.to_unicode(), bad);
+    assert_eq!(bad.to_ascii(), Err(IdnaError));
+    assert_eq!(IdnaError.to_string(), "invalid internationalized domain");
+}
+
+#[test]
+fn label_length() {
+    for validate in &[validate_address::<Intl>, validate_address::<Legacy>] {
+        let label = "a".repeat(63);
+        assert!(validate(format!("bob@{}.example", label).as_bytes()));
+        assert!(!validate(format!("bob@{}a.example", label).as_bytes()));
+        assert!(!validate(format!("bob@example.{}a", label).as_bytes()));
+    }
+}
+
+#[test]
+fn domain_length() {
+    // 4 * 63 + 3 dots = 255 octets
+    let label = "a".repeat(63);
+    let max = [label.as_str(); 4].join(".");
+    assert_eq!(max.len(), 255);
+
+    assert!(Domain::from_smtp(max.as_bytes()).is_ok());
+    assert!(Domain::from_smtp(format!("a{}", max).as_bytes()).is_err());
+    assert!(validate_address::<Legacy>(format!("bob@{}", max).as_bytes()));
+    assert!(!validate_address::<Legacy>(format!("bob@a{}", max).as_bytes()));
+}
+
+#[test]
+fn ipv4_leading_zero() {
+    assert_eq!(AddressLiteral::from_smtp(b"[0.0.0.0]").unwrap(), AddressLiteral::IP("0.0.0.0".parse().unwrap()));
+    assert_eq!(AddressLiteral::from_smtp(b"[10.100.0.1]").unwrap(), AddressLiteral::IP("10.100.0.1".parse().unwrap()));
+
+    for literal in &["[192.000.2.1]", "[010.0.0.1]", "[192.0.2.01]", "[00.1.2.3]"] {
+        assert!(AddressLiteral::from_smtp(literal.as_bytes()).is_err(), "{}", literal);
+    }
+    assert_eq!("[192.0.02.1]".parse::<AddressLiteral>().unwrap_err(),
+               AddressLiteralError{kind: AddressLiteralErrorKind::BadIpv4Octet, offset: 7});
+    assert!(!validate_address::<Intl>(b"bob@[127.0.0.01]"));
+}
+
+#[test]
+fn ipv4_out_of_range() {
+    assert!(AddressLiteral::from_smtp(b"[256.1.1.1]").is_err());
+    assert!(AddressLiteral::from_smtp(b"[1.1.1.1000]").is_err());
+}
+
+#[test]
+fn ipv6_zone() {
+    let (_, (path, _)) = rcpt_command::<Intl>(b"RCPT TO:<bob@[IPv6:fe80::1%en0.1_a-b~]>\r\n").unwrap();
+    let expected = AddressLiteral::ZonedIPv6("fe80::1".parse().unwrap(), "en0.1_a-b~".into());
+    assert_eq!(path, ForwardPath::Path(Path(Mailbox(DotAtom("bob".into()).into(), expected.clone().into()), vec![])));
+    assert_eq!(expected.to_string().parse(), Ok(expected));
+}
+
+#[test]
+fn ipv6_zone_invalid() {
+    assert!(AddressLiteral::from_smtp(b"[IPv6:fe80::1%]").is_err());
+    assert!(AddressLiteral::from_smtp(b"[IPv6:fe80::1%eth0%1]").is_err());
+    assert!(AddressLiteral::from_smtp(b"[IPv6:fe80::1%eth/0]").is_err());
+    assert!(AddressLiteral::from_smtp(b"[192.0.2.1%eth0]").is_err());
+}
+
+#[test]
+fn ipv6_zone_imf() {
+    let lit = AddressLiteral::from_imf(b"[IPv6:fe80::1%eth0]").unwrap();
+    assert_eq!(lit, AddressLiteral::ZonedIPv6("fe80::1".parse().unwrap(), "eth0".into()));
+}
+
+#[test]
+fn fromstr_roundtrip() {
+    for input in &["bob@example.org", "\"bob \\\\ \\\"smith\\\"\"@[192.0.2.1]", "bob@[IPv6:2001:db8::1]"] {
+        let mailbox: Mailbox = input.parse().unwrap();
+        assert_eq!(mailbox.to_string().parse(), Ok(mailbox));
+    }
+    for input in &["<bob@example.org>", "<@a.example,@b.example:bob@example.org>"] {
+        let path: Path = input.parse().unwrap();
+        assert_eq!(path.to_string(), *input);
+        let rp: ReversePath = input.parse().unwrap();
+        assert_eq!(rp.to_string(), *input);
+    }
+    assert_eq!("<>".parse(), Ok(ReversePath::Null));
+}
+
+#[test]
+fn fromstr_error() {
+    assert_eq!("bob@example.org>".parse::<Mailbox>(),
+               Err(SMTPParseError{kind: SMTPParseErrorKind::TrailingData, offset: 15, reason: "trailing data"}));
+    assert_eq!("bob".parse::<Mailbox>(),
+               Err(SMTPParseError{kind: SMTPParseErrorKind::Syntax, offset: 3, reason: "expected '@'"}));
+    assert_eq!("bob@exa..org".parse::<Mailbox>().unwrap_err().offset, 7);
+    assert!("<>".parse::<Path>().is_err());
+    assert!("bob@example.org".parse::<ReversePath>().is_err());
+}
+
+#[test]
+fn param_lookup() {
+    let (_, (_, params)) = mail_command::<Intl>(b"MAIL FROM:<> Size=10 smtputf8 X-Foo=a X-FOO=b\r\n").unwrap();
+
+    assert_eq!(find_param(&params, "SIZE"), Some(&params[0]));
+    assert_eq!(get_value(&params, "size"), Some("10"));
+    assert_eq!(get_value(&params, "x-foo"), Some("a"));
+    assert_eq!(get_value(&params, "SMTPUTF8"), None);
+    assert!(has_param(&params, "SMTPUTF8"));
+    assert!(!has_param(&params, "BODY"));
+    assert_eq!(find_param(&[], "SIZE"), None);
+}
+
+#[test]
+fn duplicate_params() {
+    let (_, (_, params)) = rcpt_command::<Intl>(b"RCPT TO:<bob@example.org> NOTIFY=NEVER ORCPT=rfc822;bob@example.org\r\n").unwrap();
+    assert_eq!(validate_params(&params), Ok(()));
+    assert_eq!(validate_params(&[]), Ok(()));
+
+    let (_, (_, params)) = rcpt_command::<Intl>(b"RCPT TO:<bob@example.org> NOTIFY=NEVER X=1 notify=SUCCESS\r\n").unwrap();
+    assert_eq!(validate_params(&params), Err(DuplicateParam(Keyword("notify".into()))));
+}
+
+#[test]
+fn auth_param_values() {
+    let (_, (_, params)) = exact!(&b"MAIL FROM:<a@b> AUTH=user+2B1@example.org\r\n"[..], mail_command::<Legacy>).unwrap();
+    assert_eq!(auth_param(&params), Ok(Some(Some(Mailbox::from_str("user+1@example.org").unwrap()))));
+
+    let (_, (_, params)) = exact!(&b"MAIL FROM:<a@b> AUTH=<>\r\n"[..], mail_command::<Legacy>).unwrap();
+    assert_eq!(auth_param(&params), Ok(Some(None)));
+    assert_eq!(auth_param(&[]), Ok(None));
+}
+
+#[test]
+fn auth_param_invalid() {
+    let p = |v| vec![Param::new("AUTH", v).unwrap()];
+    assert_eq!(auth_param(&p(None)), Err(AuthParamError::MissingValue));
+    assert_eq!(auth_param(&p(Some("user+2b@example.org"))), Err(AuthParamError::BadXtext));
+    assert_eq!(auth_param(&p(Some("user"))), Err(AuthParamError::BadMailbox));
+    assert_eq!(auth_param(&p(Some("<user@example.org>"))), Err(AuthParamError::BadMailbox));
+    let mut dup = p(Some("<>"));
+    dup.extend(p(Some("<>")));
+    assert_eq!(auth_param(&dup), Err(AuthParamError::Duplicate));
+}
+
+#[test]
+fn auth() {
+    let (_, auth) = exact!(&b"auth CRAM-MD5\r\n"[..], auth_command).unwrap();
+    assert_eq!(auth, AuthCommand{ mechanism: "CRAM-MD5".into(), initial_response: None });
+
+    let (_, auth) = exact!(&b"AUTH PLAIN =\r\n"[..], auth_command).unwrap();
+    assert_eq!(auth.initial_response, Some(vec![]));
+
+    let (_, auth) = exact!(&b"AUTH XOAUTH2 dXNlcg==\r\n"[..], auth_command).unwrap();
+    assert_eq!(auth.initial_response, Some(b"user".to_vec()));
+}
+
+#[test]
+fn auth_invalid() {
+    assert!(auth_command(b"AUTH\r\n").is_err());
+    assert!(auth_command(b"AUTH PLAIN abc\r\n").is_err());
+    assert!(auth_command(b"AUTH PLAIN a*bc\r\n").is_err());
+    assert!(auth_command(b"AUTH PLAIN \r\n").is_err());
+    assert!(auth_command(b"AUTH PLAIN ==\r\n").is_err());
+    assert!(auth_command(b"AUTH TOOLONGMECHANISMNAME123\r\n").is_err());
+}
+
+#[test]
+fn vrfy_expn() {
+    let (_, s) = exact!(&b"VRFY bob\r\n"[..], vrfy_command::<Legacy>).unwrap();
+    assert_eq!(&*s, "bob");
+    let (_, s) = exact!(&b"Vrfy \"bob \\\"b\\\" smith\"\r\n"[..], vrfy_command::<Legacy>).unwrap();
+    assert_eq!(&*s, "bob \"b\" smith");
+    let (_, s) = exact!("EXPN liste-été\r\n".as_bytes(), expn_command::<Intl>).unwrap();
+    assert_eq!(&*s, "liste-été");
+    let (_, s) = exact!(&b"expn staff\r\n"[..], expn_command::<Legacy>).unwrap();
+    assert_eq!(&*s, "staff");
+}
+
+#[test]
+fn vrfy_expn_invalid() {
+    assert!(vrfy_command::<Legacy>(b"VRFY\r\n").is_err());
+    assert!(vrfy_command::<Legacy>(b"VRFY \r\n").is_err());
+    assert!(vrfy_command::<Legacy>("VRFY été\r\n".as_bytes()).is_err());
+    assert!(expn_command::<Legacy>(b"EXPNstaff\r\n").is_err());
+}
+
+#[test]
+fn starttls() {
+    assert_eq!(exact!(&b"starttls\r\n"[..], starttls_command), Ok((&b""[..], ())));
+    assert!(starttls_command(b"STARTTLS \r\n").is_err());
+    assert!(starttls_command(b"STARTTLS x\r\n").is_err());
+    assert!(starttls_command(b"STARTTLS").is_err());
+}
+
+#[test]
+fn format_roundtrip() {
+    for input in &["MAIL FROM:<bob@example.org>\r\n",
+                   "MAIL FROM:<> BODY=8BITMIME SIZE=1234\r\n",
+                   "MAIL FROM:<@a.example,@b.example:bob@example.org> SMTPUTF8\r\n",
+                   "MAIL FROM:<\"bob smith\"@[192.0.2.1]> ENVID=a+2Bb\r\n"] {
+        let (_, (path, params)) = exact!(input.as_bytes(), mail_command::<Intl>).unwrap();
+        let formatted = format_mail_command(&path, &params);
+        assert_eq!(&formatted, input);
+        assert_eq!(exact!(formatted.as_bytes(), mail_command::<Intl>).unwrap().1, (path, params));
+    }
+
+    for input in &["RCPT TO:<postmaster>\r\n",
+                   "RCPT TO:<postmaster@example.org> NOTIFY=NEVER\r\n",
+                   "RCPT TO:<bob@example.org> NOTIFY=SUCCESS,FAILURE ORCPT=rfc822;bob@example.org\r\n"] {
+        let (_, (path, params)) = exact!(input.as_bytes(), rcpt_command::<Intl>).unwrap();
+        let formatted = format_rcpt_command(&path, &params);
+        assert_eq!(&formatted, input);
+        assert_eq!(exact!(formatted.as_bytes(), rcpt_command::<Intl>).unwrap().1, (path, params));
+    }
+}
+
+#[test]
+fn mailbox_new() {
+    let m = Mailbox::new("bob.smith", "example.org").unwrap();
+    assert!(matches!(m.local_part(), LocalPart::DotAtom(_)));
+    assert_eq!(m, Mailbox::from_str("bob.smith@example.org").unwrap());
+
+    let m = Mailbox::new("bob..smith", "example.org").unwrap();
+    assert!(matches!(m.local_part(), LocalPart::Quoted(_)));
+    assert_eq!(m.to_string(), "\"bob..smith\"@example.org");
+
+    let m = Mailbox::new("a\"b\\c", "[IPv6:::1]").unwrap();
+    assert_eq!(m.to_string(), "\"a\\\"b\\\\c\"@[IPv6:::1]");
+    assert_eq!(Mailbox::from_str(&m.to_string()).unwrap(), m);
+
+    assert_eq!(Mailbox::new("josé", "exemple.fr").unwrap().to_string(), "josé@exemple.fr");
+}
+
+#[test]
+fn mailbox_new_invalid() {
+    assert_eq!(Mailbox::new("bob\r\n", "example.org"), Err(MailboxError::InvalidLocalPart));
+    assert_eq!(Mailbox::new("bob", ""), Err(MailboxError::InvalidDomain));
+    assert_eq!(Mailbox::new("bob", "-example.org"), Err(MailboxError::InvalidDomain));
+    assert_eq!(Mailbox::new("bob", "[300.0.0.1]"), Err(MailboxError::InvalidDomain));
+    assert_eq!(Mailbox::new("bob", "example.org "), Err(MailboxError::InvalidDomain));
+}
+
+#[test]
+fn mailbox_accessors() {
+    let m = Mailbox::from_str("\"bob smith\"@Example.org").unwrap();
+    assert_eq!(m.local_part().to_string(), "\"bob smith\"");
+    assert_eq!(m.domain_part(), &dp("Example.org"));
+    assert_eq!(m.domain_str(), "Example.org");
+}
+
+#[test]
+fn domain_normalized() {
+    use std::collections::HashSet;
+
+    let domains: HashSet<_> = ["Example.ORG", "example.org", "EXAMPLE.org", "ÉTÉ.example"].iter()
+        .map(|d| dp(*d).normalized()).collect();
+    assert_eq!(domains.len(), 2);
+    assert!(domains.contains(&dp("été.example")));
+
+    assert!(dp("Example.ORG").eq_ignore_case(&dp("example.org")));
+    assert!(!dp("example.org").eq_ignore_case(&dp("example.com")));
+
+    let literal = DomainPart::from_str("[IPv6:::1]").unwrap();
+    assert_eq!(literal.normalized(), literal);
+    assert!(literal.eq_ignore_case(&literal));
+    assert!(!literal.eq_ignore_case(&dp("example.org")));
+}
+
+#[test]
+fn local_part_needs_quoting() {
+    for s in &["a", "a.b.c", "!#$%&'*+-/=?^_`{|}~", "1234"] {
+        assert!(!needs_quoting::<Legacy>(s), "{}", s);
+    }
+    for s in &["", ".a", "a.", "a..b", "a b", "a@b", "a\"b", "a\\b", "(a)", "a,b", "a:b", "[a]", "<a>"] {
+        assert!(needs_quoting::<Legacy>(s), "{}", s);
+    }
+}
+
+#[test]
+fn local_part_simplify() {
+    let lp = |s: &str| LocalPart::from_str(s).unwrap();
+
+    assert!(matches!(lp("\"bob\"").simplify(), LocalPart::DotAtom(_)));
+    assert_eq!(lp("\"bob\"").simplify(), lp("bob"));
+    assert_eq!(lp("bob").simplify(), lp("bob"));
+    assert_eq!(lp("\"a b\"").simplify(), lp("\"a b\""));
+    assert_eq!(lp("\".bob\"").simplify(), lp("\".bob\""));
+
+    let mut m = Mailbox::from_str("\"bob\"@example.org").unwrap();
+    m.smtp_try_unquote();
+    assert_eq!(m, Mailbox::from_str("bob@example.org").unwrap());
+}
+
+#[test]
+fn mailbox_ref_same_syntax() {
+    for input in &["bob@example.org", "a.b.c@x", "\"a b\"@example.org", "\"a\\\"b\"@[192.0.2.1]",
+                   "bob@[IPv6:::1]", "bob@[IPv6:fe80::1%eth0]", "bob@[tag:value]", "josé@été.example",
+                   "bob", "bob@", "@example.org", "a..b@x", "bob@-x", "bob@[1.2.3]", "\"a@x", "a@b@c"] {
+        let owned = exact!(input.as_bytes(), mailbox::<Intl>).map(|(_, m)| m);
+        let borrowed = exact!(input.as_bytes(), mailbox_ref::<Intl>).map(|(_, m)| m);
+
+        assert_eq!(owned.is_ok(), borrowed.is_ok(), "{}", input);
+        if let (Ok(owned), Ok(borrowed)) = (owned, borrowed) {
+            assert_eq!(borrowed.to_string(), *input);
+            assert_eq!(format!("{}@{}", borrowed.local_part(), borrowed.domain_part()), *input);
+            assert_eq!(Mailbox::from(borrowed), owned);
+        }
+    }
+    assert!(mailbox_ref::<Legacy>("josé@example.org".as_bytes()).is_err());
+}
+
+#[test]
+fn source_route_preserved() {
+    let (_, (path, _)) = exact!(&b"MAIL FROM:<@relay.example:bob@example.org>\r\n"[..], mail_command::<Intl>).unwrap();
+    match path {
+        ReversePath::Path(Path(mailbox, route)) => {
+            assert_eq!(mailbox.to_string(), "bob@example.org");
+            assert_eq!(route, [Domain::from_smtp(b"relay.example").unwrap()]);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let (_, (path, _)) = exact!(&b"RCPT TO:<@a.example,@b.example:bob@example.org>\r\n"[..], rcpt_command::<Intl>).unwrap();
+    match path {
+        ForwardPath::Path(Path(_, route)) => assert_eq!(route.len(), 2),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    assert!(rcpt_command::<Intl>(b"RCPT TO:<@a.example,b.example:bob@example.org>\r\n").is_err());
+    assert!(rcpt_command::<Intl>(b"RCPT TO:<@a.example bob@example.org>\r\n").is_err());
+}
+
+#[test]
+fn parse_options_default_is_strict() {
+    let options = ParseOptions::default();
+    let long_label = format!("MAIL FROM:<bob@{}.example>\r\n", "a".repeat(MAX_LABEL_LENGTH + 1));
+
+    for input in &["MAIL FROM:<>\r\n", "MAIL FROM:<bob@example.org> BODY=8BITMIME\r\n",
+                   "MAIL FROM:<@relay.example:bob@[192.0.2.1]>\r\n", "MAIL FROM:<bob@[010.0.0.1]>\r\n",
+                   "MAIL FROM:<josé@example.org>\r\n", "MAIL FROM:<bob@[IPv6:::1]>\r\n", &long_label] {
+        assert_eq!(mail_command_with(input.as_bytes(), &options), mail_command_with_warnings::<Legacy>(input.as_bytes()), "{}", input);
+    }
+    for input in &["RCPT TO:<postmaster>\r\n", "RCPT TO:<Postmaster@example.org>\r\n",
+                   "RCPT TO:<bob@example.org> NOTIFY=NEVER\r\n", "RCPT TO:<>\r\n"] {
+        assert_eq!(rcpt_command_with(input.as_bytes(), &options), rcpt_command_with_warnings::<Legacy>(input.as_bytes()), "{}", input);
+    }
+}
+
+#[test]
+fn parse_options_lengths() {
+    let label = "a".repeat(MAX_LABEL_LENGTH + 1);
+    let input = format!("MAIL FROM:<bob@{}.example>\r\n", label);
+    let relaxed = ParseOptions{max_label_length: 255, ..Default::default()};
+    let (_, (path, _, _)) = mail_command_with(input.as_bytes(), &relaxed).unwrap();
+    assert_eq!(path.to_string(), format!("<bob@{}.example>", label));
+
+    let tight = ParseOptions{max_domain_length: 11, ..Default::default()};
+    assert!(mail_command_with(b"MAIL FROM:<bob@example.org>\r\n", &tight).is_ok());
+    assert!(mail_command_with(b"MAIL FROM:<bob@mail.example.org>\r\n", &tight).is_err());
+    assert!(rcpt_command_with(b"RCPT TO:<postmaster@mail.example.org>\r\n", &tight).is_err());
+
+}
+
+#[test]
+#[cfg(feature = "idna")]
+fn parse_options_lengths_idna() {
+    // Internationalized labels are measured in their ASCII form.
+    let utf8 = ParseOptions{utf8: true, max_label_length: 10, ..Default::default()};
+    assert!(mail_command_with("MAIL FROM:<bob@été.example>\r\n".as_bytes(), &utf8).is_ok());
+    assert!(mail_command_with("MAIL FROM:<bob@ééééé.example>\r\n".as_bytes(), &utf8).is_err());
+}
+
+#[test]
+fn parse_options_leading_zeros() {
+    let options = ParseOptions{allow_leading_zeros: true, ..Default::default()};
+    let (_, (path, _, _)) = rcpt_command_with(b"RCPT TO:<bob@[010.001.0.255]>\r\n", &options).unwrap();
+    assert_eq!(path.to_string(), "<bob@[10.1.0.255]>");
+
+    assert!(rcpt_command_with(b"RCPT TO:<bob@[0256.0.0.1]>\r\n", &options).is_err());
+    assert!(rcpt_command_with(b"RCPT TO:<bob@[IPv6:::1]>\r\n", &options).is_ok());
+    assert!(rcpt_command_with(b"RCPT TO:<bob@[tag:value]>\r\n", &options).is_ok());
+}
+
+#[test]
+fn parse_options_utf8() {
+    let input = "RCPT TO:<josé@été.example> ORCPT=utf-8;josé@été.example\r\n".as_bytes();
+    assert!(rcpt_command_with(input, &ParseOptions::default()).is_err());
+
+    let options = ParseOptions{utf8: true, ..Default::default()};
+    assert_eq!(rcpt_command_with(input, &options), rcpt_command_with_warnings::<Intl>(input));
+}
+
+#[test]
+fn parse_options_source_route() {
+    let options = ParseOptions{allow_source_route: false, ..Default::default()};
+    assert!(mail_command_with(b"MAIL FROM:<@relay.example:bob@example.org>\r\n", &options).is_err());
+    assert!(rcpt_command_with(b"RCPT TO:<@a.example,@b.example:bob@example.org>\r\n", &options).is_err());
+    assert!(mail_command_with(b"MAIL FROM:<bob@example.org>\r\n", &options).is_ok());
+}
+
+#[test]
+fn ipv4_embedded_ipv6_literal() {
+    let mapped: Ipv6Addr = "::ffff:c000:201".parse().unwrap();
+    let compatible: Ipv6Addr = "::c000:201".parse().unwrap();
+
+    for input in &["[IPv6:::ffff:192.0.2.1]", "[IPv6:::FFFF:192.0.2.1]", "[IPv6:0:0:0:0:0:ffff:192.0.2.1]", "[IPv6:::ffff:c000:201]"] {
+        assert_eq!(input.parse(), Ok(AddressLiteral::IP(mapped.into())), "{}", input);
+    }
+    assert_eq!("[IPv6:::192.0.2.1]".parse(), Ok(AddressLiteral::IP(compatible.into())));
+    assert_eq!(mapped.to_ipv4_mapped(), Some(Ipv4Addr::new(192, 0, 2, 1)));
+
+    for input in &["[IPv6:::ffff:192.0.2.256]", "[IPv6:::ffff:192.0.2]", "[IPv6:::ffff:192.0.2.01]",
+                   "[IPv6:::ffff:192.0.2.1.]", "[IPv6:192.0.2.1]", "[IPv6:::ffff:192.0.2.1:1]"] {
+        assert!(input.parse::<AddressLiteral>().is_err(), "{}", input);
+    }
+
+    let (_, (path, _)) = exact!(&b"RCPT TO:<bob@[IPv6:::ffff:192.0.2.1]>\r\n"[..], rcpt_command::<Legacy>).unwrap();
+    assert_eq!(path.to_string(), "<bob@[IPv6:::ffff:192.0.2.1]>");
+}
+
+#[test]
+fn address_literal_canonical() {
+    let canonical = |input: &str| input.parse::<AddressLiteral>().unwrap().canonical().to_string();
+
+    assert_eq!(canonical("[IPv6:2001:DB8:0:0:0:0:0:1]"), "[IPv6:2001:db8::1]");
+    assert_eq!(canonical("[ipv6:2001:db8::1]"), "[IPv6:2001:db8::1]");
+    assert_eq!(canonical("[IPv6:2001:0db8::0001]"), "[IPv6:2001:db8::1]");
+    assert_eq!(canonical("[IPv6:FE80::1%eth0]"), "[IPv6:fe80::1%eth0]");
+    assert_eq!(canonical("[192.0.2.1]"), "[192.0.2.1]");
+    assert_eq!(canonical("[X-Tag:Value]"), "[x-tag:Value]");
+
+    assert_eq!(AddressLiteral::FreeForm("IPv6:0::1".into()).canonical().to_string(), "[IPv6:::1]");
+    assert_eq!(AddressLiteral::FreeForm("not a literal".into()).canonical().to_string(), "[not a literal]");
+}
+
+#[test]
+fn domain_part_predicates() {
+    let mailbox = |input: &str| Mailbox::from_str(input).unwrap();
+
+    let m = mailbox("bob@example.org");
+    assert!(m.domain_part().is_domain());
+    assert!(!m.domain_part().is_address_literal());
+    assert!(!m.domain_is_literal());
+    assert_eq!(m.domain_part().as_ip(), None);
+
+    let m = mailbox("bob@[192.0.2.1]");
+    assert!(m.domain_is_literal());
+    assert!(!m.domain_part().is_domain());
+    assert_eq!(m.domain_part().as_ip(), Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))));
+
+    assert_eq!(mailbox("bob@[IPv6:fe80::1%eth0]").domain_part().as_ip(), Some("fe80::1".parse().unwrap()));
+    assert!(mailbox("bob@[x400:cn=bob]").domain_is_literal());
+    assert_eq!(mailbox("bob@[x400:cn=bob]").domain_part().as_ip(), None);
+}
+
+#[test]
+fn etrn() {
+    let domain = |d: &str| Domain::from_smtp(d.as_bytes()).unwrap();
+
+    assert_eq!(exact!(&b"ETRN example.org\r\n"[..], etrn_command::<Intl>).unwrap().1, EtrnArg::Domain(domain("example.org")));
+    assert_eq!(exact!(&b"etrn @example.org\r\n"[..], etrn_command::<Intl>).unwrap().1, EtrnArg::Subdomains(domain("example.org")));
+    assert_eq!(exact!(&b"ETRN #queue-1\r\n"[..], etrn_command::<Intl>).unwrap().1, EtrnArg::Queue("queue-1".into()));
+
+    for input in &["ETRN\r\n", "ETRN \r\n", "ETRN @\r\n", "ETRN #\r\n", "ETRN -example.org\r\n",
+                   "ETRN [192.0.2.1]\r\n", "ETRN example.org extra\r\n", "ETRN #q.1\r\n"] {
+        assert!(exact!(input.as_bytes(), etrn_command::<Intl>).is_err(), "{}", input);
+    }
+
+    let (_, cmd) = command::<Intl>(b"ETRN example.org\r\n").unwrap();
+    assert!(matches!(cmd, Command::ETRN(EtrnArg::Domain(_))));
+}
+
+#[test]
+fn mt_priority() {
+    let p = |v| [Param::new("MT-PRIORITY", v).unwrap()];
+
+    assert_eq!(mt_priority_param(&p(Some("0"))), Ok(Some(0)));
+    assert_eq!(mt_priority_param(&p(Some("+9"))), Ok(Some(9)));
+    assert_eq!(mt_priority_param(&p(Some("-9"))), Ok(Some(-9)));
+    assert_eq!(mt_priority_param(&[Param::new("mt-priority", Some("3")).unwrap()]), Ok(Some(3)));
+
+    for value in &["10", "-10", "99999999999999999999", "+009", "00", "-09",
+                   "high", "-", "+-1", "1.5", "0x1"] {
+        assert_eq!(mt_priority_param(&p(Some(value))), Err(MtPriorityError::Invalid), "{}", value);
+    }
+    assert_eq!(mt_priority_param(&p(None)), Err(MtPriorityError::MissingValue));
+    assert_eq!(mt_priority_param(&[p(Some("1"))[0].clone(), p(Some("2"))[0].clone()]), Err(MtPriorityError::Duplicate));
+}
+
+#[test]
+fn deliverby() {
+    let p = |v| [Param::new("BY", v).unwrap()];
+    let by = |seconds, mode, trace| Ok(Some(DeliverBy{seconds, mode, trace}));
+
+    assert_eq!(deliverby_param(&p(Some("240;R"))), by(240, DeliverByMode::Return, false));
+    assert_eq!(deliverby_param(&p(Some("+120;rt"))), by(120, DeliverByMode::Return, true));
+    assert_eq!(deliverby_param(&p(Some("-60;N"))), by(-60, DeliverByMode::Notify, false));
+    assert_eq!(deliverby_param(&p(Some("0;NT"))), by(0, DeliverByMode::Notify, true));
+    assert_eq!(deliverby_param(&p(Some("999999999;N"))), by(999_999_999, DeliverByMode::Notify, false));
+
+    assert_eq!(deliverby_param(&p(Some("0;R"))), Err(DeliverByError::NotPositive));
+    assert_eq!(deliverby_param(&p(Some("-1;RT"))), Err(DeliverByError::NotPositive));
+    for value in &["240", "240;", ";R", "240;X", "240;RTT", "240;TR", "1000000000;N", "2.5;N", "240;N+T"] {
+        assert_eq!(deliverby_param(&p(Some(value))), Err(DeliverByError::Invalid), "{}", value);
+    }
+    assert_eq!(deliverby_param(&p(None)), Err(DeliverByError::MissingValue));
+    let dup = [p(Some("1;R"))[0].clone(), p(Some("2;R"))[0].clone()];
+    assert_eq!(deliverby_param(&dup), Err(DeliverByError::Duplicate));
+}
+
+#[test]
+fn requiretls() {
+    let (_, (_, params)) = exact!(&b"MAIL FROM:<bob@example.org> RequireTLS BODY=8BITMIME\r\n"[..], mail_command::<Legacy>).unwrap();
+    assert!(requiretls_param(&params));
+    assert_eq!(requiretls_param_checked(&params), Ok(true));
+    assert_eq!(requiretls_param_checked(&params[1..]), Ok(false));
+
+    let with_value = [Param::new("REQUIRETLS", Some("YES")).unwrap()];
+    assert!(requiretls_param(&with_value));
+    assert_eq!(requiretls_param_checked(&with_value), Err(FlagParamError::UnexpectedValue));
+
+    let dup = [params[0].clone(), params[0].clone()];
+    assert_eq!(requiretls_param_checked(&dup), Err(FlagParamError::Duplicate));
+    assert_eq!(FlagParamError::Duplicate.to_string(), "Duplicate parameter");
+}
+
+#[test]
+fn mail_params_typed() {
+    let input = b"MAIL FROM:<bob@example.org> size=500 AUTH=<> RET=HDRS ENVID=QQ314159 SMTPUTF8 X-A=1 X-B\r\n";
+    let (path, params) = mail_command_typed::<Intl>(input).unwrap();
+
+    assert_eq!(path.to_string(), "<bob@example.org>");
+    assert_eq!(params, MailParams {
+        size: Some(500),
+        body: None,
+        auth: Some(None),
+        smtputf8: true,
+        ret: Some(crate::rfc3461::DSNRet::Hdrs),
+        envid: Some("QQ314159".into()),
+        extensions: vec![Param::new("X-A", Some("1")).unwrap(), Param::new("X-B", None).unwrap()],
+    });
+
+    let (_, params) = mail_command_typed::<Intl>(b"MAIL FROM:<>\r\n").unwrap();
+    assert_eq!(params, MailParams::default());
+}
+
+#[test]
+fn mail_params_invalid() {
+    let p = |input: &str| MailParams::try_from(exact!(input.as_bytes(), mail_command::<Intl>).unwrap().1 .1);
+
+    assert_eq!(p("MAIL FROM:<> SIZE=abc\r\n"), Err(MailParamsError::Size(SizeError::Invalid)));
+    assert_eq!(p("MAIL FROM:<> BODY=8BIT\r\n"), Err(MailParamsError::Body(BodyError::Invalid)));
+    assert_eq!(p("MAIL FROM:<> AUTH=bob\r\n"), Err(MailParamsError::Auth(AuthParamError::BadMailbox)));
+    assert_eq!(p("MAIL FROM:<> SMTPUTF8=YES\r\n"), Err(MailParamsError::Smtputf8(FlagParamError::UnexpectedValue)));
+    assert_eq!(p("MAIL FROM:<> SMTPUTF8 smtputf8\r\n"), Err(MailParamsError::Smtputf8(FlagParamError::Duplicate)));
+    assert_eq!(p("MAIL FROM:<> RET=ALL\r\n"), Err(MailParamsError::Dsn("Invalid RET")));
+    assert_eq!(p("MAIL FROM:<> SIZE=abc\r\n").unwrap_err().to_string(), "Invalid SIZE");
+    assert_eq!(p("MAIL FROM:<> SMTPUTF8=YES\r\n").unwrap_err().to_string(), "Parameter with value: SMTPUTF8");
+
+    assert_eq!(mail_command_typed::<Intl>(b"MAIL FROM:<> SIZE=abc\r\n"),
+               Err(TypedCommandError::Params(MailParamsError::Size(SizeError::Invalid))));
+    match mail_command_typed::<Intl>(b"MAIL FROM:<bob>\r\n") {
+        Err(TypedCommandError::Command(e)) => assert_eq!(e.kind, SMTPParseErrorKind::InvalidPath),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn rcpt_params_typed() {
+    let input = b"RCPT TO:<bob@example.org> NOTIFY=SUCCESS,DELAY ORCPT=rfc822;bob+2B1@example.org X-A=1\r\n";
+    let (_, params) = rcpt_command_typed::<Intl>(input).unwrap();
+
+    let notify = params.notify.unwrap();
+    assert!(notify.on_success && notify.delay && !notify.on_failure);
+    assert_eq!(params.orcpt, Some(("rfc822".into(), "bob+1@example.org".into())));
+    assert_eq!(params.extensions, [Param::new("X-A", Some("1")).unwrap()]);
+
+    assert_eq!(rcpt_command_typed::<Intl>(b"RCPT TO:<bob@example.org> NOTIFY=SOMETIMES\r\n"),
+               Err(TypedCommandError::Params(RcptParamsError::Notify(NotifyError::Invalid))));
+    assert_eq!(rcpt_command_typed::<Intl>(b"RCPT TO:<bob@example.org> ORCPT=rfc822\r\n"),
+               Err(TypedCommandError::Params(RcptParamsError::Orcpt(OrcptError::Invalid))));
+    assert_eq!(RcptParams::try_from(vec![]), Ok(RcptParams::default()));
+}
+
+#[test]
+fn parse_options_param_value_length() {
+    let value = "1".repeat(MAX_PARAM_VALUE_LENGTH);
+    let input = format!("MAIL FROM:<> SIZE={}\r\n", value);
+    let (_, (_, params, _)) = mail_command_with(input.as_bytes(), &ParseOptions::default()).unwrap();
+    assert_eq!(get_value(&params, "SIZE"), Some(value.as_str()));
+
+    let input = format!("MAIL FROM:<> SIZE={}1\r\n", value);
+    assert!(mail_command_with(input.as_bytes(), &ParseOptions::default()).is_err());
+    assert!(mail_command::<Legacy>(input.as_bytes()).is_err());
+    assert!(mail_command_detailed::<Legacy>(input.as_bytes()).is_err());
+    let options = ParseOptions{max_param_value_length: usize::MAX, ..Default::default()};
+    assert!(mail_command_with(input.as_bytes(), &options).is_ok());
+
+    let options = ParseOptions{max_param_value_length: 4, ..Default::default()};
+    assert!(rcpt_command_with(b"RCPT TO:<bob@example.org> X-A=1234 X-B\r\n", &options).is_ok());
+    assert!(rcpt_command_with(b"RCPT TO:<bob@example.org> X-A=12345\r\n", &options).is_err());
+    assert!(rcpt_command_with(b"RCPT TO:<bob@example.org> X-B X-A=12345\r\n", &options).is_err());
+}
+
+#[test]
+fn command_line_length() {
+    let line = |len| {
+        let mut l = vec![b'a'; len - 2];
+        l.extend_from_slice(b"\r\n");
+        l
+    };
+
+    assert_eq!(validate_command_line(&line(MAX_COMMAND_LINE_LENGTH)), Ok(()));
+    assert_eq!(validate_command_line(&line(MAX_COMMAND_LINE_LENGTH + 1)), Err(LineTooLong{length: 513, limit: 512}));
+    assert_eq!(validate_command_line(b""), Ok(()));
+
+    // Only the first line counts.
+    let mut two = line(10);
+    two.extend(line(1000));
+    assert_eq!(validate_command_line(&two), Ok(()));
+
+    // Partial lines must leave room for the CRLF.
+    assert_eq!(validate_command_line(&[b'a'; 510]), Ok(()));
+    assert_eq!(validate_command_line(&[b'a'; 511]), Err(LineTooLong{length: 511, limit: 512}));
+    let mut cr = vec![b'a'; 510];
+    cr.push(b'\r');
+    assert_eq!(validate_command_line(&cr), Ok(()));
+    cr.insert(0, b'a');
+    assert!(validate_command_line(&cr).is_err());
+
+    assert_eq!(validate_command_line_with(&line(1012), 1012), Ok(()));
+    assert!(validate_command_line_with(&line(1013), 1012).is_err());
+    assert_eq!(LineTooLong{length: 513, limit: 512}.to_string(), "command line of 513 octets exceeds 512");
+}
+
+#[test]
+fn mailbox_matches() {
+    let m = |s: &str| Mailbox::from_str(s).unwrap();
+    let strict = MatchOptions{case_sensitive_local: true, unquote: false};
+    let loose = MatchOptions{case_sensitive_local: false, unquote: true};
+
+    assert!(m("bob@example.org").matches(&m("bob@EXAMPLE.org"), MatchOptions::default()));
+    assert!(!m("bob@example.org").matches(&m("Bob@example.org"), MatchOptions::default()));
+    assert!(m("bob@example.org").matches(&m("\"bob\"@example.org"), MatchOptions::default()));
+    assert!(!m("bob@example.org").matches(&m("\"bob\"@example.org"), strict));
+    assert!(m("\"bob\"@example.org").matches(&m("\"bob\"@example.org"), strict));
+    assert!(m("\"a b\"@example.org").matches(&m("\"A B\"@example.org"), loose));
+    assert!(m("\"a\\\"b\"@example.org").matches(&m("\"a\\\"b\"@example.org"), strict));
+    assert!(!m("bob@example.org").matches(&m("bob@example.net"), loose));
+    assert!(m("bob@[192.0.2.1]").matches(&m("bob@[192.0.2.1]"), strict));
+    assert!(!m("bob@[192.0.2.1]").matches(&m("bob@[192.0.2.2]"), loose));
+    assert!(m("josé@été.example").matches(&m("JOSÉ@ÉTÉ.example"), loose));
+}
+
+#[test]
+fn lenient_commands() {
+    let mail = |input: &str| exact!(input.as_bytes(), mail_command_lenient::<Intl>).map(|(_, (p, params, l))| (p.to_string(), params.len(), l)).map_err(|_| ());
+    let rcpt = |input: &str| exact!(input.as_bytes(), rcpt_command_lenient::<Intl>).map(|(_, (p, params, l))| (p.to_string(), params.len(), l)).map_err(|_| ());
+
+    assert_eq!(mail("MAIL FROM:<bob@example.org> SIZE=10\r\n"), Ok(("<bob@example.org>".into(), 1, false)));
+    assert_eq!(mail("MAIL FROM:bob@example.org\r\n"), Ok(("<bob@example.org>".into(), 0, true)));
+    assert_eq!(mail("MAIL FROM: <bob@example.org>\r\n"), Ok(("<bob@example.org>".into(), 0, true)));
+    assert_eq!(mail("MAIL FROM:<> \r\n"), Ok(("<>".into(), 0, true)));
+    assert_eq!(mail("mail from:  bob@example.org  BODY=8BITMIME\tSIZE=1 \r\n"), Ok(("<bob@example.org>".into(), 2, true)));
+
+    assert_eq!(rcpt("RCPT TO:<bob@example.org> \r\n"), Ok(("<bob@example.org>".into(), 0, true)));
+    assert_eq!(rcpt("RCPT TO:bob@example.org NOTIFY=NEVER\r\n"), Ok(("<bob@example.org>".into(), 1, true)));
+    assert_eq!(rcpt("RCPT TO:Postmaster\r\n"), Ok(("<postmaster>".into(), 0, true)));
+    assert_eq!(rcpt("RCPT TO:<postmaster>\r\n"), Ok(("<postmaster>".into(), 0, false)));
+
+    for input in &["MAIL FROM:\r\n", "MAIL FROM:bob\r\n", "MAIL FROM:<bob@example.org\r\n", "MAIL FROM:bob@example.org>\r\n",
+                   "MAIL FROM:bob@example.org SIZE=\r\n"] {
+        assert!(mail(input).is_err(), "{}", input);
+    }
+    assert!(rcpt("RCPT TO:\r\n").is_err());
+    assert!(rcpt("RCPT TO:<>\r\n").is_err());
+}
+
+#[test]
+fn help() {
+    assert_eq!(exact!(&b"HELP\r\n"[..], help_command::<Legacy>).unwrap().1, None);
+    let (_, topic) = exact!(&b"Help \"mail from\"\r\n"[..], help_command::<Legacy>).unwrap();
+    assert_eq!(topic.as_deref(), Some("mail from"));
+
+    for input in &["HELP \r\n", "HELP\tRCPT\r\n", "HELP RC\x01PT\r\n", "HELP \"RC\x7fPT\"\r\n", "HELPME\r\n", "HELP a b\r\n"] {
+        assert!(exact!(input.as_bytes(), help_command::<Legacy>).is_err(), "{:?}", input);
+    }
+
+    let (_, cmd) = command::<Intl>(b"HELP DATA\r\n").unwrap();
+    assert!(matches!(cmd, Command::HELP(Some(_))));
+}
+
+#[test]
+fn domain_new() {
+    assert_eq!(Domain::new("mail.example.org").unwrap().as_str(), "mail.example.org");
+    assert_eq!(Domain::new("été.example").unwrap().to_string(), "été.example");
+    assert_eq!(Domain::new("x").unwrap(), Domain::from_smtp(b"x").unwrap());
+
+    for input in &["", ".", "example.org.", "-example.org", "exa mple.org", "[192.0.2.1]", "bob@example.org"] {
+        assert_eq!(Domain::new(input), Err(DomainError::Invalid), "{}", input);
+    }
+    let label = "a".repeat(MAX_LABEL_LENGTH);
+    assert!(Domain::new(&format!("{}.org", label)).is_ok());
+    assert_eq!(Domain::new(&format!("{}a.org", label)), Err(DomainError::TooLong));
+    assert_eq!(Domain::new(&vec!["abc"; 65].join(".")), Err(DomainError::TooLong));
+}
+
+#[test]
+fn command_kinds() {
+    for input in &["EHLO example.org\r\n", "helo example.org\r\n", "MAIL FROM:<>\r\n", "RCPT TO:<postmaster>\r\n",
+                   "DATA\r\n", "RSET\r\n", "NOOP\r\n", "QUIT\r\n", "VRFY bob\r\n", "EXPN staff\r\n", "HELP\r\n",
+                   "STARTTLS\r\n", "BDAT 1 LAST\r\n", "AUTH PLAIN\r\n", "ETRN example.org\r\n"] {
+        let (_, cmd) = exact!(input.as_bytes(), command::<Intl>).unwrap();
+        let (_, kind) = command_kind(input.as_bytes()).unwrap();
+
+        assert_eq!(cmd.kind(), kind, "{}", input);
+        assert!(input.to_ascii_uppercase().starts_with(kind.as_str()));
+    }
+
+    assert_eq!(command_verb(b"QUIT").unwrap().1, "QUIT");
+    assert_eq!(command_verb(b"quit\r\n").unwrap(), (&b"\r\n"[..], "quit"));
+    assert_eq!(command_kind(b"RcPt TO:garbage").unwrap().1, CommandKind::RCPT);
+    for input in &["", " QUIT\r\n", "QUIT:\r\n", "MAIL:FROM\r\n", "QU1T\r\n", "STARTTLSX\r\n"] {
+        assert!(command_kind(input.as_bytes()).is_err(), "{}", input);
+    }
+    assert_eq!(command_verb(b"XYZZY now\r\n").unwrap().1, "XYZZY");
+}
+
+#[test]
+fn warnings_clean() {
+    let (_, (_, _, warnings)) = exact!(&b"MAIL FROM:<bob@example.org>\r\n"[..], mail_command_with_warnings::<Intl>).unwrap();
+    assert!(warnings.is_empty());
+    let (_, (_, _, warnings)) = exact!(&b"MAIL FROM:<>\r\n"[..], mail_command_with_warnings::<Intl>).unwrap();
+    assert!(warnings.is_empty());
+    let (_, (_, _, warnings)) = exact!(&b"RCPT TO:<\"bob smith\"@example.org>\r\n"[..], rcpt_command_with_warnings::<Intl>).unwrap();
+    assert!(warnings.is_empty());
+    let (_, (_, _, warnings)) = exact!(&b"RCPT TO:<postmaster>\r\n"[..], rcpt_command_with_warnings::<Intl>).unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn warnings_source_route() {
+    let (_, (_, _, warnings)) = exact!(&b"RCPT TO:<@a.example,@b.example:bob@example.org>\r\n"[..], rcpt_command_with_warnings::<Intl>).unwrap();
+    assert_eq!(warnings, [Warning::SourceRoute]);
+}
+
+#[test]
+fn warnings_unnecessary_quoting() {
+    let (_, (_, _, warnings)) = exact!(&b"RCPT TO:<\"bob.smith\"@example.org> NOTIFY=NEVER\r\n"[..], rcpt_command_with_warnings::<Intl>).unwrap();
+    assert_eq!(warnings, [Warning::UnnecessaryQuoting]);
+    assert_eq!(Warning::UnnecessaryQuoting.to_string(), "unnecessary quoting in local part");
+}
+
+#[test]
+fn pipelined_commands() {
+    let (cmds, rem) = parse_commands::<Intl>(b"MAIL FROM:<a@example.org>\r\nRCPT TO:<b@example.org>\r\nRCPT TO:<c@example.org>\r\nDATA\r\n");
+    assert_eq!(cmds.len(), 4);
+    assert!(cmds.iter().all(|c| c.is_ok()));
+    assert!(matches!(cmds[3], Ok(Command::DATA)));
+    assert!(rem.is_empty());
+}
+
+#[test]
+fn pipelined_commands_errors() {
+    let (cmds, rem) = parse_commands::<Intl>(b"RCPT TO:<b@example.org> X=\r\nXYZZY\r\nNOOP\r\n");
+    assert_eq!(cmds.len(), 3);
+    assert!(cmds[0].is_err());
+    assert!(cmds[1].is_err());
+    assert!(matches!(cmds[2], Ok(Command::NOOP(None))));
+    assert!(rem.is_empty());
+}
+
+#[test]
+fn pipelined_commands_stop() {
+    let (cmds, rem) = parse_commands::<Intl>(b"BDAT 6 LAST\r\nQUIT\r\nRSET\r\n");
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(rem, b"QUIT\r\nRSET\r\n");
+
+    let (cmds, rem) = parse_commands::<Intl>(b"STARTTLS\r\nMAIL FROM:<>\r\n");
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(rem, b"MAIL FROM:<>\r\n");
+}
+
+#[test]
+fn pipelined_commands_partial() {
+    let (cmds, rem) = parse_commands::<Intl>(b"RSET\r\nMAIL FROM:<bob@exa");
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(rem, b"MAIL FROM:<bob@exa");
+
+    let (cmds, rem) = parse_commands::<Intl>(b"");
+    assert!(cmds.is_empty());
+    assert!(rem.is_empty());
+}
+
+fn incomplete<O>(res: NomResult<O>) -> bool {
+    matches!(res, Err(nom::Err::Incomplete(_)))
+}
+
+#[test]
+fn streaming_incomplete() {
+    for input in &[&b""[..], b"MA", b"mail from", b"MAIL FROM:<", b"MAIL FROM:<bob@exa",
+                   b"MAIL FROM:<\"bob smi", b"MAIL FROM:<bob@example.org> SIZE=10",
+                   b"MAIL FROM:<bob@example.org>\r", b"MAIL FROM:<bob@exa.", b"MAIL FROM:<bob@exa-",
+                   b"MAIL FROM:<bob@[192.0.", b"MAIL FROM:<bob@[IPv6:2001:db8", b"MAIL FROM:<@a,@b:bob",
+                   b"MAIL FROM:<bob@example.org> SIZE=10 ", b"MAIL FROM:<>"] {
+        assert!(incomplete(mail_command_streaming::<Intl>(input)), "{:?}", input);
+    }
+    assert!(incomplete(rcpt_command_streaming::<Intl>(b"RCPT TO:<postmas")));
+    assert!(incomplete(command_streaming::<Intl>(b"QUI")));
+    assert!(incomplete(command_streaming::<Intl>(b"DATA\r")));
+    assert!(incomplete(command_streaming::<Intl>(b"EHLO mail.exa")));
+}
+
+#[test]
+fn streaming_invalid() {
+    for input in &[&b"RCPT TO:<bob"[..], b"MAIL FROM:bob", b"MAIL FROM:<bob\0",
+                   b"MAIL FROM:<bob\r@", b"MAIL FROM:<bob@example.org\r\n", b"MAIL FROM:<bob@@",
+                   b"MAIL FROM:<bob@exa..", b"MAIL FROM:<bob@[999.", b"MAIL FROM:<bob@exa-.",
+                   b"MAIL FROM:<bob@example.org> =", b"MAIL FROM:<bob@example.org>x"] {
+        assert!(matches!(mail_command_streaming::<Intl>(input), Err(nom::Err::Error(_))), "{:?}", input);
+    }
+    assert!(matches!(command_streaming::<Intl>(b"DATAX"), Err(nom::Err::Error(_))));
+    assert!(matches!(command_streaming::<Intl>(b"XYZZY"), Err(nom::Err::Error(_))));
+    assert!(matches!(command_streaming::<Intl>(b"SAML FROM:<bob@@"), Err(nom::Err::Error(_))));
+    assert!(matches!(command_streaming::<Intl>(b"EHLO mail..exa"), Err(nom::Err::Error(_))));
+}
+
+#[test]
+fn streaming_complete() {
+    let (rem, cmd) = command_streaming::<Intl>(b"RCPT TO:<bob@example.org>\r\nDATA").unwrap();
+    assert!(matches!(cmd, Command::RCPT(_, _)));
+    assert_eq!(rem, b"DATA");
+}
+
+fn mail_error(input: &[u8]) -> (usize, String) {
+    match mail_command::<Intl>(input) {
+        Err(nom::Err::Error(err)) => (err.offset(input), err.to_string()),
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn error_context() {
+    assert_eq!(mail_error(b"MAIL FROM:<bob.example.org>\r\n"), (26, "expected '@'".into()));
+    assert_eq!(mail_error(b"MAIL FROM:<bob@example.org\r\n"), (26, "expected '>'".into()));
+    assert_eq!(mail_error(b"MAIL FROM:bob@example.org\r\n"), (10, "expected '<'".into()));
+    assert_eq!(mail_error(b"MAIL FROM:<bob@[IPv6:zz]>\r\n"), (21, "invalid IPv6 literal".into()));
+    assert_eq!(mail_error(b"MAIL FROM:<bob@[1.2.3]>\r\n"), (21, "invalid address literal".into()));
+    assert_eq!(mail_error(b"MAIL FROM:<bob@-->\r\n"), (15, "invalid domain".into()));
+    assert_eq!(mail_error(b"MAIL FROM:<bob@example.org>BODY=7BIT\r\n"), (27, "expected CRLF".into()));
+    assert_eq!(mail_error(b"RCPT TO:<bob@example.org>\r\n"), (0, "expected MAIL FROM:".into()));
+}
+
+#[test]
+fn error_trailing_data() {
+    let input = b"QUIT\r\nNOOP\r\n";
+    match exact!(&input[..], quit_command) {
+        Err(nom::Err::Error(err)) => {
+            assert_eq!(err.to_string(), "trailing data");
+            assert_eq!(err.offset(input), 6);
+        }
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn suggested_response() {
+    let code = |input: &[u8]| rcpt_command_detailed::<Intl>(input).unwrap_err().suggested_response().0;
+
+    assert_eq!(code(b"RCPT FROM:<bob@example.org>\r\n"), 501);
+    assert_eq!(rcpt_command_detailed::<Intl>(b"RCPT FROM:<bob@example.org>\r\n").unwrap_err(),
+               SMTPParseError{kind: SMTPParseErrorKind::Syntax, offset: 4, reason: "expected TO:"});
+    assert_eq!(code(b"XYZZY TO:<bob@example.org>\r\n"), 500);
+    assert_eq!(code(b"RCPT TO:<>\r\n"), 553);
+    assert_eq!(code(b"RCPT TO:<bob>\r\n"), 501);
+    assert_eq!(code(b"RCPT TO:<bob@example.org> =\r\n"), 501);
+    assert_eq!(rcpt_command_detailed::<Intl>(b"RCPT TO:<bob@example.org> =\r\n").unwrap_err().kind,
+               SMTPParseErrorKind::InvalidParameter);
+    assert_eq!(mail_command_detailed::<Intl>(b"MAIL FROM:<bob@example.org>X\r\n").unwrap_err().suggested_response().0, 501);
+
+    let long = vec![b'a'; MAX_COMMAND_LINE_LENGTH + 1];
+    assert_eq!(validate_command_line(&long).unwrap_err().suggested_response(), (500, "Line too long"));
+}
+
+#[test]
+fn command_suggested_response() {
+    let error = |input: &[u8]| {
+        let err = parse_command::<Intl>(input).unwrap_err();
+        (err.kind, err.offset, err.suggested_response().0)
+    };
+    use self::SMTPParseErrorKind::*;
+
+    assert_eq!(error(b"XYZZY foo\r\n"), (UnknownCommand, 0, 500));
+    assert_eq!(error(b"XYZZY\r\n"), (UnknownCommand, 0, 500));
+    assert_eq!(error(b"MAIL FROM:<bob>\r\n"), (InvalidPath, 10, 501));
+    assert_eq!(error(b"MAIL FROM:<> BODY=\r\n"), (InvalidParameter, 17, 501));
+    assert_eq!(error(b"RCPT FROM:<bob@example.org>\r\n"), (Syntax, 4, 501));
+    assert_eq!(error(b"RCPT TO:<>\r\n"), (NullForwardPath, 8, 553));
+    assert_eq!(error(b"EHLO -bad\r\n"), (Syntax, 5, 501));
+    assert_eq!(error(b"QUIT now\r\n"), (Syntax, 0, 501));
+
+    assert_eq!(Command::from_str("XYZZY foo\r\n").unwrap_err().suggested_response().0, 500);
+    let (cmds, _) = parse_commands::<Intl>(b"XYZZY\r\nRCPT FROM:<bob@example.org>\r\nQUIT\r\n");
+    let codes: Vec<_> = cmds.iter().map(|c| c.as_ref().map_err(|e| e.suggested_response().0)).collect();
+    assert!(matches!(codes[..], [Err(500), Err(501), Ok(_)]), "{:?}", codes);
+}
+
+#[test]
+fn hash_keys() {
+    use std::collections::HashSet;
+
+    let mut seen = HashSet::new();
+    assert!(seen.insert(Mailbox::from_str("bob@example.org").unwrap()));
+    assert!(!seen.insert(Mailbox::from_str("bob@example.org").unwrap()));
+    // Exact comparison does not ignore the domain case.
+    assert!(seen.insert(Mailbox::from_str("bob@EXAMPLE.org").unwrap()));
+
+    let paths: HashSet<_> = [ReversePath::Null, ReversePath::from_str("<>").unwrap(),
+                             ReversePath::from_str("<bob@example.org>").unwrap()].iter().cloned().collect();
+    assert_eq!(paths.len(), 2);
+}
+
+#[test]
+fn obsolete_commands() {
+    let (_, cmd) = exact!(&b"SEND FROM:<bob@example.org>\r\n"[..], command::<Intl>).unwrap();
+    assert!(matches!(cmd, Command::SEND(ReversePath::Path(_), _)));
+    assert_eq!(cmd.kind(), CommandKind::SEND);
+
+    let (_, cmd) = exact!(&b"soml from:<>\r\n"[..], command::<Intl>).unwrap();
+    assert!(matches!(cmd, Command::SOML(ReversePath::Null, _)));
+
+    let (_, cmd) = exact!(&b"SAML FROM:<bob@example.org> BODY=7BIT\r\n"[..], command::<Intl>).unwrap();
+    assert!(matches!(cmd, Command::SAML(_, ref p) if p.len() == 1));
+
+    let (_, cmd) = exact!(&b"TURN\r\n"[..], command::<Intl>).unwrap();
+    assert_eq!(cmd.kind(), CommandKind::TURN);
+
+    assert!(turn_command(b"TURN now\r\n").is_err());
+    assert!(send_command::<Intl>(b"SEND TO:<bob@example.org>\r\n").is_err());
+    assert_eq!(command_kind(b"SAML FROM:<bad\r\n").unwrap().1, CommandKind::SAML);
+}
+
+#[test]
+fn empty_quoted_local_part() {
+    let input = b"MAIL FROM:<\"\"@example.org>\r\n";
+    match mail_command::<Intl>(input) {
+        Err(nom::Err::Error(err)) => {
+            assert_eq!(err.to_string(), "empty quoted local part");
+            assert_eq!(err.offset(input), 11);
+        }
+        res => panic!("unexpected {:?}", res),
+    }
+
+    // Unrelated failures do not mention it.
+    for input in &[&b""[..], b"\"bob", b"@"] {
+        match local_part::<Intl>(input) {
+            Err(nom::Err::Error(err)) => assert_eq!(err.to_string(), "invalid syntax", "{:?}", input),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+    assert!(mailbox_ref::<Intl>(b"\"\"@example.org").is_err());
+    assert!(mailbox_ref::<Intl>(b"\" \"@example.org").is_ok());
+
+    assert!(!validate_address::<Intl>(b"\"\"@example.org"));
+    assert!(validate_address::<Intl>(b"\" \"@example.org"));
+    assert!(LocalPart::from_str("\"\"").is_err());
+    assert_eq!(Mailbox::new("", "example.org"), Err(MailboxError::EmptyLocalPart));
+    // Still valid as a plain quoted string argument.
+    assert_eq!(vrfy_command::<Intl>(b"VRFY \"\"\r\n").unwrap().1.to_string(), "");
+
+    assert!(mail_command_with(input, &ParseOptions::default()).is_err());
+    let options = ParseOptions{allow_empty_quoted_local_part: true, ..Default::default()};
+    let (_, (path, _, _)) = mail_command_with(input, &options).unwrap();
+    assert_eq!(path.to_string(), "<\"\"@example.org>");
+}
+
+#[test]
+fn smtputf8() {
+    let flag = Param::new("SmtpUtf8", None).unwrap();
+    let body = Param::new("BODY", Some("8BITMIME")).unwrap();
+
+    assert_eq!(smtputf8_param(&[]), Ok(false));
+    assert_eq!(smtputf8_param(std::slice::from_ref(&body)), Ok(false));
+    assert_eq!(smtputf8_param(&[body, flag.clone()]), Ok(true));
+    assert!(smtputf8_requested(std::slice::from_ref(&flag)));
+    assert_eq!(smtputf8_param(&[flag.clone(), flag]), Err(FlagParamError::Duplicate));
+    assert_eq!(smtputf8_param(&[Param::new("SMTPUTF8", Some("1")).unwrap()]), Err(FlagParamError::UnexpectedValue));
+    assert_eq!(MailParams::try_from(vec![Param::new("SMTPUTF8", Some("1")).unwrap()]),
+               Err(MailParamsError::Smtputf8(FlagParamError::UnexpectedValue)));
+}
+
+// Bytes that a locale or Unicode aware case folding could confuse
+// with an ASCII letter.
+fn non_ascii_lookalikes(c: u8) -> Vec<Vec<u8>> {
+    let mut out = vec![vec![c | 0x80], vec![(c ^ 0x20) | 0x80]];
+    match c.to_ascii_lowercase() {
+        b'i' => out.extend(vec!["ı".into(), "İ".into()]),
+        b's' => out.push("ſ".into()),
+        b'k' => out.push("\u{212a}".into()),
+        _ => (),
+    }
+    out.push(char::from_u32(0xff21 + u32::from(c.to_ascii_uppercase() - b'A')).unwrap().to_string().into());
+    out
+}
+
+#[test]
+fn keywords_are_ascii_case_insensitive_only() {
+    // (command, length of the keyword prefix)
+    let inputs: &[(&[u8], usize)] = &[
+        (b"EHLO example.org\r\n", 4),
+        (b"HELO example.org\r\n", 4),
+        (b"MAIL FROM:<bob@example.org>\r\n", 9),
+        (b"RCPT TO:<bob@example.org>\r\n", 7),
+        (b"DATA\r\n", 4),
+        (b"RSET\r\n", 4),
+        (b"NOOP\r\n", 4),
+        (b"QUIT\r\n", 4),
+        (b"VRFY bob\r\n", 4),
+        (b"EXPN list\r\n", 4),
+        (b"HELP\r\n", 4),
+        (b"STARTTLS\r\n", 8),
+        (b"BDAT 10\r\n", 4),
+        (b"AUTH PLAIN\r\n", 4),
+        (b"ETRN example.org\r\n", 4),
+        (b"SEND FROM:<>\r\n", 9),
+        (b"SOML FROM:<>\r\n", 9),
+        (b"SAML FROM:<>\r\n", 9),
+        (b"TURN\r\n", 4),
+    ];
+
+    for (input, len) in inputs {
+        let lower = input.to_ascii_lowercase();
+        assert!(exact!(&lower[..], command::<Intl>).is_ok(), "{:?}", lower);
+
+        for pos in (0..*len).filter(|&p| input[p].is_ascii_alphabetic()) {
+            for c in [input[pos], lower[pos]].iter() {
+                for lookalike in non_ascii_lookalikes(*c) {
+                    let mut bad = input[..pos].to_vec();
+                    bad.extend(&lookalike);
+                    bad.extend(&input[pos + 1..]);
+                    assert!(command::<Intl>(&bad).is_err(), "{:?}", String::from_utf8_lossy(&bad));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn argument_keywords_are_ascii_only() {
+    let (_, (path, _)) = rcpt_command::<Intl>(b"RCPT TO:<POSTMASTER@example.org>\r\n").unwrap();
+    assert_eq!(path, ForwardPath::PostMaster(Some(Domain("example.org".into()))));
+    // A lookalike is an ordinary local part, not the special postmaster.
+    let (_, (path, _)) = rcpt_command::<Intl>("RCPT TO:<POſTMASTER@example.org>\r\n".as_bytes()).unwrap();
+    assert!(matches!(path, ForwardPath::Path(_)));
+    assert!(rcpt_command::<Intl>(b"RCPT TO:<postmaster\xc9>\r\n").is_err());
+    assert!(rcpt_command::<Intl>("RCPT TO:<\u{ff30}ostmaster>\r\n".as_bytes()).is_err());
+
+    assert_eq!(bdat_command(b"BDAT 10 last\r\n").unwrap().1, (10, true));
+    assert!(bdat_command("BDAT 10 LAſT\r\n".as_bytes()).is_err());
+    assert!(bdat_command(b"BDAT 10 L\xc1ST\r\n").is_err());
+
+    assert!(validate_address::<Intl>(b"bob@[ipv6:::1]"));
+    assert!(!validate_address::<Intl>("bob@[İPv6:::1]".as_bytes()));
+    assert!(!validate_address::<Intl>(b"bob@[\xc9Pv6:::1]"));
+}
+
+#[test]
+fn command_conversions() {
+    let cmd = Command::try_from(&b"MAIL FROM:<bob@example.org> BODY=8BITMIME\r\n"[..]).unwrap();
+    assert!(matches!(cmd, Command::MAIL(ReversePath::Path(_), ref p) if p.len() == 1));
+    assert!(matches!(Command::try_from("quit\r\n").unwrap(), Command::QUIT));
+    assert!(matches!(Command::from_str("RCPT TO:<postmaster>\r\n").unwrap(), Command::RCPT(ForwardPath::PostMaster(None), _)));
+
+    // A single complete line is expected.
+    assert!(Command::try_from(&b"QUIT"[..]).is_err());
+    assert!(Command::try_from(&b"QUIT\r\nQUIT\r\n"[..]).is_err());
+    assert_eq!(Command::from_str("RSET\r\nX").unwrap_err().offset, 6);
+    // Raw bytes need not be valid UTF-8 to be rejected cleanly.
+    assert!(Command::try_from(&b"VRFY \xff\r\n"[..]).is_err());
+
+    assert_eq!(ReversePath::try_from(&b"<>"[..]).unwrap(), ReversePath::Null);
+    assert_eq!(Mailbox::try_from(&b"bob@example.org"[..]).unwrap().to_string(), "bob@example.org");
+}
+
+#[test]
+fn command_dispatch() {
+    let reason = |input: &[u8]| match command::<Intl>(input) {
+        Err(nom::Err::Error(e)) => e.reason(),
+        other => panic!("unexpected {:?}", other),
+    };
+
+    assert_eq!(reason(b"XYZZY foo\r\n"), "unknown command");
+    assert_eq!(reason(b"MAILFROM:<>\r\n"), "unknown command");
+    assert_eq!(reason(b"RCPT TO:<bob>\r\n"), "expected '@'");
+
+    let err = parse_command::<Intl>(b"XYZZY\r\n").unwrap_err();
+    assert_eq!((err.kind, err.offset, err.reason), (SMTPParseErrorKind::UnknownCommand, 0, "unknown command"));
+    assert_eq!(Command::from_str("XYZZY foo\r\n").unwrap_err().kind, SMTPParseErrorKind::UnknownCommand);
+
+    let err = parse_command::<Intl>(b"RCPT TO:<bob>\r\n").unwrap_err();
+    assert_eq!((err.kind, err.offset, err.reason), (SMTPParseErrorKind::InvalidPath, 8, "invalid forward path"));
+    let err = parse_command::<Intl>(b"EHLO -bad\r\n").unwrap_err();
+    assert_eq!((err.kind, err.offset), (SMTPParseErrorKind::Syntax, 5));
+}
+
+#[test]
+fn command_raw_span() {
+    let input = b"RCPT TO:<bob@example.org> NOTIFY=NEVER\r\nDATA\r\n";
+    let (rem, (raw, cmd)) = command_with_raw::<Intl>(input).unwrap();
+    assert_eq!(raw, &input[..40]);
+    assert!(matches!(cmd, Command::RCPT(_, _)));
+
+    let (rem, (raw, cmd)) = command_with_raw::<Intl>(rem).unwrap();
+    assert_eq!(raw, b"DATA\r\n");
+    assert!(matches!(cmd, Command::DATA));
+    assert_eq!(rem, b"");
+
+    assert!(command_with_raw::<Intl>(b"RCPT TO:<bad>\r\n").is_err());
+}
+
+#[test]
+fn numeric_tld() {
+    let strict = ParseOptions{allow_numeric_tld: false, ..Default::default()};
+
+    for input in &[&b"RCPT TO:<bob@192.0.2.1>\r\n"[..], b"RCPT TO:<bob@example.123>\r\n"] {
+        assert!(rcpt_command::<Intl>(input).is_ok());
+        assert!(rcpt_command_with(input, &ParseOptions::default()).is_ok());
+        match rcpt_command_with(input, &strict) {
+            Err(nom::Err::Error(err)) => {
+                assert_eq!(err.to_string(), "numeric top-level domain");
+                assert_eq!(err.offset(input), 13);
+            }
+            res => panic!("unexpected {:?}", res),
+        }
+        let (_, (_, _, warnings)) = rcpt_command_with_warnings::<Intl>(input).unwrap();
+        assert_eq!(warnings, [Warning::NumericTopLevelDomain]);
+    }
+
+    for input in &[&b"RCPT TO:<bob@[192.0.2.1]>\r\n"[..], b"RCPT TO:<bob@123.example>\r\n",
+                   b"RCPT TO:<bob@example.1a>\r\n", b"RCPT TO:<postmaster@example.org>\r\n"] {
+        assert!(rcpt_command_with(input, &strict).is_ok());
+        assert_eq!(rcpt_command_with_warnings::<Intl>(input).unwrap().1 .2, []);
+    }
+    assert!(mail_command_with(b"MAIL FROM:<@relay.1:bob@example.org>\r\n", &strict).is_err());
+    assert!(rcpt_command_with(b"RCPT TO:<postmaster@192.0.2.1>\r\n", &strict).is_err());
+}
+
+#[test]
+fn address_literal_from_ip() {
+    let ipv4 = Ipv4Addr::new(192, 0, 2, 1);
+    let ipv6 = Ipv6Addr::from_str("2001:db8::1").unwrap();
+
+    assert_eq!(AddressLiteral::from(ipv4).to_string(), "[192.0.2.1]");
+    assert_eq!(AddressLiteral::from(ipv6).to_string(), "[IPv6:2001:db8::1]");
+    assert_eq!(AddressLiteral::from(IpAddr::from(ipv6)), AddressLiteral::IP(ipv6.into()));
+
+    let dp: DomainPart = AddressLiteral::from(ipv4).into();
+    assert_eq!(Mailbox(LocalPart::from_str("bob").unwrap(), dp).to_string(), "bob@[192.0.2.1]");
+    assert_eq!(AddressLiteral::from_str(&AddressLiteral::from(ipv6).to_string()).unwrap().as_ip(), Some(ipv6.into()));
+}
+
+#[test]
+fn smtp_path_rendering() {
+    let mailbox = Mailbox::from_str("\"bob smith\"@[IPv6:::1]").unwrap();
+    assert_eq!(mailbox.to_smtp_path(), "<\"bob smith\"@[IPv6:::1]>");
+
+    let path = ReversePath::Path(Path(mailbox.clone(), Vec::new()));
+    assert_eq!(path.to_smtp_path(), mailbox.to_smtp_path());
+    assert_eq!(ReversePath::Null.to_smtp_path(), "<>");
+
+    // The rendered paths parse back to the same value.
+    let input = format!("MAIL FROM:{}\r\n", path.to_smtp_path());
+    assert_eq!(mail_command::<Intl>(input.as_bytes()).unwrap().1 .0, path);
+}
+
+#[test]
+fn space_after_colon() {
+    let relaxed = ParseOptions{allow_space_after_colon: true, ..Default::default()};
+    let mail = b"MAIL FROM: <bob@example.org> SIZE=10\r\n";
+    let rcpt = b"RCPT TO:\t <postmaster>\r\n";
+
+    assert!(mail_command_with(mail, &ParseOptions::default()).is_err());
+    assert!(rcpt_command_with(rcpt, &ParseOptions::default()).is_err());
+
+    let (_, (path, params, _)) = mail_command_with(mail, &relaxed).unwrap();
+    assert_eq!(path.to_string(), "<bob@example.org>");
+    assert_eq!(params.len(), 1);
+    assert_eq!(rcpt_command_with(rcpt, &relaxed).unwrap().1 .0, ForwardPath::PostMaster(None));
+    assert_eq!(mail_command_with(b"MAIL FROM:<>\r\n", &relaxed).unwrap().1 .0, ReversePath::Null);
+    // Only the space after the colon is tolerated.
+    assert!(mail_command_with(b"MAIL FROM: <bob@example.org> \r\n", &relaxed).is_err());
+    assert!(mail_command_with(b"MAIL FROM : <bob@example.org>\r\n", &relaxed).is_err());
+
+    let (_, (_, _, warnings)) = rcpt_command_with(rcpt, &relaxed).unwrap();
+    assert_eq!(warnings, [Warning::SpaceAfterColon]);
+    let (_, (_, _, warnings)) = mail_command_with(b"MAIL FROM: <\"bob\"@example.org>\r\n", &relaxed).unwrap();
+    assert_eq!(warnings, [Warning::SpaceAfterColon, Warning::UnnecessaryQuoting]);
+    let (_, (_, _, warnings)) = mail_command_with(b"MAIL FROM:<bob@example.org>\r\n", &relaxed).unwrap();
+    assert_eq!(warnings, []);
+
+    // The lenient parsers report it.
+    assert!(mail_command_lenient::<Legacy>(mail).unwrap().1 .2);
+    assert!(rcpt_command_lenient::<Legacy>(rcpt).unwrap().1 .2);
+}
+
+#[test]
+fn missing_angle_brackets_and_extra_whitespace() {
+    let bare = ParseOptions{allow_missing_angle_brackets: true, ..Default::default()};
+    let spaced = ParseOptions{allow_extra_whitespace: true, ..Default::default()};
+
+    assert!(mail_command_with(b"MAIL FROM:bob@example.org\r\n", &ParseOptions::default()).is_err());
+    let (_, (path, _, warnings)) = mail_command_with(b"MAIL FROM:bob@example.org\r\n", &bare).unwrap();
+    assert_eq!(path.to_string(), "<bob@example.org>");
+    assert_eq!(warnings, [Warning::MissingAngleBrackets]);
+    let (_, (path, _, warnings)) = rcpt_command_with(b"RCPT TO:postmaster\r\n", &bare).unwrap();
+    assert_eq!(path, ForwardPath::PostMaster(None));
+    assert_eq!(warnings, [Warning::MissingAngleBrackets]);
+    assert_eq!(rcpt_command_with(b"RCPT TO:<postmaster>\r\n", &bare).unwrap().1 .2, []);
+    assert!(mail_command_with(b"MAIL FROM:bob@example.org \r\n", &bare).is_err());
+
+    for input in &[&b"MAIL FROM:<bob@example.org> \r\n"[..], b"MAIL FROM:<bob@example.org>  SIZE=10\r\n",
+                   b"MAIL FROM:<bob@example.org>\tSIZE=10\r\n", b"MAIL FROM:<bob@example.org> SIZE=10\t\r\n"] {
+        assert!(mail_command_with(input, &ParseOptions::default()).is_err());
+        assert_eq!(mail_command_with(input, &spaced).unwrap().1 .2, [Warning::ExtraWhitespace]);
+    }
+    assert_eq!(mail_command_with(b"MAIL FROM:<bob@example.org> SIZE=10\r\n", &spaced).unwrap().1 .2, []);
+    assert!(mail_command_with(b"MAIL FROM:bob@example.org  \r\n", &spaced).is_err());
+}
+
+#[test]
+fn line_endings() {
+    let trimmed = ParseOptions{require_crlf: false, ..Default::default()};
+    let line = b"MAIL FROM:<bob@example.org> SIZE=10\r\n";
+
+    assert!(mail_command::<Legacy>(strip_crlf(line)).is_err());
+    assert!(mail_command_with(strip_crlf(line), &ParseOptions::default()).is_err());
+    assert_eq!(mail_command_with(strip_crlf(line), &trimmed), mail_command_with(line, &trimmed));
+    assert_eq!(mail_command_with(line, &trimmed), mail_command_with_warnings::<Legacy>(line));
+    assert!(rcpt_command_with(b"RCPT TO:<postmaster>", &trimmed).is_ok());
+
+    // A missing CRLF is only accepted at the end of the input.
+    assert!(rcpt_command_with(b"RCPT TO:<postmaster>\n", &trimmed).is_err());
+    let (rem, _) = rcpt_command_with(b"RCPT TO:<postmaster>\r\nDATA\r\n", &trimmed).unwrap();
+    assert_eq!(rem, b"DATA\r\n");
+
+    assert_eq!(strip_crlf(b"DATA\r\n\r\n"), b"DATA\r\n");
+    assert_eq!(strip_crlf(b""), b"");
+}
+
+#[test]
+fn forward_path_display() {
+    for input in &["<postmaster>", "<postmaster@example.org>", "<bob@example.org>",
+                   "<@a.example,@b.example:bob@[192.0.2.1]>", "<\"bob smith\"@example.org>"] {
+        let path = ForwardPath::from_str(input).unwrap();
+        assert_eq!(path.to_string(), *input);
+        assert_eq!(ForwardPath::from_str(&path.to_string()).unwrap(), path);
+    }
+    assert_eq!(ForwardPath::from_str("<PostMaster>").unwrap().to_string(), "<postmaster>");
+    assert_eq!(Path::from_str("<bob@example.org>").unwrap().to_string(), "<bob@example.org>");
+    assert_eq!(String::from(ForwardPath::PostMaster(None)), "<postmaster>");
+}
+
+#[test]
+fn reverse_path_display() {
+    assert_eq!(ReversePath::Null.to_string(), "<>");
+    for input in &["<bob@example.org>", "<@a.example:bob@[IPv6:::1]>", "<\"bob smith\"@example.org>"] {
+        let path = ReversePath::from_str(input).unwrap();
+        assert_eq!(path.to_string(), *input);
+        assert_eq!(ReversePath::from_str(&path.to_string()).unwrap(), path);
+    }
+
+    let (_, (path, _)) = mail_command::<Intl>(b"MAIL FROM:<>\r\n").unwrap();
+    assert_eq!(format!("MAIL FROM:{}\r\n", path), "MAIL FROM:<>\r\n");
+    assert_eq!(String::from(ReversePath::Null), "<>");
+}
+
+#[test]
+fn dot_string_dots() {
+    for local in &[".bob", "bob.", "bob..doe", ".", "..", ".bob.", "bob...doe"] {
+        let address = format!("{}@example.org", local);
+        assert!(!validate_address::<Intl>(address.as_bytes()), "{}", address);
+        assert!(!validate_address::<Legacy>(address.as_bytes()), "{}", address);
+        assert!(LocalPart::from_str(local).is_err(), "{}", local);
+        assert!(mail_command::<Intl>(format!("MAIL FROM:<{}>\r\n", address).as_bytes()).is_err(), "{}", address);
+        assert!(rcpt_command_with(format!("RCPT TO:<{}>\r\n", address).as_bytes(), &ParseOptions::default()).is_err());
+        // Still valid when quoted.
+        assert!(validate_address::<Intl>(format!("\"{}\"@example.org", local).as_bytes()), "{}", local);
+    }
+    assert!(validate_address::<Intl>(b"bob.doe@example.org"));
+    assert!(validate_address::<Intl>(b"b.o.b@example.org"));
+}
+
+#[test]
+fn normalized_mailbox() {
+    use std::collections::HashSet;
+
+    let n = |s| NormalizedMailbox::from(Mailbox::from_str(s).unwrap());
+
+    assert_eq!(n("bob@EXAMPLE.org"), n("bob@example.org"));
+    assert_eq!(n("\"bob\"@example.org"), n("bob@example.org"));
+    assert_ne!(n("Bob@example.org"), n("bob@example.org"));
+    assert_ne!(n("bob@[192.0.2.1]"), n("bob@[192.0.2.2]"));
+    assert_eq!(n("\"Bob Smith\"@Example.org"), n("\"Bob Smith\"@example.ORG"));
+
+    let book: HashSet<_> = ["Bob.Smith@Example.Org", "Bob.Smith@example.org", "bob.smith@example.org"]
+        .iter().map(|s| n(s)).collect();
+    assert_eq!(book.len(), 2);
+
+    let first = n("Bob.Smith@Example.Org");
+    assert!(book.contains(&first));
+    assert_eq!(first.to_string(), "Bob.Smith@Example.Org");
+    assert_eq!(first.normalized().to_string(), "Bob.Smith@example.org");
+    assert_eq!(first.into_original(), Mailbox::from_str("Bob.Smith@Example.Org").unwrap());
+}
+
+#[test]
+fn ascii_predicates() {
+    assert!(LocalPart::from_str("\"bob smith\"").unwrap().is_ascii());
+    assert!(!LocalPart::from_str("\"josé smith\"").unwrap().is_ascii());
+    assert!(DomainPart::from_str("[IPv6:::1]").unwrap().is_ascii());
+    assert!(DomainPart::from_str("xn--r8jz45g.jp").unwrap().is_ascii());
+    assert!(!DomainPart::from_str("例え.jp").unwrap().is_ascii());
+    assert!(!DomainPart::Address(AddressLiteral::FreeForm("été".into())).is_ascii());
+
+    let (_, (path, _)) = mail_command::<Legacy>(b"MAIL FROM:<bob@example.org>\r\n").unwrap();
+    assert!(matches!(path, ReversePath::Path(Path(ref m, _)) if m.is_ascii()));
+    let (_, (path, _)) = mail_command::<Intl>("MAIL FROM:<bob@例え.jp>\r\n".as_bytes()).unwrap();
+    assert!(matches!(path, ReversePath::Path(Path(ref m, _)) if !m.is_ascii()));
+}
+
+#[test]
+#[cfg(feature = "idna")]
+fn downgrade() {
+    let m = |s: &str| Mailbox::from_str(s).unwrap();
+
+    assert_eq!(m("bob@example.org").downgrade().unwrap(), m("bob@example.org"));
+    assert_eq!(m("\"bob smith\"@Été.example").downgrade().unwrap().to_string(), "\"bob smith\"@xn--t-9fab.example");
+    assert_eq!(m("bob@[IPv6:::1]").downgrade().unwrap(), m("bob@[IPv6:::1]"));
+
+    let downgraded = m("bob@例え.jp").downgrade().unwrap();
+    assert!(downgraded.is_ascii());
+    assert!(validate_address::<Legacy>(downgraded.to_string().as_bytes()));
+
+    assert_eq!(m("josé@example.org").downgrade(), Err(DowngradeError::NonAsciiLocalPart));
+    assert_eq!(m("\"josé smith\"@example.org").downgrade(), Err(DowngradeError::NonAsciiLocalPart));
+    let literal = Mailbox::from_parts(LocalPart::from_str("bob").unwrap(),
+                                      DomainPart::Address(AddressLiteral::FreeForm("été".into())));
+    assert_eq!(literal.downgrade(), Err(DowngradeError::InvalidDomain));
+    assert_eq!(DowngradeError::NonAsciiLocalPart.to_string(), "non-ASCII local part");
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn std_errors() {
+    fn parse(line: &str) -> Result<Command, Box<dyn std::error::Error>> {
+        Ok(Command::from_str(line)?)
+    }
+
+    let line = "MAIL TO:<bob@example.org>\r\n";
+    assert_eq!(parse(line).unwrap_err().to_string(), Command::from_str(line).unwrap_err().to_string());
+
+    let errors: Vec<Box<dyn std::error::Error>> = vec![
+        Box::new(SizeError::Invalid),
+        Box::new(MailParamsError::Size(SizeError::Duplicate)),
+        Box::new(RcptParamsError::Notify(NotifyError::MissingValue)),
+        Box::new(TypedCommandError::<MailParamsError>::Params(MailParamsError::Size(SizeError::Invalid))),
+        Box::new(DomainError::Invalid),
+    ];
+    for err in errors {
+        assert!(!err.to_string().is_empty());
+    }
+}
@@ -1,5 +1,7 @@
+use std::str::FromStr;
+
 use crate::behaviour::{Intl, Legacy};
-use crate::rfc5322::{Address, Group, Mailbox, from, reply_to, sender, unstructured};
+use crate::rfc5322::{Address, Group, Mailbox, ReceivedHost, received, return_path, addr_spec, angle_addr, cc, cfws_comments, cfws_comments_with_depth, MAX_COMMENT_DEPTH, from, message_id, reply_to, sender, sender_mailbox, to, unfold, unique_domains, unstructured, validate_address};
 use crate::types::{Mailbox as SMTPMailbox, *};
 
 fn dp<T: Into<String>>(value: T) -> DomainPart {
@@ -98,6 +100,7 @@ fn folded_qs() {
 }
 
 #[test]
+#[cfg(feature = "std")]
 fn intl_subject() {
     let (rem, parsed) = unstructured::<Intl>(b"=?x-sjis?B?lEWWQI7Kg4GM9ZTygs6CtSiPzik=?=").unwrap();
     assert_eq!(rem.len(), 0);
@@ -128,3 +131,273 @@ fn invalid_latin1() {
     assert_eq!(rem.len(), 0);
     assert_eq!(parsed, "\u{fffd}");
 }
+
+#[test]
+fn from_mailbox_list() {
+    let (rem, parsed) = from::<Intl>(b"\"John Doe\" <john@example.org>, jane@example.org\r\n").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(parsed, [
+        Address::Mailbox(Mailbox{dname: Some("John Doe".into()),
+                                 address: SMTPMailbox(DotAtom("john".into()).into(), dp("example.org"))}),
+        Address::Mailbox(Mailbox{dname: None,
+                                 address: SMTPMailbox(DotAtom("jane".into()).into(), dp("example.org"))}),
+    ]);
+}
+
+#[test]
+fn from_folded_with_comments() {
+    let (rem, parsed) = from::<Intl>(b"Pete(A nice \\) chap) <pete(his account)@silly.test(his host)>,\r\n (x) jane@example.org (Jane)\r\n").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(parsed.len(), 2);
+    match &parsed[0] {
+        Address::Mailbox(m) => {
+            assert_eq!(m.dname, Some("Pete".into()));
+            assert_eq!(m.address.to_string(), "pete@silly.test");
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match &parsed[1] {
+        Address::Mailbox(m) => assert_eq!(m.address.to_string(), "jane@example.org"),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn to_groups() {
+    let (rem, parsed) = to::<Intl>(b"Team: a@x.test, \"B\" <b@y.test>;, Undisclosed recipients:;, c@z.test\r\n").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(parsed, [
+        Address::Group(Group{
+            dname: "Team".into(),
+            members: vec![
+                Mailbox { dname: None, address: SMTPMailbox(DotAtom("a".into()).into(), dp("x.test"))},
+                Mailbox { dname: Some("B".into()), address: SMTPMailbox(DotAtom("b".into()).into(), dp("y.test"))},
+            ]
+        }),
+        Address::Group(Group{ dname: "Undisclosed recipients".into(), members: vec![] }),
+        Address::Mailbox(Mailbox { dname: None, address: SMTPMailbox(DotAtom("c".into()).into(), dp("z.test"))}),
+    ]);
+}
+
+#[test]
+fn cc_quoted_group_name() {
+    let (rem, parsed) = cc::<Intl>(b"\"The: Team\" (staff) : a@x.test ;\r\n").unwrap();
+    assert_eq!(rem.len(), 0);
+    match &parsed[..] {
+        [Address::Group(g)] => {
+            assert_eq!(g.dname, "The: Team");
+            assert_eq!(g.members.len(), 1);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn message_id_literal() {
+    let (rem, (left, right)) = message_id::<Intl>(b"<abc$def@[192.0.2.1]>\r\n").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(left, "abc$def");
+    assert_eq!(right, DomainPart::Address(AddressLiteral::IP("192.0.2.1".parse().unwrap())));
+
+    let (_, (_, right)) = message_id::<Intl>(b"<a@[free_form]>").unwrap();
+    assert_eq!(right, DomainPart::Address(AddressLiteral::FreeForm("free_form".into())));
+    assert!(message_id::<Intl>(b"<a@[free form]>").is_err());
+}
+
+#[test]
+fn message_id_cfws() {
+    let (rem, (left, right)) = message_id::<Intl>(b"(c1)\r\n <x.y@example.org> (c2)").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(left, "x.y");
+    assert_eq!(right, dp("example.org"));
+}
+
+#[test]
+fn message_id_invalid() {
+    assert!(message_id::<Intl>(b"x@example.org").is_err());
+    assert!(message_id::<Intl>(b"<x@example.org").is_err());
+    assert!(message_id::<Intl>(b"<x (c) @example.org>").is_err());
+    assert!(message_id::<Intl>(b"<\"x\"@example.org>").is_err());
+    assert!(message_id::<Intl>(b"<x..y@example.org>").is_err());
+    assert!(message_id::<Intl>(b"<x@exa mple.org>").is_err());
+}
+
+#[test]
+fn header_addr_spec() {
+    let (rem, m) = addr_spec::<Intl>(b" \"bob smith\" @ [192.0.2.1] (literal)").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(m.to_string(), "\"bob smith\"@[192.0.2.1]");
+    assert!(addr_spec::<Intl>(b"<bob@example.org>").is_err());
+}
+
+#[test]
+fn header_angle_addr() {
+    let (rem, m) = angle_addr::<Intl>(b"(x)\r\n <bob@example.org>\r\n (y)").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(m, SMTPMailbox(DotAtom("bob".into()).into(), dp("example.org")));
+    assert!(angle_addr::<Intl>(b"bob@example.org").is_err());
+    assert!(angle_addr::<Intl>(b"<bob@example.org").is_err());
+}
+
+#[test]
+fn comments() {
+    let (rem, c) = cfws_comments::<Intl>(b"(one)(two)  ((three))").unwrap();
+    assert_eq!(rem.len(), 0);
+    assert_eq!(c, ["one", "two", "(three)"]);
+
+    let (rem, c) = cfws_comments::<Intl>(b"  \r\n x").unwrap();
+    assert_eq!(rem, b"x");
+    assert!(c.is_empty());
+
+    let (_, c) = cfws_comments::<Intl>(b"(a\\(b\\\\c ( d ) )").unwrap();
+    assert_eq!(c, ["a(b\\c ( d ) "]);
+
+    let (_, c) = cfws_comments::<Intl>("(Gérard)".as_bytes()).unwrap();
+    assert_eq!(c, ["Gérard"]);
+
+    assert!(cfws_comments::<Intl>(b"(unbalanced").is_err());
+    assert!(cfws_comments::<Intl>(b"x").is_err());
+}
+
+#[test]
+fn unfold_values() {
+    use std::borrow::Cow;
+
+    assert!(matches!(unfold(b"no folding\r\n"), Cow::Borrowed(_)));
+    assert_eq!(unfold(b"a\r\n b\r\n\t\tc"), &b"a b\t\tc"[..]);
+    assert_eq!(unfold(b"a\r\nb"), &b"a\r\nb"[..]);
+    assert_eq!(unfold(b"a\r\n\r\n b"), &b"a\r\n b"[..]);
+    assert_eq!(unfold(b"a\n b\r \r\n"), &b"a\n b\r \r\n"[..]);
+    assert_eq!(unfold(b"\xff\r\n \xfe"), &b"\xff \xfe"[..]);
+    assert_eq!(unfold(b""), &b""[..]);
+}
+
+#[test]
+fn received_exim() {
+    let (rem, r) = received::<Intl>(b"from [192.0.2.1] (helo=client.example)\r\n\tby mx.example.com with esmtpsa (TLS1.3) (Exim 4.94)\r\n\t(envelope-from <a@example.org>)\r\n\tid 1kXyZ-0001 for bob@example.com; Mon, 02 Nov 2020 10:00:00 +0000").unwrap();
+    assert_eq!(rem.len(), 0);
+    let from = r.from.unwrap();
+    assert_eq!(from.domain, DomainPart::Address(AddressLiteral::IP("192.0.2.1".parse().unwrap())));
+    assert_eq!(from.comments, ["helo=client.example"]);
+    assert_eq!(r.by.unwrap(), ReceivedHost{domain: dp("mx.example.com"), comments: vec![]});
+    assert_eq!(r.with.as_deref(), Some("esmtpsa"));
+    assert_eq!(r.id.as_deref(), Some("1kXyZ-0001"));
+    assert_eq!(r.recipient.unwrap().to_string(), "bob@example.com");
+    assert_eq!(r.date, "Mon, 02 Nov 2020 10:00:00 +0000");
+}
+
+#[test]
+fn received_bare_ipv6() {
+    let (_, r) = received::<Intl>(b"by 2002:a05:6000:1::1 with SMTP id abc.1;\r\n Tue, 1 Jul 2003 10:52:37 -0700 (PDT)\r\n").unwrap();
+    assert!(r.from.is_none());
+    assert_eq!(r.by.unwrap().domain, DomainPart::Address(AddressLiteral::IP("2002:a05:6000:1::1".parse().unwrap())));
+    assert_eq!(r.with.as_deref(), Some("SMTP"));
+    assert_eq!(r.date, "Tue, 1 Jul 2003 10:52:37 -0700 (PDT)");
+}
+
+#[test]
+fn received_misc() {
+    let (_, r) = received::<Intl>(b"FROM a.example BY b.example VIA UUCP ID <x@y> ; date").unwrap();
+    assert_eq!(r.from.unwrap().domain, dp("a.example"));
+    assert_eq!(r.via.as_deref(), Some("UUCP"));
+    assert_eq!(r.id.as_deref(), Some("<x@y>"));
+
+    let (_, r) = received::<Intl>(b"; Tue, 1 Jul 2003 10:52:37 +0200").unwrap();
+    assert_eq!(r.from, None);
+    assert_eq!(r.date, "Tue, 1 Jul 2003 10:52:37 +0200");
+
+    assert!(received::<Intl>(b"from a.example by b.example").is_err());
+}
+
+#[test]
+fn validate_address_header_grammar() {
+    assert!(validate_address::<Intl>(b"bob@example.org"));
+    assert!(validate_address::<Intl>(b" bob.smith @ (home) example.org "));
+    assert!(validate_address::<Intl>(b"bob@[free form]"));
+    assert!(!validate_address::<Intl>(b"bob"));
+    assert!(!validate_address::<Intl>(b""));
+    assert!(!validate_address::<Intl>(b"bob@example.org>"));
+}
+
+#[test]
+fn validate_address_envelope_differs() {
+    use crate::rfc5321;
+
+    for addr in &[&b"bob (comment)@example.org"[..], b"bob@[free form]"] {
+        assert!(validate_address::<Intl>(addr));
+        assert!(!rfc5321::validate_address::<Intl>(addr));
+    }
+}
+
+#[test]
+fn return_path_header() {
+    use crate::rfc5321::ReversePath;
+
+    let (rem, path) = return_path::<Intl>(b"<bob@example.org>\r\n").unwrap();
+    assert!(rem.is_empty());
+    assert_eq!(path.to_string(), "<bob@example.org>");
+
+    let (_, path) = return_path::<Intl>(b" (loop) <@a.example:bob@example.org> (check)\r\n").unwrap();
+    assert_eq!(path.to_string(), "<@a.example:bob@example.org>");
+
+    assert_eq!(return_path::<Intl>(b"<>").unwrap().1, ReversePath::Null);
+    assert_eq!(return_path::<Intl>(b" < (none) > \r\n").unwrap().1, ReversePath::Null);
+    assert!(return_path::<Intl>(b"bob@example.org\r\n").is_err());
+    assert!(return_path::<Intl>(b"<bob (Bob) @example.org>\r\n").is_err());
+}
+
+#[test]
+fn domains_of_address_list() {
+    let (_, list) = cc::<Intl>(b"a@Example.org, Empty:;, Team: b@example.ORG, c@[IPv6:::1];, d@[IPv6:0::1], e@example.net\r\n").unwrap();
+    assert_eq!(unique_domains(&list), [dp("Example.org"), DomainPart::from_str("[IPv6:::1]").unwrap(), dp("example.net")]);
+
+    assert_eq!(unique_domains(&[]), []);
+    let (_, list) = to::<Intl>(b"Undisclosed recipients:;\r\n").unwrap();
+    assert_eq!(unique_domains(&list), []);
+}
+
+#[test]
+fn sender_cardinality() {
+    let (rem, mailbox) = sender_mailbox::<Intl>(b" \"Bob\" <bob@example.org> (comment)\r\n").unwrap();
+    assert_eq!(rem, b"");
+    assert_eq!(mailbox.dname, Some("Bob".into()));
+
+    // A group is only accepted by the RFC 6854 parser.
+    assert!(sender_mailbox::<Intl>(b"Team: bob@example.org;\r\n").is_err());
+    assert!(matches!(sender::<Intl>(b"Team: bob@example.org;\r\n").unwrap().1, Address::Group(_)));
+
+    assert!(sender_mailbox::<Intl>(b"bob@example.org, alice@example.org\r\n").is_err());
+    assert!(sender_mailbox::<Intl>(b"bob@example.org\r\nX-Other: value\r\n").is_err());
+    assert_eq!(sender_mailbox::<Intl>(b"bob@example.org").unwrap().0, b"");
+    assert!(exact!(&b"bob@example.org, alice@example.org\r\n"[..], sender::<Intl>).is_err());
+    assert_eq!(exact!(&b"bob@example.org, alice@example.org\r\n"[..], reply_to::<Intl>).unwrap().1.len(), 2);
+}
+
+fn nested_comment(depth: usize) -> String {
+    format!("{}x{}", "(".repeat(depth), ")".repeat(depth))
+}
+
+#[test]
+fn comment_depth_limit() {
+    let ok = nested_comment(MAX_COMMENT_DEPTH);
+    assert_eq!(cfws_comments::<Intl>(ok.as_bytes()).unwrap().1.len(), 1);
+    let input = format!("bob@example.org {}\r\n", ok);
+    assert_eq!(from::<Intl>(input.as_bytes()).unwrap().0, b"");
+
+    for depth in &[MAX_COMMENT_DEPTH + 1, 100_000] {
+        let deep = nested_comment(*depth);
+        match cfws_comments::<Intl>(deep.as_bytes()) {
+            Err(nom::Err::Failure(err)) => {
+                assert_eq!(err.to_string(), "comment nested too deeply");
+                assert_eq!(err.offset(deep.as_bytes()), MAX_COMMENT_DEPTH);
+            }
+            res => panic!("unexpected {:?}", res),
+        }
+        let input = format!("Bob {} <bob@example.org>\r\n", deep);
+        assert!(matches!(from::<Intl>(input.as_bytes()), Err(nom::Err::Failure(_))));
+    }
+
+    assert!(cfws_comments_with_depth::<Intl>(b"(x)", 0).is_err());
+    assert!(cfws_comments_with_depth::<Intl>(b" ", 0).is_ok());
+    assert!(cfws_comments_with_depth::<Intl>(nested_comment(200).as_bytes(), 200).is_ok());
+}
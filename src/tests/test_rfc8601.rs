@@ -0,0 +1,89 @@
+use crate::behaviour::Intl;
+use crate::rfc8601::*;
+
+fn parse(input: &[u8]) -> AuthenticationResults {
+    let (rem, ar) = authentication_results::<Intl>(input).unwrap();
+    assert_eq!(rem, b"");
+    ar
+}
+
+#[test]
+fn none() {
+    let ar = parse(b"example.org 1; none");
+    assert_eq!(ar.authserv_id, "example.org");
+    assert_eq!(ar.version, Some(1));
+    assert!(ar.results.is_empty());
+}
+
+#[test]
+fn multiple_results() {
+    let ar = parse(b"example.com;\r\n\
+                     \tspf=pass smtp.mailfrom=example.net;\r\n\
+                     \tdkim=pass (good signature) header.d=example.net header.s=sel1;\r\n\
+                     \tdmarc=fail reason=\"policy says so\" header.from=example.net\r\n");
+    assert_eq!(ar.authserv_id, "example.com");
+    assert_eq!(ar.version, None);
+    assert_eq!(ar.results.len(), 3);
+
+    let dkim = &ar.results[1];
+    assert_eq!(dkim.method, "dkim");
+    assert_eq!(dkim.result, "pass");
+    assert_eq!(dkim.properties.len(), 2);
+    assert_eq!(dkim.property("HEADER", "s"), Some("sel1"));
+
+    let dmarc = &ar.results[2];
+    assert_eq!(dmarc.result, "fail");
+    assert_eq!(dmarc.reason.as_deref(), Some("policy says so"));
+    assert_eq!(dmarc.property("header", "from"), Some("example.net"));
+}
+
+#[test]
+fn address_values() {
+    let ar = parse(b"mx.example.org; auth=pass smtp.auth=bob@example.org (authenticated); spf=neutral smtp.mailfrom=@example.org");
+    assert_eq!(ar.results[0].property("smtp", "auth"), Some("bob@example.org"));
+    assert_eq!(ar.results[1].property("smtp", "mailfrom"), Some("@example.org"));
+}
+
+#[test]
+fn comments_and_case() {
+    let ar = parse(b" (comment) \"mx example\" ; DKIM/1 = PASS (ok) Header . I = \"@example.org\" ;");
+    assert_eq!(ar.authserv_id, "mx example");
+    assert_eq!(ar.results[0].method, "dkim");
+    assert_eq!(ar.results[0].method_version, Some(1));
+    assert_eq!(ar.results[0].result, "pass");
+    assert_eq!(ar.results[0].properties[0].ptype, "header");
+    assert_eq!(ar.results[0].property("header", "i"), Some("@example.org"));
+}
+
+#[test]
+fn gmail() {
+    let ar = parse(b"mx.google.com;\r\n\
+                     \x20      dkim=pass header.i=@example.org header.s=20210112 header.b=LSE0y4/K;\r\n\
+                     \x20      spf=pass (google.com: domain of bob@example.org designates 209.85.220.41 as permitted sender) smtp.mailfrom=bob@example.org;\r\n\
+                     \x20      dmarc=pass (p=NONE sp=NONE dis=NONE) header.from=example.org\r\n");
+    assert_eq!(ar.authserv_id, "mx.google.com");
+    assert_eq!(ar.results.len(), 3);
+    assert_eq!(ar.results[0].property("header", "i"), Some("@example.org"));
+    assert_eq!(ar.results[0].property("header", "s"), Some("20210112"));
+    assert_eq!(ar.results[0].property("header", "b"), Some("LSE0y4/K"));
+    assert_eq!(ar.results[1].method, "spf");
+    assert_eq!(ar.results[1].property("smtp", "mailfrom"), Some("bob@example.org"));
+    assert_eq!(ar.results[2].method, "dmarc");
+    assert_eq!(ar.results[2].property("header", "from"), Some("example.org"));
+}
+
+#[test]
+fn base64_values() {
+    let ar = parse(b"mx.example.org; dkim=pass header.b=ab+c/D9= header.a=rsa-sha256");
+    assert_eq!(ar.results[0].property("header", "b"), Some("ab+c/D9="));
+    assert_eq!(ar.results[0].property("header", "a"), Some("rsa-sha256"));
+}
+
+#[test]
+fn invalid() {
+    assert!(authentication_results::<Intl>(b"; spf=pass").is_err());
+    assert!(authentication_results::<Intl>(b"example.org").is_err());
+    assert!(authentication_results::<Intl>(b"example.org; spf").is_err());
+    assert!(authentication_results::<Intl>(b"example.org; spf=pass smtp.mailfrom=a@b.c, dkim=pass").is_err());
+    assert!(authentication_results::<Intl>(b"example.org; spf=pass\r\nX-Other: 1\r\n").is_err());
+}
@@ -0,0 +1,74 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use proptest::prelude::*;
+
+use crate::types::*;
+
+fn atom() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9!#$%&'*+/=?^_`{|}~é日-]{1,8}"
+}
+
+fn dot_atom() -> impl Strategy<Value = DotAtom> {
+    prop::collection::vec(atom(), 1..4).prop_map(|atoms| DotAtom(atoms.join(".")))
+}
+
+fn quoted_string() -> impl Strategy<Value = QuotedString> {
+    "[ -~é日]{1,12}".prop_map(QuotedString)
+}
+
+fn local_part() -> impl Strategy<Value = LocalPart> {
+    prop_oneof![
+        dot_atom().prop_map(LocalPart::DotAtom),
+        quoted_string().prop_map(LocalPart::Quoted),
+    ]
+}
+
+fn domain() -> impl Strategy<Value = Domain> {
+    let label = "été|日本|[a-zA-Z0-9]{1,10}(-[a-zA-Z0-9]{1,5})?";
+    prop::collection::vec(label, 1..5).prop_map(|labels| Domain(labels.join(".")))
+}
+
+fn address_literal() -> impl Strategy<Value = AddressLiteral> {
+    let tag = "[a-zA-Z][a-zA-Z0-9]{0,5}".prop_filter("reserved tag", |t| !t.eq_ignore_ascii_case("IPv6"));
+
+    prop_oneof![
+        any::<Ipv4Addr>().prop_map(|ip| AddressLiteral::IP(ip.into())),
+        any::<Ipv6Addr>().prop_map(|ip| AddressLiteral::IP(ip.into())),
+        (any::<Ipv6Addr>(), "[a-zA-Z0-9._~-]{1,8}").prop_map(|(ip, zone)| AddressLiteral::ZonedIPv6(ip, zone)),
+        (tag, "[!-Z^-~]{1,10}").prop_map(|(tag, value)| AddressLiteral::Tagged(tag, value)),
+    ]
+}
+
+fn domain_part() -> impl Strategy<Value = DomainPart> {
+    prop_oneof![
+        domain().prop_map(DomainPart::Domain),
+        address_literal().prop_map(DomainPart::Address),
+    ]
+}
+
+fn mailbox() -> impl Strategy<Value = Mailbox> {
+    (local_part(), domain_part()).prop_map(|(lp, dp)| Mailbox(lp, dp))
+}
+
+proptest! {
+    #[test]
+    fn quoted_string_roundtrip(qs in quoted_string()) {
+        prop_assert_eq!(QuotedString::from_smtp(qs.quoted().as_bytes()).unwrap(), qs);
+    }
+
+    #[test]
+    fn local_part_roundtrip(lp in local_part()) {
+        prop_assert_eq!(LocalPart::from_smtp(lp.to_string().as_bytes()).unwrap(), lp);
+    }
+
+    #[test]
+    fn domain_part_roundtrip(dp in domain_part()) {
+        prop_assert_eq!(DomainPart::from_str(&dp.to_string()).unwrap(), dp);
+    }
+
+    #[test]
+    fn mailbox_roundtrip(m in mailbox()) {
+        prop_assert_eq!(Mailbox::from_str(&m.to_string()).unwrap(), m);
+    }
+}
@@ -0,0 +1,54 @@
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::rfc5321::*;
+use crate::types::*;
+
+fn roundtrip<T>(input: &str)
+    where T: Serialize + DeserializeOwned + FromStr + PartialEq + Debug,
+          T::Err: Debug,
+{
+    let value = T::from_str(input).unwrap();
+    let json = serde_json::to_string(&value).unwrap();
+
+    assert_eq!(json, serde_json::to_string(input).unwrap());
+    assert_eq!(serde_json::from_str::<T>(&json).unwrap(), value);
+}
+
+#[test]
+fn address_types() {
+    roundtrip::<Mailbox>("bob@example.org");
+    roundtrip::<Mailbox>("\"bob \\\"the\\\" smith\"@[IPv6:2001:db8::1]");
+    roundtrip::<LocalPart>("\"bob smith\"");
+    roundtrip::<DomainPart>("example.org");
+    roundtrip::<DomainPart>("[192.0.2.1]");
+    roundtrip::<AddressLiteral>("[x400:cn=bob]");
+}
+
+#[test]
+fn path_types() {
+    roundtrip::<Path>("<@a.example,@b.example:bob@example.org>");
+    roundtrip::<ForwardPath>("<postmaster>");
+    roundtrip::<ForwardPath>("<bob@example.org>");
+    roundtrip::<ReversePath>("<>");
+    roundtrip::<ReversePath>("<bob@example.org>");
+}
+
+#[test]
+fn param() {
+    let param = Param::new("BODY", Some("8BITMIME")).unwrap();
+    let json = serde_json::to_string(&param).unwrap();
+
+    assert_eq!(json, r#"["BODY","8BITMIME"]"#);
+    assert_eq!(serde_json::from_str::<Param>(&json).unwrap(), param);
+}
+
+#[test]
+fn invalid() {
+    assert!(serde_json::from_str::<Mailbox>(r#""bob@""#).is_err());
+    assert!(serde_json::from_str::<DomainPart>(r#""-example.org""#).is_err());
+    assert!(serde_json::from_str::<AddressLiteral>(r#""[999.1.1.1]""#).is_err());
+    assert!(serde_json::from_str::<Param>(r#"["BO DY",null]"#).is_err());
+}
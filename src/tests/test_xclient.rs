@@ -0,0 +1,27 @@
+use crate::rfc5321::get_value;
+use crate::xclient::*;
+
+#[test]
+fn attributes() {
+    let (_, attrs) = exact!(&b"XCLIENT NAME=mail.example.org ADDR=192.0.2.1 PORT=25 LOGIN=[UNAVAILABLE] HELO=[TEMPUNAVAIL]\r\n"[..], xclient_command).unwrap();
+    assert_eq!(attrs.len(), 5);
+    assert_eq!(get_value(&attrs, "NAME"), Some("mail.example.org"));
+    assert_eq!(get_value(&attrs, "ADDR"), Some("192.0.2.1"));
+    assert_eq!(get_value(&attrs, "PORT"), Some("25"));
+    assert_eq!(get_value(&attrs, "LOGIN"), Some(UNAVAILABLE));
+    assert_eq!(get_value(&attrs, "HELO"), Some(TEMPUNAVAIL));
+}
+
+#[test]
+fn xtext_value() {
+    let (_, attrs) = exact!(&b"xclient helo=a+20b\r\n"[..], xclient_command).unwrap();
+    assert_eq!(get_value(&attrs, "HELO"), Some("a+20b"));
+}
+
+#[test]
+fn invalid() {
+    assert!(xclient_command(b"XCLIENT\r\n").is_err());
+    assert!(xclient_command(b"XCLIENT \r\n").is_err());
+    assert!(xclient_command(b"XCLIENT NAME=x").is_err());
+    assert!(xclient_command(b"XFORWARD NAME=x\r\n").is_err());
+}
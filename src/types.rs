@@ -9,16 +9,19 @@
 //! Structs such as [`types::Domain`] and [`types::QuotedString`] are
 //! newtypes around [`String`] to make sure they can only be constructed
 //! from valid values.
-use std::fmt::{self, Display};
-
-use std::net::IpAddr;
-
-#[cfg(feature = "serde")]
-use serde::{Serialize, Deserialize};
+use core::convert::TryFrom;
+use core::fmt::{self, Display};
+use core::hash::{Hash, Hasher};
+#[cfg(feature = "std")]
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+#[cfg(not(feature = "std"))]
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use core::str::FromStr;
 
 use crate::behaviour::Intl;
 use crate::rfc5321 as smtp;
 use crate::rfc5322 as imf;
+use crate::prelude::*;
 use crate::util::*;
 
 /// A domain name such as used by DNS.
@@ -28,6 +31,75 @@ string_newtype!(Domain);
 impl Domain {
     nom_from_smtp!(smtp::domain::<Intl>);
     nom_from_imf!(imf::_domain::<Intl>);
+
+    /// Build a validated domain using the SMTP syntax.
+    ///
+    /// Internationalized domains are accepted.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::{Domain, DomainError};
+    ///
+    /// assert_eq!(Domain::new("example.org").unwrap().as_str(), "example.org");
+    /// assert_eq!(Domain::new("example..org"), Err(DomainError::Invalid));
+    /// assert_eq!(Domain::new(&"a".repeat(64)), Err(DomainError::TooLong));
+    /// ```
+    pub fn new(domain: &str) -> Result<Domain, DomainError> {
+        match Domain::from_smtp(domain.as_bytes()) {
+            Ok(d) => Ok(d),
+            Err(_) if smtp::_is_domain_syntax(domain.as_bytes()) => Err(DomainError::TooLong),
+            Err(_) => Err(DomainError::Invalid),
+        }
+    }
+
+    /// Returns this domain as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Convert this domain to its ASCII form.
+    ///
+    /// U-labels are converted to punycode A-labels and the result is
+    /// lowercased as per IDNA.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Domain;
+    ///
+    /// let domain = Domain::from_smtp("例え.JP".as_bytes()).unwrap();
+    /// assert_eq!(domain.to_ascii().unwrap().to_string(), "xn--r8jz45g.jp");
+    /// ```
+    #[cfg(feature = "idna")]
+    pub fn to_ascii(&self) -> Result<Domain, IdnaError> {
+        smtp::_idna_config().to_ascii(&self.0).map(Domain).map_err(|_| IdnaError)
+    }
+
+    /// Return this domain in lower case.
+    pub fn normalized(&self) -> Domain {
+        Domain(self.0.to_lowercase())
+    }
+
+    /// Compare two domains case-insensitively.
+    pub fn eq_ignore_case(&self, other: &Domain) -> bool {
+        self.0 == other.0 || self.normalized() == other.normalized()
+    }
+
+    /// Convert this domain to its Unicode form.
+    ///
+    /// A-labels are decoded to U-labels. The domain is returned
+    /// unchanged if it can not be decoded.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::Domain;
+    ///
+    /// let domain = Domain::from_smtp(b"xn--r8jz45g.jp").unwrap();
+    /// assert_eq!(domain.to_unicode().to_string(), "例え.jp");
+    /// ```
+    #[cfg(feature = "idna")]
+    pub fn to_unicode(&self) -> Domain {
+        match smtp::_idna_config().to_unicode(&self.0) {
+            (unicode, Ok(())) => Domain(unicode),
+            (_, Err(_)) => self.clone(),
+        }
+    }
 }
 
 /// The local part of an address preceding the `"@"` in an email address.
@@ -38,6 +110,9 @@ pub enum LocalPart {
     /// Local part that may contain spaces and special characters.
     Quoted(QuotedString),
 }
+nom_fromstr!(LocalPart, smtp::local_part::<Intl>);
+serde_string!(LocalPart);
+
 impl LocalPart {
     nom_from_smtp!(smtp::local_part::<Intl>);
     nom_from_imf!(imf::local_part::<Intl>);
@@ -55,6 +130,37 @@ impl LocalPart {
             LocalPart::DotAtom(_) => (),
         }
     }
+
+    /// Consuming version of [`LocalPart::smtp_try_unquote`].
+    /// # Examples
+    /// ```
+    /// use std::str::FromStr;
+    /// use rustyknife::types::LocalPart;
+    ///
+    /// let simple = LocalPart::from_str("\"john.doe\"").unwrap().simplify();
+    /// assert_eq!(simple, LocalPart::from_str("john.doe").unwrap());
+    ///
+    /// let quoted = LocalPart::from_str("\"john..doe\"").unwrap();
+    /// assert_eq!(quoted.clone().simplify(), quoted);
+    /// ```
+    pub fn simplify(mut self) -> LocalPart {
+        self.smtp_try_unquote();
+        self
+    }
+
+    /// Returns true if this local part contains only ASCII.
+    pub fn is_ascii(&self) -> bool {
+        match self {
+            LocalPart::DotAtom(a) => a.0.is_ascii(),
+            LocalPart::Quoted(q) => q.0.is_ascii(),
+        }
+    }
+}
+
+impl From<LocalPart> for String {
+    fn from(value: LocalPart) -> String {
+        value.to_string()
+    }
 }
 
 impl From<QuotedString> for LocalPart {
@@ -141,9 +247,102 @@ pub enum DomainPart {
     Address(AddressLiteral),
 }
 
+nom_fromstr!(DomainPart, smtp::_domain_part::<Intl>);
+serde_string!(DomainPart);
+
 impl DomainPart {
     nom_from_smtp!(smtp::_domain_part::<Intl>);
     nom_from_imf!(imf::domain::<Intl>);
+
+    /// Convert the domain to its ASCII form.
+    ///
+    /// See [`Domain::to_ascii`]. Address literals are returned unchanged.
+    #[cfg(feature = "idna")]
+    pub fn to_ascii(&self) -> Result<DomainPart, IdnaError> {
+        match self {
+            DomainPart::Domain(d) => d.to_ascii().map(DomainPart::Domain),
+            DomainPart::Address(_) => Ok(self.clone()),
+        }
+    }
+
+    /// Convert the domain to its Unicode form.
+    ///
+    /// See [`Domain::to_unicode`]. Address literals are returned unchanged.
+    #[cfg(feature = "idna")]
+    pub fn to_unicode(&self) -> DomainPart {
+        match self {
+            DomainPart::Domain(d) => DomainPart::Domain(d.to_unicode()),
+            DomainPart::Address(_) => self.clone(),
+        }
+    }
+
+    /// Lowercase DNS domains for comparison and storage.
+    ///
+    /// Address literals are returned unchanged. Use this to build
+    /// keys when the derived [`PartialEq`] and [`Hash`]
+    /// implementations must not distinguish between cases.
+    /// # Examples
+    /// ```
+    /// use std::str::FromStr;
+    /// use rustyknife::types::DomainPart;
+    ///
+    /// let domain = DomainPart::from_str("Example.ORG").unwrap();
+    /// assert_eq!(domain.normalized().to_string(), "example.org");
+    /// assert!(domain.eq_ignore_case(&DomainPart::from_str("example.org").unwrap()));
+    /// ```
+    pub fn normalized(&self) -> DomainPart {
+        match self {
+            DomainPart::Domain(d) => DomainPart::Domain(d.normalized()),
+            DomainPart::Address(_) => self.clone(),
+        }
+    }
+
+    /// Returns true if this domain part contains only ASCII.
+    ///
+    /// Internationalized domains in A-label form are ASCII. Address
+    /// literals generated by the [crate::rfc5322] module may not be.
+    pub fn is_ascii(&self) -> bool {
+        match self {
+            DomainPart::Domain(d) => d.0.is_ascii(),
+            DomainPart::Address(a) => a.to_string().is_ascii(),
+        }
+    }
+
+    /// Returns true if this is a DNS domain.
+    pub fn is_domain(&self) -> bool {
+        matches!(self, DomainPart::Domain(_))
+    }
+
+    /// Returns true if this is an address literal.
+    pub fn is_address_literal(&self) -> bool {
+        matches!(self, DomainPart::Address(_))
+    }
+
+    /// Returns the IP address of an IP address literal.
+    ///
+    /// See [`AddressLiteral::as_ip`].
+    /// # Examples
+    /// ```
+    /// use std::str::FromStr;
+    /// use rustyknife::types::DomainPart;
+    ///
+    /// assert_eq!(DomainPart::from_str("[192.0.2.1]").unwrap().as_ip(), Some("192.0.2.1".parse().unwrap()));
+    /// assert_eq!(DomainPart::from_str("example.org").unwrap().as_ip(), None);
+    /// ```
+    pub fn as_ip(&self) -> Option<IpAddr> {
+        match self {
+            DomainPart::Domain(_) => None,
+            DomainPart::Address(a) => a.as_ip(),
+        }
+    }
+
+    /// Compare two domain parts, ignoring the case of DNS domains.
+    pub fn eq_ignore_case(&self, other: &DomainPart) -> bool {
+        match (self, other) {
+            (DomainPart::Domain(a), DomainPart::Domain(b)) => a.eq_ignore_case(b),
+            _ => self == other,
+        }
+    }
 }
 
 impl From<Domain> for DomainPart {
@@ -167,10 +366,23 @@ impl Display for DomainPart {
     }
 }
 
+impl From<DomainPart> for String {
+    fn from(value: DomainPart) -> String {
+        value.to_string()
+    }
+}
+
 /// A network address literal.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum AddressLiteral {
     /// An IPv4 or IPv6 address literal.
+    ///
+    /// IPv4 octets with leading zeros such as `"[192.0.2.010]"` are
+    /// rejected since they are ambiguous.
+    ///
+    /// IPv6 literals with an embedded IPv4 address such as
+    /// `"[IPv6:::ffff:192.0.2.1]"` are kept as [`IpAddr::V6`]. Use
+    /// [`Ipv6Addr::to_ipv4_mapped`] to compare them with IPv4 literals.
     /// # Examples
     /// ```
     /// use std::convert::TryFrom;
@@ -184,6 +396,16 @@ pub enum AddressLiteral {
     /// assert_eq!(ipv6, AddressLiteral::IP("2001:db8::1".parse().unwrap()));
     /// ```
     IP(IpAddr),
+    /// An IPv6 address literal with a zone identifier.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::AddressLiteral;
+    ///
+    /// let lit = AddressLiteral::from_smtp(b"[IPv6:fe80::1%eth0]").unwrap();
+    /// assert_eq!(lit, AddressLiteral::ZonedIPv6("fe80::1".parse().unwrap(), "eth0".into()));
+    /// assert_eq!(lit.to_string(), "[IPv6:fe80::1%eth0]");
+    /// ```
+    ZonedIPv6(Ipv6Addr, String),
     /// An address literal in the form tag:value.
     /// # Examples
     /// ```
@@ -202,32 +424,195 @@ impl AddressLiteral {
     /// Try to upgrade a [`AddressLiteral::FreeForm`] to the more formal subtypes.
     /// # Examples
     /// ```
-    /// use rustyknife::types::AddressLiteral;
+    /// use rustyknife::types::{AddressLiteral, AddressLiteralErrorKind};
     ///
     /// let valid = AddressLiteral::FreeForm("192.0.2.1".into());
     /// let invalid = AddressLiteral::FreeForm("somewhere".into());
     ///
     /// assert_eq!(valid.upgrade(), Ok(AddressLiteral::IP("192.0.2.1".parse().unwrap())));
-    /// assert_eq!(invalid.upgrade(), Err(()));
+    /// assert_eq!(invalid.upgrade().unwrap_err().kind, AddressLiteralErrorKind::BadTag);
     /// ```
-    pub fn upgrade(&self) -> Result<Self, ()> {
+    pub fn upgrade(&self) -> Result<Self, AddressLiteralError> {
         if let AddressLiteral::FreeForm(s) = self {
-            let (rem, parsed) = smtp::_inner_address_literal(s.as_bytes()).map_err(|_| ())?;
-
-            if rem.is_empty() {
-                Ok(parsed)
-            } else {
-                Err(())
-            }
+            smtp::_checked_inner_address_literal(s.as_bytes())
         } else {
-            Err(())
+            Err(AddressLiteralError{kind: AddressLiteralErrorKind::NotFreeForm, offset: 0})
         }
     }
 
     nom_from_smtp!(smtp::address_literal);
     nom_from_imf!(imf::domain_literal::<Intl>);
+
+    /// Returns the IP address of an IP literal.
+    ///
+    /// The zone of [`AddressLiteral::ZonedIPv6`] literals is dropped.
+    pub fn as_ip(&self) -> Option<IpAddr> {
+        match self {
+            AddressLiteral::IP(ip) => Some(*ip),
+            AddressLiteral::ZonedIPv6(ip, _) => Some((*ip).into()),
+            AddressLiteral::Tagged(_, _) | AddressLiteral::FreeForm(_) => None,
+        }
+    }
+
+    /// Returns the canonical form of this literal.
+    ///
+    /// IP literals already display in canonical form since they are
+    /// stored parsed: IPv6 addresses are compressed and lowercased and
+    /// always use the `"IPv6:"` tag. Tags of
+    /// [`AddressLiteral::Tagged`] literals are lowercased and
+    /// [`AddressLiteral::FreeForm`] literals are upgraded when
+    /// possible. Values of tagged literals are kept as is.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::AddressLiteral;
+    ///
+    /// let ipv6 = AddressLiteral::from_smtp(b"[ipv6:2001:DB8:0:0:0:0:0:1]").unwrap();
+    /// assert_eq!(ipv6.canonical().to_string(), "[IPv6:2001:db8::1]");
+    ///
+    /// let tagged = AddressLiteral::from_smtp(b"[X400:cn=Bob]").unwrap();
+    /// assert_eq!(tagged.canonical().to_string(), "[x400:cn=Bob]");
+    ///
+    /// let free = AddressLiteral::FreeForm("192.0.2.1".into());
+    /// assert_eq!(free.canonical(), AddressLiteral::IP("192.0.2.1".parse().unwrap()));
+    /// ```
+    pub fn canonical(&self) -> AddressLiteral {
+        match self {
+            AddressLiteral::Tagged(tag, value) => AddressLiteral::Tagged(tag.to_ascii_lowercase(), value.clone()),
+            AddressLiteral::FreeForm(_) => self.upgrade().map_or_else(|_| self.clone(), |l| l.canonical()),
+            _ => self.clone(),
+        }
+    }
+
+    /// Returns true if the literal uses a tag registered with IANA.
+    ///
+    /// IP literals are always registered. [`AddressLiteral::Tagged`]
+    /// is checked against [`smtp::ADDRESS_LITERAL_TAGS`] and
+    /// [`AddressLiteral::FreeForm`] is never registered.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::AddressLiteral;
+    ///
+    /// assert!(AddressLiteral::from_smtp(b"[IPv6:::1]").unwrap().is_registered());
+    /// assert!(!AddressLiteral::from_smtp(b"[x400:cn=bob]").unwrap().is_registered());
+    /// ```
+    pub fn is_registered(&self) -> bool {
+        match self {
+            AddressLiteral::IP(_) | AddressLiteral::ZonedIPv6(_, _) => true,
+            AddressLiteral::Tagged(tag, _) => smtp::ADDRESS_LITERAL_TAGS.iter().any(|r| tag.eq_ignore_ascii_case(r)),
+            AddressLiteral::FreeForm(_) => false,
+        }
+    }
 }
 
+/// Parse using SMTP syntax, including the enclosing brackets.
+///
+/// # Examples
+/// ```
+/// use rustyknife::types::{AddressLiteral, AddressLiteralErrorKind};
+///
+/// let ip: AddressLiteral = "[192.0.2.1]".parse().unwrap();
+/// assert_eq!(ip, AddressLiteral::IP("192.0.2.1".parse().unwrap()));
+///
+/// let err = "[192.0.999.1]".parse::<AddressLiteral>().unwrap_err();
+/// assert_eq!(err.kind, AddressLiteralErrorKind::BadIpv4Octet);
+/// assert_eq!(err.offset, 7);
+/// ```
+impl FromStr for AddressLiteral {
+    type Err = AddressLiteralError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let missing = |offset| AddressLiteralError{kind: AddressLiteralErrorKind::MissingBracket, offset};
+
+        let inner = s.strip_prefix('[').ok_or_else(|| missing(0))?;
+        let inner = inner.strip_suffix(']').ok_or_else(|| missing(s.len()))?;
+
+        smtp::_checked_inner_address_literal(inner.as_bytes())
+            .map_err(|e| AddressLiteralError{offset: e.offset + 1, ..e})
+    }
+}
+
+impl<'a> TryFrom<&'a str> for AddressLiteral {
+    type Error = AddressLiteralError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<IpAddr> for AddressLiteral {
+    fn from(value: IpAddr) -> AddressLiteral {
+        AddressLiteral::IP(value)
+    }
+}
+
+impl From<Ipv4Addr> for AddressLiteral {
+    fn from(value: Ipv4Addr) -> AddressLiteral {
+        AddressLiteral::IP(value.into())
+    }
+}
+
+impl From<Ipv6Addr> for AddressLiteral {
+    fn from(value: Ipv6Addr) -> AddressLiteral {
+        AddressLiteral::IP(value.into())
+    }
+}
+
+impl From<AddressLiteral> for String {
+    fn from(value: AddressLiteral) -> String {
+        value.to_string()
+    }
+}
+
+serde_string!(AddressLiteral);
+
+/// The reason an address literal is invalid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AddressLiteralErrorKind {
+    /// The opening or closing bracket is missing.
+    MissingBracket,
+    /// An IPv4 octet is missing or invalid.
+    BadIpv4Octet,
+    /// The address following the `"IPv6:"` tag is invalid.
+    BadIpv6,
+    /// The tag of a `tag:value` literal is empty.
+    EmptyTag,
+    /// The tag of a `tag:value` literal is invalid or not followed by a colon.
+    BadTag,
+    /// The value of a `tag:value` literal is empty or invalid.
+    BadValue,
+    /// Unexpected data follows an otherwise valid literal.
+    TrailingData,
+    /// Only [`AddressLiteral::FreeForm`] literals can be upgraded.
+    NotFreeForm,
+}
+
+/// Error returned when an address literal fails to parse.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddressLiteralError {
+    /// The reason parsing failed.
+    pub kind: AddressLiteralErrorKind,
+    /// Offset of the byte where parsing diverged.
+    pub offset: usize,
+}
+
+impl Display for AddressLiteralError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self.kind {
+            AddressLiteralErrorKind::MissingBracket => "missing bracket",
+            AddressLiteralErrorKind::BadIpv4Octet => "invalid IPv4 octet",
+            AddressLiteralErrorKind::BadIpv6 => "invalid IPv6 address",
+            AddressLiteralErrorKind::EmptyTag => "empty tag",
+            AddressLiteralErrorKind::BadTag => "invalid tag",
+            AddressLiteralErrorKind::BadValue => "invalid value",
+            AddressLiteralErrorKind::TrailingData => "trailing data",
+            AddressLiteralErrorKind::NotFreeForm => "not a free form literal",
+        };
+        write!(f, "{} at offset {}", reason, self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AddressLiteralError {}
 
 impl Display for AddressLiteral {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -236,6 +621,7 @@ impl Display for AddressLiteral {
                 IpAddr::V4(ipv4) => write!(f, "[{}]", ipv4),
                 IpAddr::V6(ipv6) => write!(f, "[IPv6:{}]", ipv6),
             },
+            AddressLiteral::ZonedIPv6(ip, zone) => write!(f, "[IPv6:{}%{}]", ip, zone),
             AddressLiteral::Tagged(tag, value) => write!(f, "[{}:{}]", tag, value),
             AddressLiteral::FreeForm(value) => write!(f, "[{}]", value),
         }
@@ -243,12 +629,49 @@ impl Display for AddressLiteral {
 }
 
 /// A valid email address.
+///
+/// Comparison and hashing are exact, including the case of the
+/// domain. Use [`Mailbox::matches`] to compare addresses the way a
+/// mail server would.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "serde", serde(try_from="&str", into="String"))]
 pub struct Mailbox(pub(crate) LocalPart, pub(crate) DomainPart);
+serde_string!(Mailbox);
 
 impl Mailbox {
+    /// Build a validated mailbox from a local part and a domain.
+    ///
+    /// `local` is the unquoted local part. It is quoted automatically
+    /// if it is not a valid dot-atom. `domain` may be a domain name or
+    /// an address literal.
+    /// # Examples
+    /// ```
+    /// use rustyknife::types::{Mailbox, MailboxError};
+    ///
+    /// assert_eq!(Mailbox::new("bob", "example.org").unwrap().to_string(), "bob@example.org");
+    /// assert_eq!(Mailbox::new("bob smith", "[192.0.2.1]").unwrap().to_string(), "\"bob smith\"@[192.0.2.1]");
+    /// assert_eq!(Mailbox::new("bob", "example..org"), Err(MailboxError::InvalidDomain));
+    /// ```
+    pub fn new(local: &str, domain: &str) -> Result<Mailbox, MailboxError> {
+        if local.is_empty() {
+            return Err(MailboxError::EmptyLocalPart);
+        }
+
+        let local = match exact!(local.as_bytes(), smtp::dot_string::<Intl>) {
+            Ok((_, da)) => LocalPart::DotAtom(da),
+            Err(_) => {
+                let quoted = QuotedString(local.into()).quoted();
+                match exact!(quoted.as_bytes(), smtp::quoted_string::<Intl>) {
+                    Ok((_, qs)) => LocalPart::Quoted(qs),
+                    Err(_) => return Err(MailboxError::InvalidLocalPart),
+                }
+            }
+        };
+        let (_, domain) = exact!(domain.as_bytes(), smtp::_domain_part::<Intl>)
+            .map_err(|_| MailboxError::InvalidDomain)?;
+
+        Ok(Mailbox(local, domain))
+    }
+
     /// Return the local part to the left of the "@".
     pub fn local_part(&self) -> &LocalPart {
         &self.0
@@ -259,6 +682,120 @@ impl Mailbox {
         &self.1
     }
 
+    /// Render the domain part to the right of the "@".
+    ///
+    /// Address literals keep their brackets.
+    /// # Examples
+    /// ```
+    /// use std::str::FromStr;
+    /// use rustyknife::types::Mailbox;
+    ///
+    /// let mailbox = Mailbox::from_str("bob@[192.0.2.1]").unwrap();
+    /// assert_eq!(mailbox.domain_str(), "[192.0.2.1]");
+    /// ```
+    pub fn domain_str(&self) -> String {
+        self.1.to_string()
+    }
+
+    /// Render this mailbox as an SMTP path in angle brackets.
+    /// # Examples
+    /// ```
+    /// use std::str::FromStr;
+    /// use rustyknife::types::Mailbox;
+    ///
+    /// let mailbox = Mailbox::from_str("bob@example.org").unwrap();
+    /// assert_eq!(mailbox.to_smtp_path(), "<bob@example.org>");
+    /// ```
+    pub fn to_smtp_path(&self) -> String {
+        format!("<{}>", self)
+    }
+
+    /// Returns true if both the local part and the domain contain only
+    /// ASCII, so that the mailbox can be used without SMTPUTF8.
+    /// # Examples
+    /// ```
+    /// use std::str::FromStr;
+    /// use rustyknife::types::Mailbox;
+    ///
+    /// assert!(Mailbox::from_str("bob@example.org").unwrap().is_ascii());
+    /// assert!(!Mailbox::from_str("josé@example.org").unwrap().is_ascii());
+    /// assert!(!Mailbox::from_str("bob@été.example").unwrap().is_ascii());
+    /// ```
+    pub fn is_ascii(&self) -> bool {
+        self.0.is_ascii() && self.1.is_ascii()
+    }
+
+    /// Downgrade this mailbox for use without SMTPUTF8.
+    ///
+    /// The domain is converted to A-labels as per [RFC 6530]. A local
+    /// part containing UTF-8 can not be downgraded.
+    /// # Examples
+    /// ```
+    /// use std::str::FromStr;
+    /// use rustyknife::types::{DowngradeError, Mailbox};
+    ///
+    /// let mailbox = Mailbox::from_str("bob@例え.JP").unwrap();
+    /// assert_eq!(mailbox.downgrade().unwrap().to_string(), "bob@xn--r8jz45g.jp");
+    ///
+    /// let mailbox = Mailbox::from_str("josé@example.org").unwrap();
+    /// assert_eq!(mailbox.downgrade(), Err(DowngradeError::NonAsciiLocalPart));
+    /// ```
+    ///
+    /// [RFC 6530]: https://tools.ietf.org/html/rfc6530
+    #[cfg(feature = "idna")]
+    pub fn downgrade(&self) -> Result<Mailbox, DowngradeError> {
+        if !self.0.is_ascii() {
+            return Err(DowngradeError::NonAsciiLocalPart);
+        }
+        let domain = self.1.to_ascii().map_err(|_| DowngradeError::InvalidDomain)?;
+        if !domain.is_ascii() {
+            return Err(DowngradeError::InvalidDomain);
+        }
+
+        Ok(Mailbox(self.0.clone(), domain))
+    }
+
+    /// Returns true if the domain part is an address literal.
+    pub fn domain_is_literal(&self) -> bool {
+        self.1.is_address_literal()
+    }
+
+    /// Compare two mailboxes as configured by `options`.
+    ///
+    /// Domains are always compared case-insensitively while address
+    /// literals must be identical.
+    /// # Examples
+    /// ```
+    /// use std::str::FromStr;
+    /// use rustyknife::types::{Mailbox, MatchOptions};
+    ///
+    /// let a = Mailbox::from_str("Bob@Example.ORG").unwrap();
+    /// let b = Mailbox::from_str("\"Bob\"@example.org").unwrap();
+    /// let c = Mailbox::from_str("bob@example.org").unwrap();
+    ///
+    /// assert!(a.matches(&b, MatchOptions::default()));
+    /// assert!(!a.matches(&c, MatchOptions::default()));
+    /// assert!(a.matches(&c, MatchOptions{case_sensitive_local: false, ..Default::default()}));
+    /// ```
+    pub fn matches(&self, other: &Mailbox, options: MatchOptions) -> bool {
+        // Quoting only matters when not comparing the unquoted content.
+        fn local(lp: &LocalPart, unquote: bool) -> (bool, &str) {
+            match lp {
+                LocalPart::DotAtom(a) => (false, &a.0),
+                LocalPart::Quoted(q) => (!unquote, &q.0),
+            }
+        }
+        let ((a_quoted, a), (b_quoted, b)) = (local(&self.0, options.unquote), local(&other.0, options.unquote));
+
+        let local_matches = a_quoted == b_quoted && if options.case_sensitive_local {
+            a == b
+        } else {
+            a == b || a.to_lowercase() == b.to_lowercase()
+        };
+
+        local_matches && self.1.eq_ignore_case(&other.1)
+    }
+
     /// Split the mailbox apart.
     pub fn into_parts(self) -> (LocalPart, DomainPart) {
         (self.0, self.1)
@@ -280,6 +817,189 @@ impl Mailbox {
     nom_from_imf!(imf::addr_spec::<Intl>);
 }
 
+/// Error returned by [`Mailbox::new`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MailboxError {
+    /// The local part contains characters that can not be quoted.
+    InvalidLocalPart,
+    /// The local part is empty.
+    EmptyLocalPart,
+    /// The domain is neither a valid domain name nor an address literal.
+    InvalidDomain,
+}
+
+impl Display for MailboxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            MailboxError::InvalidLocalPart => "invalid local part",
+            MailboxError::EmptyLocalPart => "empty local part",
+            MailboxError::InvalidDomain => "invalid domain",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MailboxError {}
+
+/// Error returned by [`Domain::to_ascii`] and [`DomainPart::to_ascii`].
+///
+/// The domain is not a valid internationalized domain name as per
+/// [IDNA].
+///
+/// [IDNA]: https://www.unicode.org/reports/tr46/
+#[cfg(feature = "idna")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IdnaError;
+
+#[cfg(feature = "idna")]
+impl Display for IdnaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid internationalized domain")
+    }
+}
+
+#[cfg(all(feature = "std", feature = "idna"))]
+impl std::error::Error for IdnaError {}
+
+/// Error returned by [`Mailbox::downgrade`].
+#[cfg(feature = "idna")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DowngradeError {
+    /// The local part contains UTF-8.
+    NonAsciiLocalPart,
+    /// The domain can not be converted to ASCII.
+    InvalidDomain,
+}
+
+#[cfg(feature = "idna")]
+impl Display for DowngradeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DowngradeError::NonAsciiLocalPart => "non-ASCII local part",
+            DowngradeError::InvalidDomain => "domain can not be converted to ASCII",
+        })
+    }
+}
+
+#[cfg(all(feature = "std", feature = "idna"))]
+impl std::error::Error for DowngradeError {}
+
+/// Error returned by [`Domain::new`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DomainError {
+    /// The domain is not syntactically valid.
+    Invalid,
+    /// The domain or one of its labels is too long.
+    TooLong,
+}
+
+impl Display for DomainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DomainError::Invalid => "invalid domain",
+            DomainError::TooLong => "domain too long",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DomainError {}
+
+/// Options for [`Mailbox::matches`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MatchOptions {
+    /// Compare local parts case-sensitively as required by [RFC 5321].
+    /// Most servers treat them case-insensitively in practice.
+    ///
+    /// [RFC 5321]: https://tools.ietf.org/html/rfc5321#section-2.4
+    pub case_sensitive_local: bool,
+    /// Compare the content of quoted local parts so that `"bob"` and
+    /// `bob` are equal. Otherwise quoted and unquoted local parts
+    /// never match.
+    pub unquote: bool,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        MatchOptions {
+            case_sensitive_local: true,
+            unquote: true,
+        }
+    }
+}
+
+/// A mailbox that keeps its original form but compares normalized.
+///
+/// Equality and hashing ignore the case of the domain and needless
+/// quoting of the local part, as [`Mailbox::matches`] does with the
+/// default [`MatchOptions`]. The case of the local part is preserved.
+/// # Examples
+/// ```
+/// use std::collections::HashSet;
+/// use std::str::FromStr;
+/// use rustyknife::types::{Mailbox, NormalizedMailbox};
+///
+/// let a = NormalizedMailbox::from(Mailbox::from_str("Bob.Smith@Example.Org").unwrap());
+/// let b = NormalizedMailbox::from(Mailbox::from_str("\"Bob.Smith\"@example.org").unwrap());
+///
+/// assert_eq!(a, b);
+/// assert_eq!(a.original().to_string(), "Bob.Smith@Example.Org");
+/// assert_eq!(a.normalized().to_string(), "Bob.Smith@example.org");
+///
+/// let set: HashSet<_> = vec![a, b].into_iter().collect();
+/// assert_eq!(set.len(), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct NormalizedMailbox {
+    original: Mailbox,
+    normalized: Mailbox,
+}
+
+impl NormalizedMailbox {
+    /// Return the mailbox as it was given.
+    pub fn original(&self) -> &Mailbox {
+        &self.original
+    }
+
+    /// Return the normalized mailbox used for comparison.
+    pub fn normalized(&self) -> &Mailbox {
+        &self.normalized
+    }
+
+    /// Return the mailbox as it was given.
+    pub fn into_original(self) -> Mailbox {
+        self.original
+    }
+}
+
+impl From<Mailbox> for NormalizedMailbox {
+    fn from(original: Mailbox) -> Self {
+        let normalized = Mailbox(original.0.clone().simplify(), original.1.normalized());
+
+        NormalizedMailbox{original, normalized}
+    }
+}
+
+impl PartialEq for NormalizedMailbox {
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized == other.normalized
+    }
+}
+
+impl Eq for NormalizedMailbox {}
+
+impl Hash for NormalizedMailbox {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.normalized.hash(state)
+    }
+}
+
+impl Display for NormalizedMailbox {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.original.fmt(f)
+    }
+}
+
 // FIXME: is type unification a good thing ?
 nom_fromstr!(Mailbox, smtp::mailbox::<Intl>);
 
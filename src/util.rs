@@ -1,16 +1,96 @@
-use std::borrow::Cow;
-use std::str;
-
-use encoding::{Encoding, DecoderTrap};
-use encoding::all::ASCII;
+use alloc::borrow::Cow;
+use core::fmt::{self, Display};
+use core::str;
 
 use nom::IResult;
+use nom::error::{ContextError, ErrorKind, FromExternalError, ParseError};
 use nom::bytes::complete::take;
 use nom::combinator::{map, recognize, verify};
 use nom::multi::{fold_many0, fold_many1};
-// Change this to something else that implements ParseError to get a
-// different error type out of nom.
-pub(crate) type NomError<'a> = ();
+
+use crate::prelude::*;
+/// Error returned by the parsers.
+///
+/// Points at the input where parsing failed. When several
+/// alternatives fail, the one that got the furthest is kept.
+/// The context describes the innermost construct that was expected.
+/// # Examples
+/// ```
+/// use rustyknife::behaviour::Intl;
+/// use rustyknife::rfc5321::mail_command;
+///
+/// let input = b"MAIL FROM:<bob.example.org>\r\n";
+/// let err = match mail_command::<Intl>(input) {
+///     Err(nom::Err::Error(err)) => err,
+///     _ => unreachable!(),
+/// };
+///
+/// assert_eq!(err.to_string(), "expected '@'");
+/// assert_eq!(err.offset(input), 26);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NomError<'a> {
+    /// Remaining input where the error occured.
+    pub input: &'a [u8],
+    /// Kind of parser that failed.
+    pub kind: ErrorKind,
+    /// Description of what was expected, if known.
+    pub context: Option<&'static str>,
+}
+
+impl<'a> NomError<'a> {
+    /// Offset of the error in the `input` that was given to the parser.
+    pub fn offset(&self, input: &[u8]) -> usize {
+        input.len().saturating_sub(self.input.len())
+    }
+
+    /// Short description of the failure, as shown by `Display`.
+    pub fn reason(&self) -> &'static str {
+        match (self.context, self.kind) {
+            (Some(ctx), _) => ctx,
+            (None, ErrorKind::Eof) => "trailing data",
+            (None, _) => "invalid syntax",
+        }
+    }
+}
+
+impl<'a> ParseError<&'a [u8]> for NomError<'a> {
+    fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
+        NomError{input, kind, context: None}
+    }
+
+    fn append(_: &'a [u8], _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn or(self, other: Self) -> Self {
+        if other.input.len() < self.input.len() || (other.input.len() == self.input.len() && self.context.is_none()) {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+// The innermost context is the most specific.
+impl<'a> ContextError<&'a [u8]> for NomError<'a> {
+    fn add_context(_: &'a [u8], ctx: &'static str, mut other: Self) -> Self {
+        other.context.get_or_insert(ctx);
+        other
+    }
+}
+
+impl<'a, E> FromExternalError<&'a [u8], E> for NomError<'a> {
+    fn from_external_error(input: &'a [u8], kind: ErrorKind, _: E) -> Self {
+        Self::from_error_kind(input, kind)
+    }
+}
+
+impl<'a> Display for NomError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.reason())
+    }
+}
 
 /// Shortcut type for taking in bytes and spitting out a success or NomError.
 pub type NomResult<'a, O, E=NomError<'a>> = IResult<&'a [u8], O, E>;
@@ -24,27 +104,80 @@ pub fn ascii_to_string<'a, T: Into<Cow<'a, [u8]>>>(i: T) -> Cow<'a, str> {
             Cow::Owned(i) => Cow::Owned(String::from_utf8(i).unwrap()),
         }
     } else {
-        Cow::Owned(ASCII.decode(&i, DecoderTrap::Replace).unwrap())
+        Cow::Owned(i.iter().map(|&c| if c.is_ascii() { char::from(c) } else { '\u{fffd}' }).collect())
     }
 }
 
+// Charset decoding of RFC 2047 encoded words and RFC 2231 values.
+#[cfg(feature = "std")]
+mod charset {
+    use encoding::{DecoderTrap, EncodingRef};
+    use encoding::all::ASCII;
+    use encoding::label::encoding_from_whatwg_label;
+
+    pub(crate) type Charset = EncodingRef;
+
+    pub(crate) const ASCII_CHARSET: Charset = ASCII;
+
+    pub(crate) fn charset_from_label(label: &str) -> Option<Charset> {
+        encoding_from_whatwg_label(label)
+    }
+
+    pub(crate) fn charset_decode(charset: Charset, bytes: &[u8]) -> String {
+        charset.decode(bytes, DecoderTrap::Replace).unwrap()
+    }
+}
+
+// Without std only ASCII and UTF-8 are known. Text in other charsets
+// is decoded as ASCII.
+#[cfg(not(feature = "std"))]
+mod charset {
+    use super::ascii_to_string;
+    use crate::prelude::*;
+
+    #[derive(Clone, Copy)]
+    pub(crate) enum Charset {
+        Ascii,
+        Utf8,
+    }
+
+    pub(crate) const ASCII_CHARSET: Charset = Charset::Ascii;
+
+    pub(crate) fn charset_from_label(label: &str) -> Option<Charset> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "ascii" | "us-ascii" => Some(Charset::Ascii),
+            "utf-8" | "utf8" | "unicode-1-1-utf-8" => Some(Charset::Utf8),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn charset_decode(charset: Charset, bytes: &[u8]) -> String {
+        match charset {
+            Charset::Ascii => ascii_to_string(bytes).into_owned(),
+            Charset::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+        }
+    }
+}
+
+pub(crate) use self::charset::*;
+
 macro_rules! nom_fromstr {
     ( $type:ty, $func:path ) => {
-        impl std::str::FromStr for $type {
-            type Err = ();
+        impl core::str::FromStr for $type {
+            type Err = crate::rfc5321::SMTPParseError;
 
             fn from_str(s: &str) -> Result<Self, Self::Err> {
-                exact!(s.as_bytes(), $func).map(|(_, r)| r).map_err(|_| ())
+                crate::rfc5321::_exact(s.as_bytes(), $func)
             }
         }
-        impl <'a> std::convert::TryFrom<&'a [u8]> for $type {
+        impl <'a> core::convert::TryFrom<&'a [u8]> for $type {
             type Error = nom::Err<NomError<'a>>;
 
             fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
                 exact!(value, $func).map(|(_, v)| v)
             }
         }
-        impl <'a> std::convert::TryFrom<&'a str> for $type {
+        impl <'a> core::convert::TryFrom<&'a str> for $type {
             type Error = nom::Err<NomError<'a>>;
 
             fn try_from(value: &'a str) -> Result<Self, Self::Error> {
@@ -73,17 +206,17 @@ macro_rules! nom_from_imf {
 
 macro_rules! string_newtype {
     ( $type:ident ) => {
-        impl std::fmt::Display for $type {
-            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        impl core::fmt::Display for $type {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
                 write!(f, "{}", self.0)
             }
         }
-        impl std::convert::AsRef<[u8]> for $type {
+        impl core::convert::AsRef<[u8]> for $type {
             fn as_ref(&self) -> &[u8] {
                 self.0.as_bytes()
             }
         }
-        impl std::ops::Deref for $type {
+        impl core::ops::Deref for $type {
             type Target = str;
             fn deref(&self) -> &Self::Target {
                 &self.0
@@ -95,7 +228,7 @@ macro_rules! string_newtype {
             }
         }
 
-        impl std::fmt::Debug for $type {
+        impl core::fmt::Debug for $type {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 write!(f, "{:?}", self.0)
             }
@@ -103,6 +236,26 @@ macro_rules! string_newtype {
     }
 }
 
+// Serialize through Display and deserialize through TryFrom<&str> so
+// that only valid values can be deserialized.
+macro_rules! serde_string {
+    ( $type:ty ) => {
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $type {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $type {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+                <$type as core::convert::TryFrom<&str>>::try_from(value.as_str()).map_err(serde::de::Error::custom)
+            }
+        }
+    }
+}
+
 pub(crate) fn fold_prefix0<I, O, E, F, G>(mut prefix: F, mut cont: G) -> impl FnMut(I) -> IResult<I, Vec<O>, E>
     where I: Clone + PartialEq,
           F: FnMut(I) -> IResult<I, O, E>,
@@ -122,7 +275,7 @@ pub(crate) fn fold_prefix0<I, O, E, F, G>(mut prefix: F, mut cont: G) -> impl Fn
 }
 
 pub(crate) fn recognize_many0<I, O, E, F>(f: F) -> impl FnMut(I) -> IResult<I, I, E>
-    where I: Clone + PartialEq + nom::Slice<std::ops::RangeTo<usize>> + nom::Offset,
+    where I: Clone + PartialEq + nom::Slice<core::ops::RangeTo<usize>> + nom::Offset,
           F: FnMut(I) -> IResult<I, O, E>,
           E: nom::error::ParseError::<I>,
 {
@@ -130,7 +283,7 @@ pub(crate) fn recognize_many0<I, O, E, F>(f: F) -> impl FnMut(I) -> IResult<I, I
 }
 
 pub(crate) fn recognize_many1<I, O, E, F>(f: F) -> impl FnMut(I) -> IResult<I, I, E>
-    where I: Clone + PartialEq + nom::Slice<std::ops::RangeTo<usize>> + nom::Offset,
+    where I: Clone + PartialEq + nom::Slice<core::ops::RangeTo<usize>> + nom::Offset,
           F: FnMut(I) -> IResult<I, O, E>,
           E: nom::error::ParseError::<I>,
 {
@@ -0,0 +1,42 @@
+//! Postfix [XCLIENT] SMTP extension parser
+//!
+//! [XCLIENT]: http://www.postfix.org/XCLIENT_README.html
+
+use nom::bytes::complete::tag_no_case;
+use nom::multi::many1;
+use nom::sequence::{delimited, preceded};
+
+use crate::behaviour::Legacy;
+use crate::rfc5234::{crlf, wsp};
+use crate::rfc5321::{esmtp_param, Param};
+use crate::prelude::*;
+use crate::util::*;
+
+/// Value sent for an attribute that is not available.
+pub const UNAVAILABLE: &str = "[UNAVAILABLE]";
+
+/// Value sent for an attribute that is temporarily not available.
+pub const TEMPUNAVAIL: &str = "[TEMPUNAVAIL]";
+
+/// Parse a XCLIENT command.
+///
+/// Returns the attributes as ESMTP parameters such as `NAME` and
+/// `ADDR`. The values are returned as sent: they are xtext encoded
+/// unless they are [`UNAVAILABLE`] or [`TEMPUNAVAIL`]. Use
+/// [`crate::rfc3461::xtext_decode`] to decode them. The attribute
+/// names are not validated.
+/// # Examples
+/// ```
+/// use rustyknife::rfc5321::{get_value, Param};
+/// use rustyknife::xclient::{xclient_command, UNAVAILABLE};
+///
+/// let (_, attrs) = xclient_command(b"XCLIENT NAME=[UNAVAILABLE] ADDR=192.0.2.1\r\n").unwrap();
+///
+/// assert_eq!(get_value(&attrs, "NAME"), Some(UNAVAILABLE));
+/// assert_eq!(get_value(&attrs, "addr"), Some("192.0.2.1"));
+/// ```
+pub fn xclient_command(input: &[u8]) -> NomResult<Vec<Param>> {
+    delimited(tag_no_case("XCLIENT"),
+              many1(preceded(many1(wsp), esmtp_param::<Legacy>)),
+              crlf)(input)
+}
@@ -10,6 +10,7 @@ use nom::sequence::{delimited, preceded, separated_pair};
 
 use crate::rfc5234::{crlf, wsp};
 use crate::rfc3461::xtext;
+use crate::prelude::*;
 use crate::util::*;
 
 /// XFORWARD parameter name and value.